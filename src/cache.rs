@@ -0,0 +1,133 @@
+//! On-disk cache of fetched feeds for conditional requests
+//!
+//! Each feed URL gets one JSON file holding the validators the server sent
+//! (`ETag` and `Last-Modified`), the body and a hash of it. Later runs send
+//! the validators back, and a `304 Not Modified` answer is checked against
+//! the cached body instead of downloading the feed again. The hash tells
+//! when the content last changed, even for servers without validators.
+
+use crate::io::write_atomic;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What is remembered about a feed URL between runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// Hash of the body, see `content_hash`
+    pub content_hash: String,
+    /// When the feed was last fetched or confirmed unchanged
+    pub checked_at: DateTime<Utc>,
+    /// When the body was first seen with its current hash
+    pub changed_at: DateTime<Utc>,
+    pub body: String,
+}
+
+/// A directory of cache entries, one file per URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        HttpCache { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", content_hash(url)))
+    }
+
+    /// Returns the entry for a URL, if there is a readable one
+    pub fn load(&self, url: &str) -> Option<CacheEntry> {
+        let content = fs::read_to_string(self.path(url)).ok()?;
+        serde_json::from_str::<CacheEntry>(&content)
+            .ok()
+            .filter(|entry| entry.url == url)
+    }
+
+    /// Writes the entry for its URL, replacing any previous one
+    pub fn store(&self, entry: &CacheEntry) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_vec(entry).map_err(std::io::Error::other)?;
+        write_atomic(&self.path(&entry.url), &content, 0)
+    }
+
+    /// Records a fresh download of a URL
+    ///
+    /// # Arguments
+    /// * `previous` - The entry the request was made with, if any
+    /// * `url` - The URL that was fetched
+    /// * `etag` - The response's `ETag` header
+    /// * `last_modified` - The response's `Last-Modified` header
+    /// * `body` - The response body
+    ///
+    /// # Returns
+    /// * `CacheEntry` - The new entry, whose `changed_at` is kept from
+    ///   `previous` when the body hashes the same
+    pub fn update(
+        &self,
+        previous: Option<&CacheEntry>,
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: &str,
+    ) -> CacheEntry {
+        let now = Utc::now();
+        let hash = content_hash(body);
+        let changed_at = previous
+            .filter(|entry| entry.content_hash == hash)
+            .map_or(now, |entry| entry.changed_at);
+        let entry = CacheEntry {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            content_hash: hash,
+            checked_at: now,
+            changed_at,
+            body: body.to_string(),
+        };
+        // A cache that cannot be written only costs a download next time
+        let _ = self.store(&entry);
+        entry
+    }
+
+    /// Records that a URL was confirmed unchanged by a `304` answer
+    pub fn touch(&self, entry: &CacheEntry) -> CacheEntry {
+        let entry = CacheEntry {
+            checked_at: Utc::now(),
+            ..entry.clone()
+        };
+        let _ = self.store(&entry);
+        entry
+    }
+}
+
+/// Hashes content with 64-bit FNV-1a, which is stable across builds
+pub fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// The cache directory used when none is configured:
+/// `$XDG_CACHE_HOME/opml-manager/http`, or `~/.cache/opml-manager/http`
+pub fn default_cache_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("opml-manager").join("http"))
+}
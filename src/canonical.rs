@@ -0,0 +1,137 @@
+//! Canonical forms of feed URLs, used to decide when two feeds are the same
+//!
+//! Equivalences defined by the URL standard are always applied: the scheme
+//! and host are lowercased, international host names are converted to
+//! punycode, default ports are dropped and fragments are ignored. Paths keep
+//! their case, since most servers treat them case-sensitively. Everything
+//! else is a `UrlRule` that can be switched off.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use url::Url;
+
+/// Query parameters that only track where a visitor came from
+const TRACKING_PARAMS: [&str; 9] = [
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_ga",
+];
+
+/// An optional rule applied when canonicalizing URLs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlRule {
+    /// Treat http and https as the same
+    Scheme,
+    /// Treat `www.example.com` and `example.com` as the same
+    Www,
+    /// Ignore a trailing slash at the end of the path
+    TrailingSlash,
+    /// Ignore the order of query parameters
+    SortQuery,
+    /// Drop `utm_*` and other tracking parameters
+    Tracking,
+}
+
+impl UrlRule {
+    /// Every rule, applied when none are selected explicitly
+    pub fn all() -> &'static [UrlRule] {
+        &[
+            UrlRule::Scheme,
+            UrlRule::Www,
+            UrlRule::TrailingSlash,
+            UrlRule::SortQuery,
+            UrlRule::Tracking,
+        ]
+    }
+}
+
+/// Reduces URLs to a canonical form according to a set of rules
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canonicalizer {
+    rules: Vec<UrlRule>,
+}
+
+impl Default for Canonicalizer {
+    fn default() -> Self {
+        Canonicalizer::new(UrlRule::all())
+    }
+}
+
+impl Canonicalizer {
+    pub fn new(rules: &[UrlRule]) -> Self {
+        Canonicalizer {
+            rules: rules.to_vec(),
+        }
+    }
+
+    fn applies(&self, rule: UrlRule) -> bool {
+        self.rules.contains(&rule)
+    }
+
+    /// Returns the canonical form of a URL
+    ///
+    /// # Arguments
+    /// * `url` - The URL to canonicalize
+    ///
+    /// # Returns
+    /// * `String` - The canonical form; input that is not an http(s) URL is
+    ///   only trimmed
+    pub fn canonicalize(&self, url: &str) -> String {
+        let url = url.trim();
+        let mut parsed = match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+            _ => return url.to_string(),
+        };
+
+        parsed.set_fragment(None);
+
+        if self.applies(UrlRule::Scheme) && parsed.scheme() == "http" {
+            let _ = parsed.set_scheme("https");
+        }
+
+        if self.applies(UrlRule::Www) {
+            if let Some(host) = parsed.host_str().and_then(|h| h.strip_prefix("www.")) {
+                let host = host.to_string();
+                let _ = parsed.set_host(Some(&host));
+            }
+        }
+
+        if parsed.query().is_some()
+            && (self.applies(UrlRule::Tracking) || self.applies(UrlRule::SortQuery))
+        {
+            let mut pairs: Vec<(String, String)> = parsed
+                .query_pairs()
+                .into_owned()
+                .filter(|(name, _)| !self.applies(UrlRule::Tracking) || !is_tracking_param(name))
+                .collect();
+            if self.applies(UrlRule::SortQuery) {
+                pairs.sort();
+            }
+            if pairs.is_empty() {
+                parsed.set_query(None);
+            } else {
+                parsed.query_pairs_mut().clear().extend_pairs(pairs);
+            }
+        }
+
+        if self.applies(UrlRule::TrailingSlash) && parsed.path().len() > 1 {
+            let path = parsed.path().trim_end_matches('/').to_string();
+            parsed.set_path(&path);
+        }
+
+        parsed.into()
+    }
+
+    /// Returns true if two URLs have the same canonical form
+    pub fn same(&self, a: &str, b: &str) -> bool {
+        self.canonicalize(a) == self.canonicalize(b)
+    }
+}
+
+fn is_tracking_param(name: &str) -> bool {
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+/// Canonicalizes a URL with every rule applied
+pub fn canonicalize(url: &str) -> String {
+    Canonicalizer::default().canonicalize(url)
+}
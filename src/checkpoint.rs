@@ -0,0 +1,166 @@
+//! Checkpoints that let an interrupted validation run be resumed
+//!
+//! While `validate` runs, each result is appended to a checkpoint file as
+//! one JSON line, so the progress survives an interruption. A run started
+//! with `--resume` takes the results of feeds already checked from the
+//! checkpoint and validates only the rest. Feeds that were cancelled or
+//! failed with an internal error are not recorded, so they are tried again.
+//! A checkpoint stays on disk until it is explicitly removed, so a run that
+//! fails part way can still be resumed.
+
+use crate::validation::{ValidationResult, ValidationStatus};
+use crate::{Feed, Result};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A checkpoint file being written; kept on disk until removed
+pub struct Checkpoint {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl Checkpoint {
+    /// The checkpoint used for an input file when none is given
+    pub fn default_path(input_file: &str) -> PathBuf {
+        PathBuf::from(format!("{}.checkpoint", input_file))
+    }
+
+    /// Starts a checkpoint, replacing any previous one with `results`
+    ///
+    /// # Arguments
+    /// * `path` - Where the checkpoint is kept
+    /// * `results` - Results carried over from the run being resumed
+    ///
+    /// # Returns
+    /// * `Result<Checkpoint>` - The checkpoint, ready to record further results
+    pub fn create(path: impl Into<PathBuf>, results: &[ValidationResult]) -> Result<Self> {
+        let path = path.into();
+        let file = File::create(&path)?;
+        let checkpoint = Checkpoint {
+            path,
+            file: Mutex::new(file),
+        };
+        for result in results {
+            checkpoint.record(result)?;
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a result, unless the feed still needs checking
+    pub fn record(&self, result: &ValidationResult) -> Result<()> {
+        if !is_checked(result) {
+            return Ok(());
+        }
+        let mut line = serde_json::to_string(result).map_err(std::io::Error::other)?;
+        line.push('\n');
+        // One write per line keeps lines whole even if the process dies
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Deletes the checkpoint once the run it belongs to is complete
+    pub fn remove(self) -> Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path)?;
+        Ok(())
+    }
+
+    /// Reads the results recorded in a checkpoint
+    ///
+    /// A line cut short by an interruption is skipped.
+    ///
+    /// # Returns
+    /// * `Result<Vec<ValidationResult>>` - The results, or none if there is
+    ///   no checkpoint at `path`
+    pub fn load(path: &Path) -> Result<Vec<ValidationResult>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut results = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(result) = serde_json::from_str::<ValidationResult>(&line?) {
+                results.push(result);
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Returns true if a result settles its feed, so a resumed run can skip it
+fn is_checked(result: &ValidationResult) -> bool {
+    !matches!(
+        result.status,
+        ValidationStatus::Cancelled | ValidationStatus::InternalError
+    )
+}
+
+/// Feeds split into those a checkpoint already has results for and the rest
+pub struct Resumed {
+    /// One slot per input feed, filled for feeds checked before
+    slots: Vec<Option<ValidationResult>>,
+    /// Feeds that still need validating, in input order
+    pub pending: Vec<Feed>,
+}
+
+impl Resumed {
+    /// Matches checkpointed results to the feeds of this run
+    ///
+    /// # Arguments
+    /// * `feeds` - The feeds to validate
+    /// * `previous` - Results loaded from the checkpoint
+    pub fn new(feeds: &[Feed], previous: Vec<ValidationResult>) -> Self {
+        let mut by_url: HashMap<String, Vec<ValidationResult>> = HashMap::new();
+        for result in previous.into_iter().filter(is_checked) {
+            by_url.entry(result.url.clone()).or_default().push(result);
+        }
+
+        let mut slots = Vec::with_capacity(feeds.len());
+        let mut pending = Vec::new();
+        for feed in feeds {
+            let candidates = by_url.get_mut(&feed.xml_url);
+            let found = candidates.and_then(|candidates| {
+                let index = candidates.iter().position(|result| result.is_for(feed))?;
+                Some(candidates.swap_remove(index))
+            });
+            match found {
+                Some(result) => slots.push(Some(result)),
+                None => {
+                    slots.push(None);
+                    pending.push(feed.clone());
+                }
+            }
+        }
+        Resumed { slots, pending }
+    }
+
+    /// Results carried over from the checkpoint
+    pub fn carried_over(&self) -> Vec<ValidationResult> {
+        self.slots.iter().flatten().cloned().collect()
+    }
+
+    /// Combines carried over results with the results for `pending`
+    ///
+    /// # Arguments
+    /// * `results` - One result per pending feed, in the same order
+    ///
+    /// # Returns
+    /// * `Vec<ValidationResult>` - One result per input feed, in input order
+    pub fn merge(self, results: Vec<ValidationResult>) -> Vec<ValidationResult> {
+        let mut results = results.into_iter();
+        self.slots
+            .into_iter()
+            .filter_map(|slot| slot.or_else(|| results.next()))
+            .collect()
+    }
+}
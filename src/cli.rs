@@ -1,45 +1,401 @@
-use clap::{Parser, Subcommand};
+use crate::canonical::UrlRule;
+use crate::config::Settings;
+use crate::duplicates::DEFAULT_MIN_CONFIDENCE;
+use crate::filter::Filter;
+use crate::lint::Severity;
+use crate::prune::PruneReason;
+use crate::redirects::UrlSource;
+use crate::report::ReportSection;
+use crate::validation::IssueKind;
+use chrono::TimeDelta;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(author, version, about)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+    /// Output format for analyze, validate and lint-feeds
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<OutputFormat>,
+    /// Write analyze, validate and lint-feeds output to this path (- for stdout) instead of the default
+    #[arg(long, short = 'o', global = true, value_name = "PATH")]
+    pub output: Option<String>,
+    /// Timeout in seconds for feed validation [default: 10]
+    #[arg(long, global = true)]
+    pub timeout: Option<u64>,
+    /// User-Agent header sent when fetching feeds
+    #[arg(long, global = true, value_name = "AGENT")]
+    pub user_agent: Option<String>,
+    /// Maximum number of feeds fetched at once, 0 for unlimited [default: 16]
+    #[arg(long, global = true, value_name = "N")]
+    pub concurrency: Option<usize>,
+    /// Maximum number of requests in flight to one host, 0 for unlimited [default: 2]
+    #[arg(long, global = true, value_name = "N")]
+    pub per_host: Option<usize>,
+    /// Minimum delay in milliseconds between requests to one host [default: 250]
+    #[arg(long, global = true, value_name = "MS")]
+    pub host_delay: Option<u64>,
+    /// Attempts per feed request including the first, 1 to disable retries [default: 5]
+    #[arg(long, global = true, value_name = "N")]
+    pub retry_attempts: Option<u32>,
+    /// Delay in milliseconds before the first retry, doubled for each further retry [default: 1000]
+    #[arg(long, global = true, value_name = "MS")]
+    pub retry_delay: Option<u64>,
+    /// Longest delay in milliseconds between attempts, including Retry-After waits [default: 30000]
+    #[arg(long, global = true, value_name = "MS")]
+    pub retry_max_delay: Option<u64>,
+    /// Share of each retry delay that is randomized, between 0 and 1 [default: 0.1]
+    #[arg(long, global = true, value_name = "FRACTION")]
+    pub retry_jitter: Option<f64>,
+    /// Failures that are retried, separated by commas [default: dns,tls,connect,rate-limited,server-error,body-read]
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "ISSUES"
+    )]
+    pub retry_on: Option<Vec<IssueKind>>,
+    /// Download every feed in full instead of sending conditional requests
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+    /// Directory of the HTTP cache [default: ~/.cache/opml-manager/http]
+    #[arg(long, global = true, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+    /// Record every feed response into this directory, to replay later with --replay
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+    /// Answer feed requests from responses recorded with --record instead of the network
+    #[arg(long, global = true, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+    /// Report feeds whose newest item is older than this as stale, e.g. 365d, 0 to disable [default: 365d]; passing it on the command line enables the stale reason of prune
+    #[arg(long, global = true, value_name = "AGE", value_parser = parse_age)]
+    pub stale_after: Option<TimeDelta>,
+    /// Largest response body read per feed, e.g. 20MB or 512KB, 0 for no limit [default: 20MB]
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_size)]
+    pub max_body_size: Option<u64>,
+    /// Configuration profile to apply
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// Rules deciding when two feed URLs are the same, separated by commas [default: all]
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_delimiter = ',',
+        value_name = "RULES"
+    )]
+    pub url_rules: Option<Vec<UrlRule>>,
+}
+
+impl Cli {
+    /// Returns the settings given explicitly as command-line flags
+    pub fn settings(&self) -> Settings {
+        Settings {
+            timeout: self.timeout,
+            user_agent: self.user_agent.clone(),
+            concurrency: self.concurrency,
+            per_host: self.per_host,
+            host_delay: self.host_delay,
+            retry_attempts: self.retry_attempts,
+            retry_delay: self.retry_delay,
+            retry_max_delay: self.retry_max_delay,
+            retry_jitter: self.retry_jitter,
+            retry_on: self.retry_on.clone(),
+            cache: self.no_cache.then_some(false),
+            cache_dir: self.cache_dir.clone(),
+            stale_after: self.stale_after,
+            max_body_size: self.max_body_size,
+            format: self.format,
+            report_sections: match &self.command {
+                Commands::Report { sections, .. } => sections.clone(),
+                _ => None,
+            },
+            url_rules: self.url_rules.clone(),
+        }
+    }
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     /// Analyze OPML file for duplicates and potential issues
     Analyze {
-        /// Input OPML file path
+        /// Input OPML file path, or - for stdin
         input_file: String,
+        #[command(flatten)]
+        content: ContentArgs,
+    },
+    /// Print the category hierarchy with feed counts
+    Tree {
+        /// Input OPML file path, or - for stdin
+        input_file: String,
+        /// Show individual feeds under their categories
+        #[arg(long)]
+        feeds: bool,
+        /// Maximum category depth to display
+        #[arg(long)]
+        depth: Option<usize>,
+        /// When to use colored output
+        #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+        color: ColorChoice,
+        /// Validate feeds and mark each one with its status (implies --feeds)
+        #[arg(long)]
+        validate: bool,
     },
     /// Remove duplicate feeds while preserving categories
     Dedupe {
-        /// Input OPML file path
+        /// Input OPML file path, or - for stdin
         input_file: String,
-        /// Output OPML file path
-        output_file: String,
+        /// Output OPML file path, or - for stdout
+        #[arg(required_unless_present = "in_place")]
+        output_file: Option<String>,
+        #[command(flatten)]
+        content: ContentArgs,
+        #[command(flatten)]
+        write: WriteArgs,
     },
     /// Validate feeds and check for issues
     Validate {
-        /// Input OPML file path
+        /// Input OPML file path, or - for stdin
+        input_file: String,
+        /// Only validate feeds matching this filter expression
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Option<Filter>,
+        /// Skip feeds already checked by an interrupted run, as recorded in the checkpoint
+        #[arg(long)]
+        resume: bool,
+        /// Checkpoint file recording results as they arrive [default: <INPUT_FILE>.checkpoint]
+        #[arg(long, value_name = "PATH")]
+        checkpoint: Option<PathBuf>,
+    },
+    /// Check feeds for problems readers trip over, such as missing GUIDs or bad dates
+    LintFeeds {
+        /// Input OPML file path, or - for stdin
         input_file: String,
-        /// Timeout in seconds for feed validation
-        #[arg(long, default_value = "10")]
-        timeout: u64,
+        /// Only lint feeds matching this filter expression
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Option<Filter>,
+        /// Leave out warnings less severe than this
+        #[arg(long, value_enum, default_value_t = Severity::Info)]
+        min_severity: Severity,
     },
     /// Generate a detailed report about the OPML file
     Report {
-        /// Input OPML file path
+        /// Input OPML file path, or - for stdin
         input_file: String,
-        /// Output report file path
+        /// Output report file path, or - for stdout
         output_file: String,
         /// Include feed validation in report
         #[arg(long)]
         validate_feeds: bool,
-        /// Timeout in seconds for feed validation
-        #[arg(long, default_value = "10")]
-        timeout: u64,
+        /// Include lint warnings in report (implies --validate-feeds)
+        #[arg(long)]
+        lint_feeds: bool,
+        /// Only report on feeds matching this filter expression
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Option<Filter>,
+        /// Report sections to include, separated by commas
+        #[arg(long, value_enum, value_delimiter = ',')]
+        sections: Option<Vec<ReportSection>>,
+    },
+    /// Remove dead feeds based on validation results
+    Prune {
+        /// Input OPML file path, or - for stdin
+        input_file: String,
+        /// Output OPML file path, or - for stdout
+        #[arg(required_unless_present_any = ["in_place", "dry_run"])]
+        output_file: Option<String>,
+        /// Reasons for which feeds are removed, separated by commas
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = PruneReason::defaults())]
+        reasons: Vec<PruneReason>,
+        /// Use results saved by `validate --format json|ndjson` instead of validating again
+        #[arg(long, value_name = "PATH")]
+        results: Option<String>,
+        /// Only remove feeds that failed this many consecutive runs
+        #[arg(long, value_name = "RUNS", default_value_t = 1)]
+        grace: u32,
+        /// File tracking consecutive failures between runs [default: <input>.prune-history.json]
+        #[arg(long, value_name = "PATH")]
+        history: Option<String>,
+        /// Write the list of removed feeds and reasons to this path
+        #[arg(long, value_name = "PATH")]
+        log: Option<String>,
+        /// Show what would be removed without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Update the URLs of feeds that have permanently moved
+    FixRedirects {
+        /// Input OPML file path, or - for stdin
+        input_file: String,
+        /// Output OPML file path, or - for stdout
+        #[arg(required_unless_present_any = ["in_place", "dry_run"])]
+        output_file: Option<String>,
+        /// Where new URLs are taken from, separated by commas
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = UrlSource::all())]
+        sources: Vec<UrlSource>,
+        /// Use results saved by `validate --format json|ndjson` instead of validating again
+        #[arg(long, value_name = "PATH")]
+        results: Option<String>,
+        /// Write the list of changed URLs to this path
+        #[arg(long, value_name = "PATH")]
+        log: Option<String>,
+        /// Show which URLs would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Inspect the effective configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Select feeds matching a filter expression
+    #[command(alias = "search")]
+    Filter {
+        /// Input OPML file path, or - for stdin
+        input_file: String,
+        /// Filter expression, e.g. 'category = "Tech/*" and title ~ /rust/i'
+        expression: Filter,
+        /// Output OPML file path; matching feeds are listed if omitted
+        output_file: Option<String>,
+    },
+    /// Remove feeds matching a filter expression
+    Remove {
+        /// Input OPML file path, or - for stdin
+        input_file: String,
+        /// Output OPML file path, or - for stdout
+        #[arg(required_unless_present = "in_place")]
+        output_file: Option<String>,
+        /// Feeds matching this filter expression are removed
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Filter,
+        #[command(flatten)]
+        write: WriteArgs,
+    },
+    /// Move feeds matching a filter expression into another category
+    Move {
+        /// Input OPML file path, or - for stdin
+        input_file: String,
+        /// Output OPML file path, or - for stdout
+        #[arg(required_unless_present = "in_place")]
+        output_file: Option<String>,
+        /// Feeds matching this filter expression are moved
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Filter,
+        /// Destination category path, with levels separated by '/'
+        #[arg(long, value_name = "CATEGORY")]
+        to: String,
+        #[command(flatten)]
+        write: WriteArgs,
     },
 }
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the effective settings and where each came from
+    Show,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color output when writing to a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// A Markdown report
+    Markdown,
+}
+
+/// Options shared by every subcommand that rewrites an OPML file
+#[derive(Args, Clone, Debug, Default)]
+pub struct WriteArgs {
+    /// Overwrite the input file instead of writing to an output path
+    #[arg(long, conflicts_with = "output_file")]
+    pub in_place: bool,
+    /// Keep up to N rotated .bak copies of the file being replaced
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "1")]
+    pub backup: Option<usize>,
+}
+
+/// Options for detecting duplicates by fetching feed content
+#[derive(Args, Clone, Debug, Default)]
+pub struct ContentArgs {
+    /// Also fetch feeds to find duplicates served under different URLs
+    #[arg(long)]
+    pub content: bool,
+    /// Confidence between 0 and 1 from which feeds count as content duplicates
+    #[arg(long, value_name = "SCORE", default_value_t = DEFAULT_MIN_CONFIDENCE, requires = "content")]
+    pub min_confidence: f64,
+}
+
+/// Parses a size such as `20MB`, `512KB` or `1GB`; a bare number means bytes
+///
+/// Units are powers of 1024 and case-insensitive.
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (number, unit) = match input.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => input.split_at(index),
+        None => (input, "b"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}'", input))?;
+    let factor: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        "g" | "gb" => 1024 * 1024 * 1024,
+        _ => {
+            return Err(format!(
+                "invalid size unit '{}', expected B, KB, MB or GB",
+                unit.trim()
+            ))
+        }
+    };
+    number
+        .checked_mul(factor)
+        .ok_or_else(|| format!("size '{}' is too large", input))
+}
+
+/// Parses an age such as `365d`, `8w` or `12h`; a bare number means days
+pub fn parse_age(input: &str) -> Result<TimeDelta, String> {
+    let input = input.trim();
+    let (number, unit) = match input.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => input.split_at(index),
+        None => (input, "d"),
+    };
+    let number: i64 = number
+        .parse()
+        .map_err(|_| format!("invalid age '{}'", input))?;
+    let age = match unit {
+        "h" => TimeDelta::try_hours(number),
+        "d" => TimeDelta::try_days(number),
+        "w" => TimeDelta::try_weeks(number),
+        "y" => number.checked_mul(365).and_then(TimeDelta::try_days),
+        _ => {
+            return Err(format!(
+                "invalid age unit '{}', expected h, d, w or y",
+                unit
+            ))
+        }
+    };
+    age.ok_or_else(|| format!("age '{}' is too large", input))
+}
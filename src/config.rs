@@ -0,0 +1,678 @@
+//! Layered configuration for default options
+//!
+//! Settings are resolved from, in increasing order of precedence:
+//! 1. built-in defaults
+//! 2. the user config file, `$XDG_CONFIG_HOME/opml-manager/config.toml`
+//! 3. the project config file, `.opml-manager.toml` in the current directory
+//! 4. `OPML_MANAGER_*` environment variables
+//! 5. command-line flags
+//!
+//! Each config file may define named profiles under `[profiles.<name>]`.
+//! When a profile is selected, its settings override the top-level settings
+//! of the same file.
+//!
+//! ```toml
+//! timeout = 20
+//! user-agent = "my-reader/1.0"
+//! per-host = 1
+//! host-delay = 1000
+//! retry-attempts = 3
+//! cache-dir = "/var/cache/opml-manager"
+//! stale-after = "3y"
+//! max-body-size = "5MB"
+//! retry-on = ["connect", "rate-limited", "server-error"]
+//!
+//! [profiles.ci]
+//! timeout = 5
+//! format = "json"
+//! report-sections = ["summary", "duplicates"]
+//! url-rules = ["scheme", "www"]
+//! ```
+
+use crate::cache::{default_cache_dir, HttpCache};
+use crate::canonical::{Canonicalizer, UrlRule};
+use crate::cli::{parse_age, parse_size, OutputFormat};
+use crate::error::{OPMLError, Result};
+use crate::report::ReportSection;
+use crate::retry::{
+    RetryPolicy, DEFAULT_BASE_DELAY_MS, DEFAULT_JITTER, DEFAULT_MAX_ATTEMPTS, DEFAULT_MAX_DELAY_MS,
+};
+use crate::scheduler::{Limits, DEFAULT_CONCURRENCY, DEFAULT_HOST_DELAY_MS, DEFAULT_PER_HOST};
+use crate::validation::{IssueKind, DEFAULT_MAX_BODY_SIZE, DEFAULT_TIMEOUT_SECS};
+use chrono::TimeDelta;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Name of the project-local config file
+pub const PROJECT_CONFIG_FILE: &str = ".opml-manager.toml";
+
+/// Prefix for environment variables overriding settings
+pub const ENV_PREFIX: &str = "OPML_MANAGER_";
+
+/// One layer of settings; unset fields fall through to lower layers
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    pub timeout: Option<u64>,
+    pub user_agent: Option<String>,
+    pub concurrency: Option<usize>,
+    pub per_host: Option<usize>,
+    pub host_delay: Option<u64>,
+    pub retry_attempts: Option<u32>,
+    pub retry_delay: Option<u64>,
+    pub retry_max_delay: Option<u64>,
+    pub retry_jitter: Option<f64>,
+    pub retry_on: Option<Vec<IssueKind>>,
+    pub cache: Option<bool>,
+    pub cache_dir: Option<PathBuf>,
+    #[serde(deserialize_with = "deserialize_age")]
+    pub stale_after: Option<TimeDelta>,
+    #[serde(deserialize_with = "deserialize_size")]
+    pub max_body_size: Option<u64>,
+    pub format: Option<OutputFormat>,
+    pub report_sections: Option<Vec<ReportSection>>,
+    pub url_rules: Option<Vec<UrlRule>>,
+}
+
+/// A parsed config file with its top-level settings and profiles
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub settings: Settings,
+    pub profiles: BTreeMap<String, Settings>,
+}
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Profile(String, PathBuf),
+    Env(String),
+    Cli,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Profile(name, path) => write!(f, "{} [profiles.{}]", path.display(), name),
+            Source::Env(var) => write!(f, "environment ${}", var),
+            Source::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// An effective setting value and its origin
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn default(value: T) -> Self {
+        Setting {
+            value,
+            source: Source::Default,
+        }
+    }
+
+    fn update(&mut self, value: Option<T>, source: &Source) {
+        if let Some(value) = value {
+            self.value = value;
+            self.source = source.clone();
+        }
+    }
+}
+
+/// The effective configuration after all layers are applied
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Timeout in seconds for each feed request
+    pub timeout: Setting<u64>,
+    /// User-Agent header sent with feed requests
+    pub user_agent: Setting<String>,
+    /// Maximum number of feeds validated at once; 0 means unlimited
+    pub concurrency: Setting<usize>,
+    /// Maximum number of requests in flight to one host; 0 means unlimited
+    pub per_host: Setting<usize>,
+    /// Minimum time in milliseconds between requests to one host
+    pub host_delay: Setting<u64>,
+    /// Attempts per feed request, including the first
+    pub retry_attempts: Setting<u32>,
+    /// Delay in milliseconds before the first retry
+    pub retry_delay: Setting<u64>,
+    /// Longest delay in milliseconds between attempts
+    pub retry_max_delay: Setting<u64>,
+    /// Share of each retry delay that is randomized
+    pub retry_jitter: Setting<f64>,
+    /// Failures that are retried
+    pub retry_on: Setting<Vec<IssueKind>>,
+    /// Whether fetched feeds are cached for conditional requests
+    pub cache: Setting<bool>,
+    /// Directory of the HTTP cache; `None` if no default location is known
+    pub cache_dir: Setting<Option<PathBuf>>,
+    /// Age of the newest item beyond which a feed is stale; zero disables the check
+    pub stale_after: Setting<TimeDelta>,
+    /// Largest response body read per feed, in bytes; zero disables the limit
+    pub max_body_size: Setting<u64>,
+    /// Default output format; `None` leaves the choice to each command
+    pub format: Setting<Option<OutputFormat>>,
+    /// Sections included in Markdown reports
+    pub report_sections: Setting<Vec<ReportSection>>,
+    /// Rules deciding when two feed URLs are the same
+    pub url_rules: Setting<Vec<UrlRule>>,
+    /// The selected profile, if any
+    pub profile: Option<String>,
+    /// Config files that were found and applied, lowest precedence first
+    pub files: Vec<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            timeout: Setting::default(DEFAULT_TIMEOUT_SECS),
+            user_agent: Setting::default(default_user_agent()),
+            concurrency: Setting::default(DEFAULT_CONCURRENCY),
+            per_host: Setting::default(DEFAULT_PER_HOST),
+            host_delay: Setting::default(DEFAULT_HOST_DELAY_MS),
+            retry_attempts: Setting::default(DEFAULT_MAX_ATTEMPTS),
+            retry_delay: Setting::default(DEFAULT_BASE_DELAY_MS),
+            retry_max_delay: Setting::default(DEFAULT_MAX_DELAY_MS),
+            retry_jitter: Setting::default(DEFAULT_JITTER),
+            retry_on: Setting::default(RetryPolicy::default_retry_on().to_vec()),
+            cache: Setting::default(true),
+            cache_dir: Setting::default(default_cache_dir()),
+            stale_after: Setting::default(TimeDelta::days(DEFAULT_STALE_AFTER_DAYS)),
+            max_body_size: Setting::default(DEFAULT_MAX_BODY_SIZE),
+            format: Setting::default(None),
+            report_sections: Setting::default(ReportSection::all().to_vec()),
+            url_rules: Setting::default(UrlRule::all().to_vec()),
+            profile: None,
+            files: Vec::new(),
+        }
+    }
+}
+
+/// Days without new items after which a feed is stale, unless configured otherwise
+pub const DEFAULT_STALE_AFTER_DAYS: i64 = 365;
+
+fn deserialize_age<'de, D>(deserializer: D) -> std::result::Result<Option<TimeDelta>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let age = String::deserialize(deserializer)?;
+    parse_age(&age).map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Formats an age the way `parse_age` reads it
+fn format_age(age: TimeDelta) -> String {
+    if age.num_hours() % 24 == 0 {
+        format!("{}d", age.num_days())
+    } else {
+        format!("{}h", age.num_hours())
+    }
+}
+
+fn deserialize_size<'de, D>(deserializer: D) -> std::result::Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let size = String::deserialize(deserializer)?;
+    parse_size(&size)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+/// Formats a size the way `parse_size` reads it, in the largest exact unit
+fn format_size(size: u64) -> String {
+    let units = [("GB", 1 << 30), ("MB", 1 << 20), ("KB", 1 << 10)];
+    for (unit, factor) in units {
+        if size > 0 && size.is_multiple_of(factor) {
+            return format!("{}{}", size / factor, unit);
+        }
+    }
+    size.to_string()
+}
+
+/// The User-Agent sent when none is configured
+pub fn default_user_agent() -> String {
+    format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+}
+
+impl ConfigFile {
+    /// Reads and parses a config file
+    pub fn read(path: &Path) -> Result<ConfigFile> {
+        let content = fs::read_to_string(path)?;
+        ConfigFile::parse(path, &content)
+    }
+
+    /// Parses config file content; `path` is only used for messages and provenance
+    pub fn parse(path: &Path, content: &str) -> Result<ConfigFile> {
+        let invalid =
+            |e: &dyn fmt::Display| OPMLError::Config(format!("{}: {}", path.display(), e));
+
+        let mut table: toml::Table = content.parse().map_err(|e| invalid(&e))?;
+        let profiles = match table.remove("profiles") {
+            Some(profiles) => profiles
+                .try_into::<BTreeMap<String, Settings>>()
+                .map_err(|e| invalid(&e))?,
+            None => BTreeMap::new(),
+        };
+        let settings = table.try_into::<Settings>().map_err(|e| invalid(&e))?;
+
+        Ok(ConfigFile {
+            path: path.to_path_buf(),
+            settings,
+            profiles,
+        })
+    }
+}
+
+impl Settings {
+    /// Reads settings from `OPML_MANAGER_*` variables
+    ///
+    /// # Arguments
+    /// * `vars` - Environment variables, usually `std::env::vars()`
+    ///
+    /// # Returns
+    /// * `Result<Settings>` - The settings, or `OPMLError::Config` for unparsable values
+    pub fn from_env(vars: impl IntoIterator<Item = (String, String)>) -> Result<Settings> {
+        let mut settings = Settings::default();
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let invalid = |e: &dyn fmt::Display| OPMLError::Config(format!("${}: {}", name, e));
+            match key {
+                "TIMEOUT" => settings.timeout = Some(value.parse().map_err(|e| invalid(&e))?),
+                "USER_AGENT" => settings.user_agent = Some(value),
+                "CONCURRENCY" => {
+                    settings.concurrency = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "PER_HOST" => settings.per_host = Some(value.parse().map_err(|e| invalid(&e))?),
+                "HOST_DELAY" => settings.host_delay = Some(value.parse().map_err(|e| invalid(&e))?),
+                "RETRY_ATTEMPTS" => {
+                    settings.retry_attempts = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "RETRY_DELAY" => {
+                    settings.retry_delay = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "RETRY_MAX_DELAY" => {
+                    settings.retry_max_delay = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "RETRY_JITTER" => {
+                    settings.retry_jitter = Some(value.parse().map_err(|e| invalid(&e))?)
+                }
+                "RETRY_ON" => {
+                    settings.retry_on = Some(
+                        value
+                            .split(',')
+                            .filter(|s| !s.trim().is_empty())
+                            .map(|s| IssueKind::from_str(s.trim(), true))
+                            .collect::<std::result::Result<_, _>>()
+                            .map_err(|e| invalid(&e))?,
+                    )
+                }
+                "CACHE" => settings.cache = Some(value.parse().map_err(|e| invalid(&e))?),
+                "CACHE_DIR" => settings.cache_dir = Some(PathBuf::from(value)),
+                "STALE_AFTER" => {
+                    settings.stale_after = Some(parse_age(&value).map_err(|e| invalid(&e))?)
+                }
+                "MAX_BODY_SIZE" => {
+                    settings.max_body_size = Some(parse_size(&value).map_err(|e| invalid(&e))?)
+                }
+                "FORMAT" => {
+                    settings.format =
+                        Some(OutputFormat::from_str(&value, true).map_err(|e| invalid(&e))?)
+                }
+                "REPORT_SECTIONS" => {
+                    settings.report_sections = Some(
+                        value
+                            .split(',')
+                            .map(|s| ReportSection::from_str(s.trim(), true))
+                            .collect::<std::result::Result<_, _>>()
+                            .map_err(|e| invalid(&e))?,
+                    )
+                }
+                "URL_RULES" => {
+                    settings.url_rules = Some(
+                        value
+                            .split(',')
+                            .filter(|s| !s.trim().is_empty())
+                            .map(|s| UrlRule::from_str(s.trim(), true))
+                            .collect::<std::result::Result<_, _>>()
+                            .map_err(|e| invalid(&e))?,
+                    )
+                }
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the standard file locations and the environment
+    ///
+    /// # Arguments
+    /// * `profile` - The profile selected on the command line, if any;
+    ///   `OPML_MANAGER_PROFILE` is used otherwise
+    /// * `cli` - Settings given as command-line flags
+    ///
+    /// # Returns
+    /// * `Result<Config>` - The effective configuration
+    pub fn load(profile: Option<&str>, cli: &Settings) -> Result<Config> {
+        let mut files = Vec::new();
+        for path in config_paths() {
+            if path.is_file() {
+                files.push(ConfigFile::read(&path)?);
+            }
+        }
+
+        let env_profile = std::env::var(format!("{}PROFILE", ENV_PREFIX)).ok();
+        let profile = profile.or(env_profile.as_deref());
+        let env = Settings::from_env(std::env::vars())?;
+        Config::resolve(&files, &env, cli, profile)
+    }
+
+    /// Applies config files, environment and command-line settings over the defaults
+    pub fn resolve(
+        files: &[ConfigFile],
+        env: &Settings,
+        cli: &Settings,
+        profile: Option<&str>,
+    ) -> Result<Config> {
+        let mut config = Config {
+            profile: profile.map(String::from),
+            ..Config::default()
+        };
+
+        let mut profile_found = false;
+        for file in files {
+            config.apply(&file.settings, &Source::File(file.path.clone()));
+            if let Some(name) = profile {
+                if let Some(settings) = file.profiles.get(name) {
+                    profile_found = true;
+                    config.apply(
+                        settings,
+                        &Source::Profile(name.to_string(), file.path.clone()),
+                    );
+                }
+            }
+            config.files.push(file.path.clone());
+        }
+
+        if let Some(name) = profile {
+            if !profile_found {
+                return Err(OPMLError::Config(format!("unknown profile '{}'", name)));
+            }
+        }
+
+        config.apply_env(env);
+        config.apply(cli, &Source::Cli);
+        Ok(config)
+    }
+
+    fn apply(&mut self, settings: &Settings, source: &Source) {
+        self.timeout.update(settings.timeout, source);
+        self.user_agent.update(settings.user_agent.clone(), source);
+        self.concurrency.update(settings.concurrency, source);
+        self.per_host.update(settings.per_host, source);
+        self.host_delay.update(settings.host_delay, source);
+        self.retry_attempts.update(settings.retry_attempts, source);
+        self.retry_delay.update(settings.retry_delay, source);
+        self.retry_max_delay
+            .update(settings.retry_max_delay, source);
+        self.retry_jitter.update(settings.retry_jitter, source);
+        self.retry_on.update(settings.retry_on.clone(), source);
+        self.cache.update(settings.cache, source);
+        self.cache_dir
+            .update(settings.cache_dir.clone().map(Some), source);
+        self.stale_after.update(settings.stale_after, source);
+        self.max_body_size.update(settings.max_body_size, source);
+        self.format.update(settings.format.map(Some), source);
+        self.report_sections
+            .update(settings.report_sections.clone(), source);
+        self.url_rules.update(settings.url_rules.clone(), source);
+    }
+
+    fn apply_env(&mut self, env: &Settings) {
+        let source = |key: &str| Source::Env(format!("{}{}", ENV_PREFIX, key));
+        self.timeout.update(env.timeout, &source("TIMEOUT"));
+        self.user_agent
+            .update(env.user_agent.clone(), &source("USER_AGENT"));
+        self.concurrency
+            .update(env.concurrency, &source("CONCURRENCY"));
+        self.per_host.update(env.per_host, &source("PER_HOST"));
+        self.host_delay
+            .update(env.host_delay, &source("HOST_DELAY"));
+        self.retry_attempts
+            .update(env.retry_attempts, &source("RETRY_ATTEMPTS"));
+        self.retry_delay
+            .update(env.retry_delay, &source("RETRY_DELAY"));
+        self.retry_max_delay
+            .update(env.retry_max_delay, &source("RETRY_MAX_DELAY"));
+        self.retry_jitter
+            .update(env.retry_jitter, &source("RETRY_JITTER"));
+        self.retry_on
+            .update(env.retry_on.clone(), &source("RETRY_ON"));
+        self.cache.update(env.cache, &source("CACHE"));
+        self.cache_dir
+            .update(env.cache_dir.clone().map(Some), &source("CACHE_DIR"));
+        self.stale_after
+            .update(env.stale_after, &source("STALE_AFTER"));
+        self.max_body_size
+            .update(env.max_body_size, &source("MAX_BODY_SIZE"));
+        self.format.update(env.format.map(Some), &source("FORMAT"));
+        self.report_sections
+            .update(env.report_sections.clone(), &source("REPORT_SECTIONS"));
+        self.url_rules
+            .update(env.url_rules.clone(), &source("URL_RULES"));
+    }
+
+    /// Returns the canonicalizer for the configured URL rules
+    pub fn canonicalizer(&self) -> Canonicalizer {
+        Canonicalizer::new(&self.url_rules.value)
+    }
+
+    /// Returns the request limits for fetching feeds
+    pub fn limits(&self) -> Limits {
+        Limits {
+            concurrency: self.concurrency.value,
+            per_host: self.per_host.value,
+            host_delay: Duration::from_millis(self.host_delay.value),
+        }
+    }
+
+    /// Returns the policy for retrying failed feed requests
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.retry_attempts.value.max(1),
+            base_delay: Duration::from_millis(self.retry_delay.value),
+            max_delay: Duration::from_millis(self.retry_max_delay.value),
+            jitter: self.retry_jitter.value,
+            retry_on: self.retry_on.value.clone(),
+        }
+    }
+
+    /// Returns the HTTP cache, unless caching is disabled
+    pub fn http_cache(&self) -> Option<HttpCache> {
+        if !self.cache.value {
+            return None;
+        }
+        self.cache_dir.value.as_ref().map(HttpCache::new)
+    }
+
+    /// Returns the staleness threshold, or `None` if the check is disabled
+    pub fn stale_after(&self) -> Option<TimeDelta> {
+        Some(self.stale_after.value).filter(|age| *age > TimeDelta::zero())
+    }
+
+    /// Returns true if a staleness threshold was passed on the command line,
+    /// so `prune` also removes stale feeds
+    ///
+    /// A threshold from a config file or the environment only tunes the
+    /// check and never makes `prune` delete feeds on its own.
+    pub fn stale_after_from_cli(&self) -> bool {
+        self.stale_after.source == Source::Cli && self.stale_after().is_some()
+    }
+
+    /// Returns the response size limit, or `None` if bodies are read in full
+    pub fn max_body_size(&self) -> Option<u64> {
+        Some(self.max_body_size.value).filter(|size| *size > 0)
+    }
+
+    /// Formats the effective settings and where each came from
+    pub fn describe(&self) -> String {
+        let mut output = String::new();
+        if let Some(profile) = &self.profile {
+            output.push_str(&format!("# profile: {}\n", profile));
+        }
+        if self.files.is_empty() {
+            output.push_str("# no config files found\n");
+        }
+        for file in &self.files {
+            output.push_str(&format!("# config file: {}\n", file.display()));
+        }
+
+        let mut line = |key: &str, value: String, source: &Source| {
+            output.push_str(&format!("{} = {}  # {}\n", key, value, source));
+        };
+        line(
+            "timeout",
+            self.timeout.value.to_string(),
+            &self.timeout.source,
+        );
+        line(
+            "user-agent",
+            format!("{:?}", self.user_agent.value),
+            &self.user_agent.source,
+        );
+        line(
+            "concurrency",
+            self.concurrency.value.to_string(),
+            &self.concurrency.source,
+        );
+        line(
+            "per-host",
+            self.per_host.value.to_string(),
+            &self.per_host.source,
+        );
+        line(
+            "host-delay",
+            self.host_delay.value.to_string(),
+            &self.host_delay.source,
+        );
+        line(
+            "retry-attempts",
+            self.retry_attempts.value.to_string(),
+            &self.retry_attempts.source,
+        );
+        line(
+            "retry-delay",
+            self.retry_delay.value.to_string(),
+            &self.retry_delay.source,
+        );
+        line(
+            "retry-max-delay",
+            self.retry_max_delay.value.to_string(),
+            &self.retry_max_delay.source,
+        );
+        line(
+            "retry-jitter",
+            self.retry_jitter.value.to_string(),
+            &self.retry_jitter.source,
+        );
+        let retry_on: Vec<String> = self
+            .retry_on
+            .value
+            .iter()
+            .map(|k| format!("{:?}", value_name(k)))
+            .collect();
+        line(
+            "retry-on",
+            format!("[{}]", retry_on.join(", ")),
+            &self.retry_on.source,
+        );
+        line("cache", self.cache.value.to_string(), &self.cache.source);
+        line(
+            "cache-dir",
+            self.cache_dir
+                .value
+                .as_ref()
+                .map(|dir| format!("{:?}", dir.display().to_string()))
+                .unwrap_or_else(|| "\"none\"".to_string()),
+            &self.cache_dir.source,
+        );
+        line(
+            "stale-after",
+            format!("{:?}", format_age(self.stale_after.value)),
+            &self.stale_after.source,
+        );
+        line(
+            "max-body-size",
+            format!("{:?}", format_size(self.max_body_size.value)),
+            &self.max_body_size.source,
+        );
+        line(
+            "format",
+            self.format
+                .value
+                .map(|f| format!("{:?}", value_name(&f)))
+                .unwrap_or_else(|| "\"per command\"".to_string()),
+            &self.format.source,
+        );
+        let sections: Vec<String> = self
+            .report_sections
+            .value
+            .iter()
+            .map(|s| format!("{:?}", value_name(s)))
+            .collect();
+        line(
+            "report-sections",
+            format!("[{}]", sections.join(", ")),
+            &self.report_sections.source,
+        );
+        let rules: Vec<String> = self
+            .url_rules
+            .value
+            .iter()
+            .map(|r| format!("{:?}", value_name(r)))
+            .collect();
+        line(
+            "url-rules",
+            format!("[{}]", rules.join(", ")),
+            &self.url_rules.source,
+        );
+        output
+    }
+}
+
+/// Config file locations, lowest precedence first
+pub fn config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    if let Some(dir) = config_home {
+        paths.push(dir.join("opml-manager").join("config.toml"));
+    }
+    paths.push(PathBuf::from(PROJECT_CONFIG_FILE));
+    paths
+}
+
+fn value_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
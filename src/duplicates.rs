@@ -0,0 +1,257 @@
+//! Detecting feeds that serve the same content under different URLs
+//!
+//! URL canonicalization cannot tell that a feedburner proxy and the origin
+//! feed, or `/feed` and `/rss.xml`, are the same feed. Here feeds are
+//! compared by what they say about themselves once fetched: where they
+//! redirect to, their atom id and self link, their channel link and the
+//! GUIDs of their recent items. Each matching signal adds to a confidence
+//! score, and a feed scoring above a threshold against an earlier kept feed
+//! is reported as its duplicate.
+
+use crate::canonical::Canonicalizer;
+use crate::validation::ValidationResult;
+use crate::Feed;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Default confidence above which two feeds are considered duplicates
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.8;
+
+/// Evidence that two feeds are the same
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Signal {
+    /// Both URLs redirect to the same final URL
+    FinalUrl,
+    /// Both feeds declare the same atom id
+    AtomId,
+    /// The feeds' self links point to the same URL, or to each other
+    SelfLink,
+    /// Both feeds link to the same website
+    ChannelLink,
+    /// Most recent items have the same GUIDs
+    ItemGuids,
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Signal::FinalUrl => "final-url",
+            Signal::AtomId => "atom-id",
+            Signal::SelfLink => "self-link",
+            Signal::ChannelLink => "channel-link",
+            Signal::ItemGuids => "item-guids",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Signal {
+    /// How strongly the signal alone suggests a duplicate
+    ///
+    /// A shared channel link is weak on its own, since a site's posts and
+    /// comments feeds both link to the site.
+    fn weight(self) -> f64 {
+        match self {
+            Signal::FinalUrl => 0.95,
+            Signal::AtomId => 0.95,
+            Signal::SelfLink => 0.9,
+            Signal::ChannelLink => 0.5,
+            Signal::ItemGuids => 0.9,
+        }
+    }
+}
+
+/// A feed found to duplicate an earlier feed in the list
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentDuplicate {
+    pub title: String,
+    pub url: String,
+    /// Title of the earlier feed that is kept
+    pub duplicate_of_title: String,
+    /// URL of the earlier feed that is kept
+    pub duplicate_of: String,
+    /// Likelihood between 0 and 1 that both feeds are the same
+    pub confidence: f64,
+    pub signals: Vec<Signal>,
+}
+
+/// Scores how likely two validated feeds are to be the same feed
+///
+/// Signals are treated as independent evidence, so the confidence is
+/// `1 - Π(1 - weight)` over the matching signals. GUID overlap scales its
+/// weight by the share of the smaller feed's items found in the other one,
+/// and counts only when at least half of them are shared.
+///
+/// # Returns
+/// * `(f64, Vec<Signal>)` - The confidence and the signals that matched
+pub fn score(
+    a: &ValidationResult,
+    b: &ValidationResult,
+    canonical: &Canonicalizer,
+) -> (f64, Vec<Signal>) {
+    score_with(&Scored::new(a), &Scored::new(b), canonical)
+}
+
+/// A result together with the GUID set its comparisons need
+struct Scored<'a> {
+    result: &'a ValidationResult,
+    guids: HashSet<&'a str>,
+}
+
+impl<'a> Scored<'a> {
+    fn new(result: &'a ValidationResult) -> Self {
+        let guids = result
+            .identity
+            .iter()
+            .flat_map(|identity| identity.guids.iter().map(String::as_str))
+            .collect();
+        Scored { result, guids }
+    }
+}
+
+/// Scores two results whose GUID sets are already built
+fn score_with(a: &Scored, b: &Scored, canonical: &Canonicalizer) -> (f64, Vec<Signal>) {
+    let (guids_a, guids_b) = (&a.guids, &b.guids);
+    let (a, b) = (a.result, b.result);
+    let same = |x: &Option<String>, y: &Option<String>| match (x, y) {
+        (Some(x), Some(y)) => canonical.same(x, y),
+        _ => false,
+    };
+    let (Some(id_a), Some(id_b)) = (&a.identity, &b.identity) else {
+        return (0.0, Vec::new());
+    };
+
+    let mut signals = Vec::new();
+    let mut doubt = 1.0;
+    let mut add = |signal: Signal, weight: f64| {
+        signals.push(signal);
+        doubt *= 1.0 - weight;
+    };
+
+    if canonical.same(a.final_url(), b.final_url()) {
+        add(Signal::FinalUrl, Signal::FinalUrl.weight());
+    }
+    if matches!((&id_a.id, &id_b.id), (Some(x), Some(y)) if x == y) {
+        add(Signal::AtomId, Signal::AtomId.weight());
+    }
+    let points_at = |result: &ValidationResult, url: &str| {
+        result
+            .self_url
+            .as_deref()
+            .is_some_and(|s| canonical.same(s, url))
+    };
+    if same(&a.self_url, &b.self_url)
+        || points_at(a, &b.url)
+        || points_at(a, b.final_url())
+        || points_at(b, &a.url)
+        || points_at(b, a.final_url())
+    {
+        add(Signal::SelfLink, Signal::SelfLink.weight());
+    }
+    if same(&id_a.link, &id_b.link) {
+        add(Signal::ChannelLink, Signal::ChannelLink.weight());
+    }
+
+    let smaller = guids_a.len().min(guids_b.len());
+    if smaller > 0 {
+        let overlap = guids_a.intersection(guids_b).count() as f64 / smaller as f64;
+        if overlap >= 0.5 {
+            add(Signal::ItemGuids, Signal::ItemGuids.weight() * overlap);
+        }
+    }
+
+    (1.0 - doubt, signals)
+}
+
+/// Finds feeds that duplicate an earlier feed by content
+///
+/// Feeds are taken in document order, and each is reported against the kept
+/// feed it scores highest against, if that score is at least
+/// `min_confidence`; otherwise it is kept. Matches are not chained: a feed
+/// is only reported against a feed it matches itself, so the confidence and
+/// signals given are those of that very pair.
+///
+/// # Arguments
+/// * `feeds` - The feeds, in document order
+/// * `results` - Validation results for the feeds, matched by URL
+/// * `canonical` - Decides when two URLs are the same
+/// * `min_confidence` - Confidence from which feeds are considered the same
+///
+/// # Returns
+/// * `Vec<ContentDuplicate>` - The feeds that could be removed
+pub fn find_content_duplicates(
+    feeds: &[Feed],
+    results: &[ValidationResult],
+    canonical: &Canonicalizer,
+    min_confidence: f64,
+) -> Vec<ContentDuplicate> {
+    let by_url: HashMap<&str, &ValidationResult> =
+        results.iter().map(|r| (r.url.as_str(), r)).collect();
+    let checked: Vec<(&Feed, Scored)> = feeds
+        .iter()
+        .filter_map(|feed| {
+            let result = by_url.get(feed.xml_url.as_str())?;
+            Some((feed, Scored::new(result)))
+        })
+        .collect();
+
+    let mut kept: Vec<usize> = Vec::new();
+    let mut duplicates = Vec::new();
+    for (j, (feed, scored)) in checked.iter().enumerate() {
+        // The earliest of equally strong matches wins
+        let mut best: Option<(usize, f64, Vec<Signal>)> = None;
+        for &i in &kept {
+            let (confidence, signals) = score_with(&checked[i].1, scored, canonical);
+            if confidence >= min_confidence && best.as_ref().is_none_or(|b| confidence > b.1) {
+                best = Some((i, confidence, signals));
+            }
+        }
+        match best {
+            Some((i, confidence, signals)) => duplicates.push(ContentDuplicate {
+                title: feed.title.clone(),
+                url: feed.xml_url.clone(),
+                duplicate_of_title: checked[i].0.title.clone(),
+                duplicate_of: checked[i].0.xml_url.clone(),
+                confidence,
+                signals,
+            }),
+            None => kept.push(j),
+        }
+    }
+    duplicates
+}
+
+/// Formats content duplicates as a Markdown section
+pub fn format_content_duplicates_markdown(duplicates: &[ContentDuplicate]) -> String {
+    let mut output = String::from("## Content Duplicates\n\n");
+    if duplicates.is_empty() {
+        output.push_str("No feeds serve the same content\n\n");
+        return output;
+    }
+
+    output.push_str("| Feed | URL | Duplicate Of | Confidence | Signals |\n");
+    output.push_str("|------|-----|--------------|------------|---------|\n");
+    for duplicate in duplicates {
+        output.push_str(&format!(
+            "| {} | {} | {} ({}) | {:.0}% | {} |\n",
+            duplicate.title.replace("|", "&#124;"),
+            duplicate.url,
+            duplicate.duplicate_of_title.replace("|", "&#124;"),
+            duplicate.duplicate_of,
+            duplicate.confidence * 100.0,
+            signal_names(&duplicate.signals)
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+fn signal_names(signals: &[Signal]) -> String {
+    signals
+        .iter()
+        .map(Signal::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
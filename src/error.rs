@@ -28,6 +28,12 @@ pub enum OPMLError {
 
     #[error("Category nesting too deep: maximum depth is {0} levels")]
     CategoryNestingTooDeep(usize),
+
+    #[error("Invalid filter expression: {0}")]
+    InvalidFilter(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
 }
 
 pub type Result<T> = std::result::Result<T, OPMLError>;
@@ -1,9 +1,19 @@
+use std::collections::BTreeMap;
+use url::Url;
+
 #[derive(Debug, Clone)]
 pub struct Feed {
     pub title: String,
     pub xml_url: String,
     pub html_url: Option<String>,
     pub category: Vec<String>,
+    /// Tags from the outline's OPML `category` attribute
+    pub tags: Vec<String>,
+    /// Any other outline attributes, preserved verbatim, keyed by their
+    /// qualified name such as `language` or `podcast:guid`
+    pub attributes: BTreeMap<String, String>,
+    /// Namespace URIs of the prefixed `attributes`, by prefix
+    pub namespaces: BTreeMap<String, String>,
 }
 
 impl Feed {
@@ -18,6 +28,21 @@ impl Feed {
             xml_url,
             html_url,
             category,
+            tags: Vec::new(),
+            attributes: BTreeMap::new(),
+            namespaces: BTreeMap::new(),
         }
     }
+
+    /// Returns the host of the feed URL, if it can be parsed
+    pub fn domain(&self) -> Option<String> {
+        Url::parse(&self.xml_url)
+            .ok()
+            .and_then(|url| url.host_str().map(String::from))
+    }
+
+    /// Returns the category path joined with `/`
+    pub fn category_path(&self) -> String {
+        self.category.join("/")
+    }
 }
@@ -0,0 +1,369 @@
+//! Transports that fetch feed documents for validation
+//!
+//! Validation asks a `FeedFetcher` for one URL at a time and follows
+//! redirects, retries and caching itself. `reqwest::Client` is the fetcher
+//! used against the network. A `Cassette` replays responses from a
+//! directory of recordings instead, and a `Recorder` fills such a directory
+//! while fetching through another fetcher, so whole validation runs can be
+//! repeated offline with the same results.
+
+use crate::cache::content_hash;
+use crate::format::{is_binary_content_type, sniff_binary, SNIFF_LEN};
+use crate::io::write_atomic;
+use crate::validation::IssueKind;
+use crate::Result;
+use encoding_rs::{Encoding, UTF_8};
+use futures::future::BoxFuture;
+use reqwest::header::{
+    AsHeaderName, HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, IF_MODIFIED_SINCE,
+    IF_NONE_MATCH,
+};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// A request for one URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchRequest {
+    pub url: Url,
+    /// Sent as `If-None-Match`
+    pub etag: Option<String>,
+    /// Sent as `If-Modified-Since`
+    pub last_modified: Option<String>,
+    /// Bytes of body read before giving up, or `None` for no limit
+    pub max_body_size: Option<u64>,
+}
+
+/// A response to a `FetchRequest`
+#[derive(Debug, Clone, Default)]
+pub struct FetchResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// The body of a successful response; empty for any other status
+    pub body: String,
+}
+
+impl FetchResponse {
+    /// The value of a header, if present and valid text
+    pub fn header(&self, name: impl AsHeaderName) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+/// Why a request got no response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchError {
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl FetchError {
+    pub fn new(kind: IssueKind, message: impl Into<String>) -> Self {
+        FetchError {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    fn from_request_error(error: reqwest::Error) -> Self {
+        let kind = IssueKind::from_request_error(&error);
+        if kind == IssueKind::Timeout {
+            return FetchError::new(kind, "Network timeout");
+        }
+        FetchError::new(kind, error.to_string())
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+/// The outcome of fetching one URL
+pub type FetchResult = std::result::Result<FetchResponse, FetchError>;
+
+/// Fetches single URLs without following redirects
+pub trait FeedFetcher: Send + Sync {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult>;
+}
+
+/// Shares a fetcher, so the caller can keep a handle to it
+impl<T: FeedFetcher + ?Sized> FeedFetcher for std::sync::Arc<T> {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        (**self).fetch(request)
+    }
+}
+
+/// Fetches over the network; see `validation::build_client` for a suitable client
+impl FeedFetcher for Client {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        Box::pin(async move {
+            let mut builder = self.get(request.url.clone());
+            if let Some(etag) = &request.etag {
+                builder = builder.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &request.last_modified {
+                builder = builder.header(IF_MODIFIED_SINCE, last_modified);
+            }
+
+            let response = builder
+                .send()
+                .await
+                .map_err(FetchError::from_request_error)?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = if status.is_success() {
+                read_body(response, request.max_body_size).await?
+            } else {
+                String::new()
+            };
+            Ok(FetchResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// Reads a body in chunks, giving up as soon as it is too large or binary
+///
+/// Audio, video and images are refused by their `Content-Type` without
+/// reading anything, and other binary files by their first bytes. Text is
+/// decoded with the charset of the `Content-Type`, or as UTF-8.
+async fn read_body(
+    mut response: reqwest::Response,
+    max_body_size: Option<u64>,
+) -> std::result::Result<String, FetchError> {
+    let too_large = |limit| {
+        FetchError::new(
+            IssueKind::ResponseTooLarge,
+            format!("response too large: over the limit of {} bytes", limit),
+        )
+    };
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    if let Some(content_type) = content_type
+        .as_deref()
+        .filter(|c| is_binary_content_type(c))
+    {
+        return Err(FetchError::new(
+            IssueKind::BinaryContent,
+            format!("binary content: served as {}", content_type),
+        ));
+    }
+    if let (Some(limit), Some(length)) = (max_body_size, response.content_length()) {
+        if length > limit {
+            return Err(too_large(limit));
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut sniffed = false;
+    loop {
+        let chunk = response
+            .chunk()
+            .await
+            .map_err(|_| FetchError::new(IssueKind::BodyRead, "Failed to read response text"))?;
+        let Some(chunk) = chunk else { break };
+        bytes.extend_from_slice(&chunk);
+        if max_body_size.is_some_and(|limit| bytes.len() as u64 > limit) {
+            return Err(too_large(max_body_size.unwrap_or_default()));
+        }
+        if !sniffed && bytes.len() >= SNIFF_LEN {
+            sniffed = true;
+            check_text(&bytes)?;
+        }
+    }
+    if !sniffed {
+        check_text(&bytes)?;
+    }
+
+    let encoding = content_type
+        .as_deref()
+        .and_then(charset)
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(UTF_8);
+    Ok(encoding.decode(&bytes).0.into_owned())
+}
+
+/// Fails if the first bytes of a body belong to a binary file
+fn check_text(start: &[u8]) -> std::result::Result<(), FetchError> {
+    match sniff_binary(start) {
+        Some(name) => Err(FetchError::new(
+            IssueKind::BinaryContent,
+            format!("binary content: looks like {}", name),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The `charset` parameter of a `Content-Type` header
+fn charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// One recorded outcome, as stored in a cassette file
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<FetchError>,
+}
+
+impl Recording {
+    fn new(url: &Url, result: &FetchResult) -> Self {
+        let mut recording = Recording {
+            url: url.to_string(),
+            status: None,
+            headers: Vec::new(),
+            body: String::new(),
+            error: None,
+        };
+        match result {
+            Ok(response) => {
+                recording.status = Some(response.status.as_u16());
+                recording.headers = response
+                    .headers
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        Some((name.to_string(), value.to_str().ok()?.to_string()))
+                    })
+                    .collect();
+                recording.body = response.body.clone();
+            }
+            Err(error) => recording.error = Some(error.clone()),
+        }
+        recording
+    }
+
+    fn into_result(self) -> FetchResult {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let status = self
+            .status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .ok_or_else(|| FetchError::new(IssueKind::Other, "Recording has no valid status"))?;
+        let headers = self
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value).ok()?,
+                ))
+            })
+            .collect();
+        Ok(FetchResponse {
+            status,
+            headers,
+            body: self.body,
+        })
+    }
+}
+
+/// A directory of recorded responses, one JSON file per URL, replayed as a fetcher
+///
+/// Conditional request headers are ignored on replay, so a cassette is
+/// best recorded and replayed without the HTTP cache.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cassette {
+    dir: PathBuf,
+}
+
+impl Cassette {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Cassette { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", content_hash(url)))
+    }
+
+    /// Returns the recorded outcome for a URL, if there is a readable one
+    pub fn load(&self, url: &Url) -> Option<FetchResult> {
+        let content = fs::read_to_string(self.path(url.as_str())).ok()?;
+        serde_json::from_str::<Recording>(&content)
+            .ok()
+            .filter(|recording| recording.url == url.as_str())
+            .map(Recording::into_result)
+    }
+
+    /// Records the outcome for a URL, replacing any previous one
+    pub fn store(&self, url: &Url, result: &FetchResult) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let content = serde_json::to_vec_pretty(&Recording::new(url, result))
+            .map_err(std::io::Error::other)?;
+        write_atomic(&self.path(url.as_str()), &content, 0)
+    }
+}
+
+impl FeedFetcher for Cassette {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        Box::pin(async move {
+            self.load(&request.url).unwrap_or_else(|| {
+                Err(FetchError::new(
+                    IssueKind::Other,
+                    format!(
+                        "No recorded response for {} in {}",
+                        request.url,
+                        self.dir.display()
+                    ),
+                ))
+            })
+        })
+    }
+}
+
+/// Fetches through another fetcher and records every outcome in a cassette
+///
+/// When a URL is fetched more than once, as with retries, the last outcome
+/// is the one kept.
+pub struct Recorder<F> {
+    inner: F,
+    cassette: Cassette,
+}
+
+impl<F: FeedFetcher> Recorder<F> {
+    pub fn new(inner: F, cassette: Cassette) -> Self {
+        Recorder { inner, cassette }
+    }
+}
+
+impl<F: FeedFetcher> FeedFetcher for Recorder<F> {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        Box::pin(async move {
+            let result = self.inner.fetch(request).await;
+            if let Err(e) = self.cassette.store(&request.url, &result) {
+                return Err(FetchError::new(
+                    IssueKind::Other,
+                    format!("Failed to record response: {}", e),
+                ));
+            }
+            result
+        })
+    }
+}
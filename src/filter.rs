@@ -0,0 +1,463 @@
+//! A small expression language for selecting feeds
+//!
+//! Expressions compare feed fields against values and combine the
+//! comparisons with `and`, `or`, `not` and parentheses:
+//!
+//! ```text
+//! category = "Tech/*" and domain = medium.com and title ~ /rust/i and status != valid
+//! ```
+//!
+//! Supported fields are `title`, `xmlUrl` (or `url`), `htmlUrl` (or `site`),
+//! `domain`, `category` (the path joined with `/`), `tag`, `status` and
+//! `@name` (or `attr.name`) for any other outline attribute. Field names are
+//! case-insensitive.
+//!
+//! Operators:
+//! - `=` / `==` compare case-insensitively; `*` and `?` act as wildcards.
+//!   For `domain`, subdomains also match, so `domain = medium.com` matches
+//!   `blog.medium.com`.
+//! - `!=` is the negation of `=`.
+//! - `~` matches a regular expression, written either as a string or as
+//!   `/pattern/flags` (only the `i` flag is supported).
+//! - `!~` is the negation of `~`.
+
+use crate::error::{OPMLError, Result};
+use crate::Feed;
+use regex::{Regex, RegexBuilder};
+
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    Title,
+    XmlUrl,
+    HtmlUrl,
+    Domain,
+    Category,
+    Tag,
+    Status,
+    Attribute(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Pattern(Regex),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Op(Op),
+    Text(String),
+    Word(String),
+    Regex(String, String),
+}
+
+impl Filter {
+    /// Parses a filter expression
+    ///
+    /// # Arguments
+    /// * `input` - The expression source, e.g. `title ~ /rust/i and tag = news`
+    ///
+    /// # Returns
+    /// * `Result<Filter>` - The compiled filter, or `OPMLError::InvalidFilter`
+    pub fn parse(input: &str) -> Result<Filter> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(OPMLError::InvalidFilter(format!(
+                "unexpected {:?} after end of expression",
+                token
+            )));
+        }
+        Ok(Filter { expr })
+    }
+
+    /// Returns true if the feed matches the expression
+    ///
+    /// Comparisons against `status` see an empty value, since no validation
+    /// result is available.
+    pub fn matches(&self, feed: &Feed) -> bool {
+        self.matches_with_status(feed, None)
+    }
+
+    /// Returns true if the feed, with the given validation status, matches the expression
+    pub fn matches_with_status(&self, feed: &Feed, status: Option<&str>) -> bool {
+        evaluate(&self.expr, feed, status)
+    }
+
+    /// Returns true if the expression refers to the validation status
+    pub fn uses_status(&self) -> bool {
+        fn walk(expr: &Expr) -> bool {
+            match expr {
+                Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => walk(lhs) || walk(rhs),
+                Expr::Not(inner) => walk(inner),
+                Expr::Compare(field, _, _) => *field == Field::Status,
+            }
+        }
+        walk(&self.expr)
+    }
+}
+
+impl std::str::FromStr for Filter {
+    type Err = OPMLError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Filter::parse(s)
+    }
+}
+
+fn evaluate(expr: &Expr, feed: &Feed, status: Option<&str>) -> bool {
+    match expr {
+        Expr::And(lhs, rhs) => evaluate(lhs, feed, status) && evaluate(rhs, feed, status),
+        Expr::Or(lhs, rhs) => evaluate(lhs, feed, status) || evaluate(rhs, feed, status),
+        Expr::Not(inner) => !evaluate(inner, feed, status),
+        Expr::Compare(field, op, value) => {
+            let candidates = field_values(field, feed, status);
+            let any_match = candidates.iter().any(|candidate| match value {
+                Value::Pattern(re) => re.is_match(candidate),
+                Value::Text(text) if *field == Field::Domain => {
+                    let candidate = candidate.to_lowercase();
+                    let text = text.to_lowercase();
+                    glob_match(&text, &candidate) || candidate.ends_with(&format!(".{}", text))
+                }
+                Value::Text(text) => glob_match(&text.to_lowercase(), &candidate.to_lowercase()),
+            });
+            match op {
+                Op::Eq | Op::Match => any_match,
+                Op::Ne | Op::NotMatch => !any_match,
+            }
+        }
+    }
+}
+
+fn field_values(field: &Field, feed: &Feed, status: Option<&str>) -> Vec<String> {
+    let single = |value: Option<String>| vec![value.unwrap_or_default()];
+    match field {
+        Field::Title => vec![feed.title.clone()],
+        Field::XmlUrl => vec![feed.xml_url.clone()],
+        Field::HtmlUrl => single(feed.html_url.clone()),
+        Field::Domain => single(feed.domain()),
+        Field::Category => vec![feed.category_path()],
+        Field::Tag if feed.tags.is_empty() => vec![String::new()],
+        Field::Tag => feed.tags.clone(),
+        Field::Status => single(status.map(String::from)),
+        Field::Attribute(name) => single(feed.attributes.get(name).cloned()),
+    }
+}
+
+/// Case-sensitive glob match supporting `*` and `?`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '=' => {
+                chars.next();
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                }
+                tokens.push(Token::Op(Op::Eq));
+            }
+            '~' => {
+                chars.next();
+                tokens.push(Token::Op(Op::Match));
+            }
+            '!' => {
+                chars.next();
+                match chars.peek() {
+                    Some('=') => {
+                        chars.next();
+                        tokens.push(Token::Op(Op::Ne));
+                    }
+                    Some('~') => {
+                        chars.next();
+                        tokens.push(Token::Op(Op::NotMatch));
+                    }
+                    _ => tokens.push(Token::Word("not".to_string())),
+                }
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(OPMLError::InvalidFilter(format!("expected '{}{}'", c, c)));
+                }
+                let keyword = if c == '&' { "and" } else { "or" };
+                tokens.push(Token::Word(keyword.to_string()));
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => text.push(escaped),
+                            None => break,
+                        },
+                        Some(ch) if ch == c => break,
+                        Some(ch) => text.push(ch),
+                        None => {
+                            return Err(OPMLError::InvalidFilter(
+                                "unterminated string literal".to_string(),
+                            ))
+                        }
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            '/' => {
+                chars.next();
+                let mut pattern = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') if chars.peek() == Some(&'/') => {
+                            chars.next();
+                            pattern.push('/');
+                        }
+                        Some('/') => break,
+                        Some(ch) => pattern.push(ch),
+                        None => {
+                            return Err(OPMLError::InvalidFilter(
+                                "unterminated regular expression".to_string(),
+                            ))
+                        }
+                    }
+                }
+                let mut flags = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if !ch.is_ascii_alphabetic() {
+                        break;
+                    }
+                    flags.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Regex(pattern, flags));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()=!~\"'".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut lhs = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.next();
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut lhs = self.parse_not()?;
+        while self.peek_keyword("and") {
+            self.next();
+            let rhs = self.parse_not()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.peek_keyword("not") {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(OPMLError::InvalidFilter("expected ')'".to_string())),
+                }
+            }
+            Some(Token::Word(name)) => {
+                let field = parse_field(&name)?;
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    _ => {
+                        return Err(OPMLError::InvalidFilter(format!(
+                            "expected an operator after '{}'",
+                            name
+                        )))
+                    }
+                };
+                let value = self.parse_value(op)?;
+                Ok(Expr::Compare(field, op, value))
+            }
+            Some(token) => Err(OPMLError::InvalidFilter(format!(
+                "expected a field name, found {:?}",
+                token
+            ))),
+            None => Err(OPMLError::InvalidFilter(
+                "unexpected end of expression".to_string(),
+            )),
+        }
+    }
+
+    fn parse_value(&mut self, op: Op) -> Result<Value> {
+        let (source, flags) = match self.next() {
+            Some(Token::Text(text)) | Some(Token::Word(text)) => (text, String::new()),
+            Some(Token::Regex(pattern, flags)) => (pattern, flags),
+            _ => {
+                return Err(OPMLError::InvalidFilter(
+                    "expected a value after operator".to_string(),
+                ))
+            }
+        };
+
+        match op {
+            Op::Eq | Op::Ne => Ok(Value::Text(source)),
+            Op::Match | Op::NotMatch => {
+                if let Some(flag) = flags.chars().find(|&f| f != 'i') {
+                    return Err(OPMLError::InvalidFilter(format!(
+                        "unsupported regex flag '{}'",
+                        flag
+                    )));
+                }
+                RegexBuilder::new(&source)
+                    .case_insensitive(flags.contains('i'))
+                    .build()
+                    .map(Value::Pattern)
+                    .map_err(|e| OPMLError::InvalidFilter(e.to_string()))
+            }
+        }
+    }
+}
+
+fn parse_field(name: &str) -> Result<Field> {
+    if let Some(attr) = name
+        .strip_prefix('@')
+        .or_else(|| name.strip_prefix("attr."))
+    {
+        return Ok(Field::Attribute(attr.to_string()));
+    }
+    match name.to_lowercase().as_str() {
+        "title" | "text" => Ok(Field::Title),
+        "xmlurl" | "url" => Ok(Field::XmlUrl),
+        "htmlurl" | "site" => Ok(Field::HtmlUrl),
+        "domain" | "host" => Ok(Field::Domain),
+        "category" | "path" => Ok(Field::Category),
+        "tag" | "tags" => Ok(Field::Tag),
+        "status" => Ok(Field::Status),
+        _ => Err(OPMLError::InvalidFilter(format!(
+            "unknown field '{}'",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("tech/*", "tech/rust"));
+        assert!(glob_match("*.xml", "feed.xml"));
+        assert!(glob_match("f??d", "feed"));
+        assert!(!glob_match("tech/*", "science/tech"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Filter::parse("title =").is_err());
+        assert!(Filter::parse("bogus = 1").is_err());
+        assert!(Filter::parse("(title = a").is_err());
+        assert!(Filter::parse("title ~ /x/g").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Which syndication format a feed document is written in
+//!
+//! Readers accept more than RSS 2.0 and Atom 1.0: RSS 0.9x and 1.0 (RDF),
+//! the pre-standard Atom 0.3 and JSON Feed are all still served. Detection
+//! looks only at the document's root, so it is cheap enough to run on every
+//! fetched feed.
+
+use roxmltree::Document;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const ATOM_03_NS: &str = "http://purl.org/atom/ns#";
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const RSS_10_NS: &str = "http://purl.org/rss/1.0/";
+const RSS_090_NS: &str = "http://my.netscape.com/rdf/simple/0.9/";
+const JSON_FEED_PREFIX: &str = "https://jsonfeed.org/version/";
+
+/// A family of feed formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeedKind {
+    /// RSS 0.9x and 2.0, rooted at `<rss>`
+    Rss,
+    /// RSS 1.0 and 0.90, rooted at `<rdf:RDF>`
+    Rdf,
+    Atom,
+    JsonFeed,
+}
+
+impl FeedKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FeedKind::Rss => "rss",
+            FeedKind::Rdf => "rdf",
+            FeedKind::Atom => "atom",
+            FeedKind::JsonFeed => "json-feed",
+        }
+    }
+
+    /// The name readers know the format by
+    pub fn label(self) -> &'static str {
+        match self {
+            FeedKind::Rss | FeedKind::Rdf => "RSS",
+            FeedKind::Atom => "Atom",
+            FeedKind::JsonFeed => "JSON Feed",
+        }
+    }
+}
+
+impl fmt::Display for FeedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The detected format of a feed and the version it declares
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FeedFormat {
+    pub kind: FeedKind,
+    /// Version such as `2.0` or `1.1`; `None` if the document does not say
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl FeedFormat {
+    fn new(kind: FeedKind, version: Option<&str>) -> Self {
+        FeedFormat {
+            kind,
+            version: version
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from),
+        }
+    }
+
+    /// Detects the format of a parsed XML document
+    ///
+    /// # Arguments
+    /// * `doc` - The parsed document
+    ///
+    /// # Returns
+    /// * `Option<FeedFormat>` - The format, or `None` if the root is not a feed
+    pub fn from_xml(doc: &Document) -> Option<Self> {
+        let root = doc.root_element();
+        let name = root.tag_name();
+        match name.name() {
+            "rss" => Some(FeedFormat::new(FeedKind::Rss, root.attribute("version"))),
+            // A bare channel is how some RSS 0.9x generators start the document
+            "channel" => Some(FeedFormat::new(FeedKind::Rss, None)),
+            "RDF" if name.namespace() == Some(RDF_NS) => {
+                let channel = root.children().find(|n| n.has_tag_name("channel"))?;
+                let version = match channel.tag_name().namespace() {
+                    Some(RSS_090_NS) => Some("0.90"),
+                    Some(RSS_10_NS) | None => Some("1.0"),
+                    Some(_) => None,
+                };
+                Some(FeedFormat::new(FeedKind::Rdf, version))
+            }
+            "feed" => {
+                let version = match name.namespace() {
+                    Some(ATOM_NS) => Some("1.0"),
+                    Some(ATOM_03_NS) => Some("0.3"),
+                    _ => root.attribute("version"),
+                };
+                Some(FeedFormat::new(FeedKind::Atom, version))
+            }
+            _ => None,
+        }
+    }
+
+    /// Detects a JSON Feed from its `version` member
+    ///
+    /// # Arguments
+    /// * `value` - The parsed JSON document
+    ///
+    /// # Returns
+    /// * `Option<FeedFormat>` - The format, or `None` if the document is not a JSON Feed
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let version = value.get("version")?.as_str()?;
+        let number = version.strip_prefix(JSON_FEED_PREFIX)?;
+        let number = if number == "1" { "1.0" } else { number };
+        Some(FeedFormat::new(FeedKind::JsonFeed, Some(number)))
+    }
+}
+
+impl fmt::Display for FeedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {}", self.kind.label(), version),
+            None => f.write_str(self.kind.label()),
+        }
+    }
+}
+
+/// Returns true if a document looks like JSON rather than XML or HTML
+pub fn looks_like_json(text: &str) -> bool {
+    text.trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with('{')
+}
+
+/// Bytes looked at to tell a binary file from a text document
+pub const SNIFF_LEN: usize = 512;
+
+/// Leading bytes of binary files that are mistaken for feeds, with their names
+const BINARY_SIGNATURES: [(&[u8], &str); 12] = [
+    (b"ID3", "MP3 audio"),
+    (b"\xff\xfb", "MP3 audio"),
+    (b"OggS", "Ogg media"),
+    (b"fLaC", "FLAC audio"),
+    (b"RIFF", "RIFF media"),
+    (b"%PDF", "PDF document"),
+    (b"PK\x03\x04", "ZIP archive"),
+    (b"\x1f\x8b", "gzip data"),
+    (b"\x89PNG", "PNG image"),
+    (b"\xff\xd8\xff", "JPEG image"),
+    (b"GIF8", "GIF image"),
+    (b"\x00\x00\x01\xba", "MPEG video"),
+];
+
+/// Returns true if a `Content-Type` header announces audio, video or an image
+///
+/// SVG images are XML and pass, and so does `application/octet-stream`,
+/// which misconfigured servers send for feeds too.
+pub fn is_binary_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    ["audio/", "video/", "image/"]
+        .iter()
+        .any(|prefix| mime.starts_with(prefix))
+        && !mime.ends_with("+xml")
+}
+
+/// Names the kind of binary file the first bytes of a document belong to
+///
+/// # Arguments
+/// * `start` - The first bytes of the document; `SNIFF_LEN` bytes are enough
+///
+/// # Returns
+/// * `Option<&'static str>` - A name such as "MP3 audio", or `None` for text
+pub fn sniff_binary(start: &[u8]) -> Option<&'static str> {
+    let start = &start[..start.len().min(SNIFF_LEN)];
+    if let Some((_, name)) = BINARY_SIGNATURES
+        .iter()
+        .find(|(signature, _)| start.starts_with(signature))
+    {
+        return Some(name);
+    }
+    if start.get(4..8) == Some(b"ftyp") {
+        return Some("MP4 media");
+    }
+    // UTF-16 text is full of NUL bytes, but starts with a byte order mark
+    let utf16 = start.starts_with(b"\xff\xfe") || start.starts_with(b"\xfe\xff");
+    (!utf16 && start.contains(&0)).then_some("binary data")
+}
@@ -0,0 +1,240 @@
+//! Making sense of HTML pages served where a feed should be
+//!
+//! Expired domains answer with parking pages, and redesigned sites answer
+//! old feed URLs with their homepage or a "not found" page, all with status
+//! 200. These helpers tell such pages apart and find the feed a page
+//! advertises through autodiscovery links.
+
+use url::Url;
+
+/// Phrases shown on domain parking and for-sale pages, lowercased
+const PARKING_PHRASES: [&str; 5] = [
+    "this domain is for sale",
+    "this domain may be for sale",
+    "buy this domain",
+    "domain is parked",
+    "parked free, courtesy of",
+];
+
+/// Parking services and domain marketplaces, as found in links and scripts
+const PARKING_SERVICES: [&str; 9] = [
+    "parkingcrew.net",
+    "sedoparking.com",
+    "bodis.com",
+    "dan.com/buy-domain",
+    "hugedomains.com",
+    "afternic.com",
+    "parklogic.com",
+    "above.com/marketplace",
+    "window.park",
+];
+
+/// Content types of autodiscovery links that point at feeds
+const FEED_LINK_TYPES: [&str; 4] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/rdf+xml",
+    "application/feed+json",
+];
+
+/// Words in a page title that give away a "not found" page
+const NOT_FOUND_TITLES: [&str; 4] = ["404", "not found", "page missing", "does not exist"];
+
+/// Returns true if a document looks like an HTML page
+pub fn looks_like_html(text: &str) -> bool {
+    let start: String = text.trim_start().chars().take(512).collect();
+    let start = start.to_lowercase();
+    start.starts_with("<!doctype html") || start.contains("<html")
+}
+
+/// Returns true if a `Content-Type` header announces HTML
+pub fn is_html_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    mime.eq_ignore_ascii_case("text/html") || mime.eq_ignore_ascii_case("application/xhtml+xml")
+}
+
+/// The parking signature that gives a page away as parked, if any
+///
+/// A parking phrase in the title is enough. Elsewhere, a single mention of a
+/// phrase or parking service is common on real sites, so at least two
+/// signatures are needed: phrases in the visible text, services anywhere.
+pub fn parking_signature(html: &str) -> Option<&'static str> {
+    let html = html.to_lowercase();
+    let title = page_title(&html).unwrap_or_default();
+    if let Some(phrase) = PARKING_PHRASES.iter().find(|p| title.contains(*p)) {
+        return Some(phrase);
+    }
+
+    let text = visible_text(&html);
+    let found: Vec<&'static str> = PARKING_PHRASES
+        .iter()
+        .filter(|phrase| text.contains(*phrase))
+        .chain(
+            PARKING_SERVICES
+                .iter()
+                .filter(|service| html.contains(*service)),
+        )
+        .copied()
+        .collect();
+    (found.len() >= 2).then(|| found[0])
+}
+
+/// The text a page shows, without markup, scripts or styles, whitespace collapsed
+fn visible_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        rest = &rest[start..];
+        let hidden = ["<script", "<style"]
+            .iter()
+            .find(|tag| rest.starts_with(*tag))
+            .map(|tag| format!("</{}", &tag[1..]));
+        if let Some(close) = hidden {
+            rest = &rest[rest.find(&close).unwrap_or(rest.len())..];
+        }
+        rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+    }
+    text.push_str(rest);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The text of a page's `<title>`, with whitespace collapsed
+pub fn page_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html[start..end].split_whitespace().collect::<Vec<_>>();
+    (!title.is_empty()).then(|| title.join(" "))
+}
+
+/// Returns true if a page's title says the page was not found
+pub fn is_not_found_page(html: &str) -> bool {
+    page_title(html).is_some_and(|title| {
+        let title = title.to_lowercase();
+        NOT_FOUND_TITLES.iter().any(|words| title.contains(words))
+    })
+}
+
+/// Finds the feeds a page advertises with `<link rel="alternate">` tags
+///
+/// # Arguments
+/// * `html` - The page
+/// * `base` - The URL the page was fetched from, for relative links
+///
+/// # Returns
+/// * `Vec<String>` - Absolute feed URLs, in document order
+pub fn discover_feeds(html: &str, base: &Url) -> Vec<String> {
+    let base = tags(html, "base")
+        .iter()
+        .find_map(|attributes| attribute(attributes, "href"))
+        .and_then(|href| base.join(href).ok())
+        .unwrap_or_else(|| base.clone());
+
+    tags(html, "link")
+        .iter()
+        .filter(|attributes| {
+            attribute(attributes, "rel").is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|token| token.eq_ignore_ascii_case("alternate"))
+            })
+        })
+        .filter(|attributes| {
+            attribute(attributes, "type").is_some_and(|kind| {
+                FEED_LINK_TYPES
+                    .iter()
+                    .any(|feed_type| kind.trim().eq_ignore_ascii_case(feed_type))
+            })
+        })
+        .filter_map(|attributes| attribute(attributes, "href"))
+        .filter_map(|href| base.join(href.trim()).ok())
+        .map(String::from)
+        .collect()
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// The attributes of every start tag with a name, lowercasing attribute names
+///
+/// This is a scanner, not a parser: it is enough for the `<link>` and
+/// `<base>` tags of a page's head, which carry no nested markup.
+fn tags(html: &str, name: &str) -> Vec<Vec<(String, String)>> {
+    // ASCII lowercasing keeps byte offsets valid for the original text
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{}", name);
+    let mut found = Vec::new();
+    let mut offset = 0;
+
+    while let Some(position) = lower[offset..].find(&open) {
+        let start = offset + position + open.len();
+        offset = start;
+        // `<link` must not match `<linkage`
+        if !html[start..].starts_with(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>') {
+            continue;
+        }
+        let (attributes, end) = parse_attributes(&html[start..]);
+        found.push(attributes);
+        offset = start + end;
+    }
+    found
+}
+
+/// Parses attributes up to the end of a tag, returning them and the bytes consumed
+fn parse_attributes(text: &str) -> (Vec<(String, String)>, usize) {
+    let mut attributes = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    loop {
+        while chars
+            .next_if(|(_, c)| c.is_whitespace() || *c == '/')
+            .is_some()
+        {}
+        let Some(&(start, c)) = chars.peek() else {
+            return (attributes, text.len());
+        };
+        if c == '>' {
+            return (attributes, start + 1);
+        }
+
+        let mut end = start;
+        while let Some((i, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && !"=>/".contains(*c)) {
+            end = i + c.len_utf8();
+        }
+        let name = text[start..end].to_ascii_lowercase();
+        if end == start {
+            // A stray character such as an unmatched quote
+            chars.next();
+            continue;
+        }
+
+        while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if(|(_, c)| *c == '=').is_some() {
+            while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+            match chars.next_if(|(_, c)| *c == '"' || *c == '\'') {
+                Some((_, quote)) => {
+                    for (_, c) in chars.by_ref() {
+                        if c == quote {
+                            break;
+                        }
+                        value.push(c);
+                    }
+                }
+                None => {
+                    while let Some((_, c)) = chars.next_if(|(_, c)| !c.is_whitespace() && *c != '>')
+                    {
+                        value.push(c);
+                    }
+                }
+            }
+        }
+        attributes.push((name, value.replace("&amp;", "&")));
+    }
+}
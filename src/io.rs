@@ -0,0 +1,200 @@
+//! Reading and writing command input and output, where `-` means stdin or stdout
+//!
+//! Regular files are never written in place: content goes to a temporary file
+//! in the same directory which is then renamed over the target, so a crash
+//! leaves either the old or the new file, never a truncated one. Pipes,
+//! devices and other special files are written directly.
+
+use crate::error::Result;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Path value that refers to stdin or stdout
+pub const STDIO: &str = "-";
+
+/// Returns true if the path refers to stdin or stdout
+pub fn is_stdio(path: &str) -> bool {
+    path == STDIO
+}
+
+/// Reads the whole input, from stdin if `path` is `-`
+pub fn read_input(path: &str) -> Result<String> {
+    if is_stdio(path) {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        Ok(content)
+    } else {
+        Ok(fs::read_to_string(path)?)
+    }
+}
+
+/// Writes the output, to stdout if `path` is `-`
+pub fn write_output(path: &str, content: &str) -> Result<()> {
+    if is_stdio(path) {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    } else {
+        write_atomic(Path::new(path), content.as_bytes(), 0)
+    }
+}
+
+/// Atomically replaces a file's content, optionally keeping backups
+///
+/// Symlinks are resolved first, so the file they point to is replaced and
+/// backed up while the link itself stays. New paths and regular files are
+/// replaced atomically; special files such as a FIFO or `/dev/stdout` are
+/// written into directly.
+///
+/// # Arguments
+/// * `path` - The file to write
+/// * `content` - The new content
+/// * `backups` - How many rotated `.bak` copies of the previous content to keep
+///
+/// # Returns
+/// * `Result<()>` - Ok once the new content has been renamed into place
+pub fn write_atomic(path: &Path, content: &[u8], backups: usize) -> Result<()> {
+    let resolved = fs::canonicalize(path).ok();
+    let path = resolved.as_deref().unwrap_or(path);
+    if let Ok(metadata) = fs::metadata(path) {
+        if !metadata.is_file() {
+            let mut file = File::create(path)?;
+            file.write_all(content)?;
+            file.flush()?;
+            return Ok(());
+        }
+    }
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut temp = tempfile::Builder::new()
+        .prefix(".opml-manager-")
+        .tempfile_in(dir)?;
+    temp.write_all(content)?;
+    temp.as_file().sync_all()?;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp.path(), metadata.permissions())?;
+        if backups > 0 {
+            rotate_backups(path, backups)?;
+        }
+    }
+
+    temp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Returns the path of the n-th backup of a file: `.bak` for the newest,
+/// then `.bak.1`, `.bak.2` and so on
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    if n == 0 {
+        name.push(".bak");
+    } else {
+        name.push(format!(".bak.{}", n));
+    }
+    PathBuf::from(name)
+}
+
+/// Shifts existing backups one slot older and copies the file into the newest slot
+fn rotate_backups(path: &Path, keep: usize) -> Result<()> {
+    let oldest = backup_path(path, keep - 1);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for n in (0..keep - 1).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 0))?;
+    Ok(())
+}
+
+/// An advisory lock guarding a file against concurrent modification
+///
+/// The lock is taken on a `<file>.lock` sidecar rather than the file itself,
+/// because atomic writes replace the file and with it any lock held on it.
+/// The lock is released, and the sidecar deleted, when the value is dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    file: File,
+}
+
+impl FileLock {
+    /// Blocks until the lock for `path` is acquired
+    pub fn acquire(path: &Path) -> Result<FileLock> {
+        let path = lock_path(path);
+        loop {
+            let file = open_lock_file(&path)?;
+            file.lock()?;
+            if is_current(&path, &file) {
+                return Ok(FileLock { path, file });
+            }
+        }
+    }
+
+    /// Acquires the lock for `path` if nobody else holds it
+    ///
+    /// # Returns
+    /// * `Result<Option<FileLock>>` - `None` if the lock is held elsewhere
+    pub fn try_acquire(path: &Path) -> Result<Option<FileLock>> {
+        let path = lock_path(path);
+        loop {
+            let file = open_lock_file(&path)?;
+            match file.try_lock() {
+                Ok(()) if is_current(&path, &file) => return Ok(Some(FileLock { path, file })),
+                Ok(()) => continue,
+                Err(fs::TryLockError::WouldBlock) => return Ok(None),
+                Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Deleted while still locked, so a waiter holding the old sidecar
+        // notices and starts over on a new one
+        let _ = fs::remove_file(&self.path);
+        let _ = self.file.unlock();
+    }
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+    Ok(OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?)
+}
+
+/// Returns true if `file` is still the sidecar at `path`, not one deleted by its last holder
+#[cfg(unix)]
+fn is_current(path: &Path, file: &File) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(path), file.metadata()) {
+        (Ok(current), Ok(locked)) => current.dev() == locked.dev() && current.ino() == locked.ino(),
+        _ => false,
+    }
+}
+
+/// Returns true if `file` is still the sidecar at `path`, not one deleted by its last holder
+#[cfg(not(unix))]
+fn is_current(path: &Path, _file: &File) -> bool {
+    // Open files cannot be deleted here, so the sidecar is never replaced
+    path.exists()
+}
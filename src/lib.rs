@@ -3,6 +3,7 @@
 //! This library provides functionality for:
 //! - Parsing and generating OPML files
 //! - Analyzing feed lists for duplicates
+//! - Selecting feeds with filter expressions
 //! - Validating feeds
 //! - Generating reports about feed lists
 //!
@@ -25,14 +26,32 @@
 //! # }
 //! ```
 
+pub mod cache;
+pub mod canonical;
+pub mod checkpoint;
 pub mod cli;
+pub mod config;
+pub mod duplicates;
 pub mod error;
 pub mod feed;
+pub mod fetcher;
+pub mod filter;
+pub mod format;
+pub mod html;
+pub mod io;
+pub mod lint;
+pub mod metadata;
 pub mod opml;
+pub mod prune;
+pub mod redirects;
 pub mod report;
+pub mod retry;
+pub mod scheduler;
+pub mod tree;
 pub mod validation;
 
 pub use error::{OPMLError, Result};
 pub use feed::Feed;
+pub use filter::Filter;
 pub use opml::{generate_opml, parse_opml};
 pub use validation::{validate_feed, ValidationResult};
@@ -0,0 +1,693 @@
+//! Checking that feeds are well-formed for readers, beyond being parsable
+//!
+//! Validation answers "is this a feed"; linting looks at what readers trip
+//! over in feeds that pass: missing titles and GUIDs, dates they cannot
+//! parse, relative links they cannot resolve and headers that contradict
+//! the document.
+
+use crate::format::{looks_like_json, FeedFormat, FeedKind};
+use crate::metadata::{self, alternate_link, child_text, json_items, json_text, parse_date};
+use crate::retry::RetryPolicy;
+use crate::validation::{fetch_feed_document, ValidationResult};
+use crate::{Feed, Result};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use reqwest::Client;
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use url::Url;
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Content types that announce an XML feed
+const XML_FEED_TYPES: [&str; 5] = [
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/rdf+xml",
+    "application/xml",
+    "text/xml",
+];
+
+/// Content types that announce a JSON Feed
+const JSON_FEED_TYPES: [&str; 2] = ["application/feed+json", "application/json"];
+
+/// How much a lint warning matters to readers
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    /// Deviations that readers cope with
+    Info,
+    /// Problems that some readers handle badly
+    Warning,
+    /// Violations of the format's requirements
+    Error,
+}
+
+/// What a lint warning is about
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// The channel or feed has no title
+    MissingTitle,
+    /// The channel or feed has no link to its website
+    MissingLink,
+    /// Items without a GUID or id, which readers need to tell items apart
+    MissingGuid,
+    /// Several items share a GUID, so readers show only one of them
+    DuplicateGuid,
+    /// Dates that cannot be parsed at all
+    InvalidDate,
+    /// Dates that only parse by tolerating deviations from the format's date syntax
+    NonStandardDate,
+    /// Relative links that cannot be resolved because there is no `xml:base`
+    RelativeLink,
+    /// A missing or non-feed `Content-Type` header
+    ContentType,
+    /// The header and the XML declaration disagree on the character set
+    CharsetMismatch,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
+}
+
+/// One problem found in a feed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintWarning {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl LintWarning {
+    fn new(rule: LintRule, severity: Severity, message: impl Into<String>) -> Self {
+        LintWarning {
+            rule,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// A feed's validation result together with its lint warnings
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedLint {
+    #[serde(flatten)]
+    pub result: ValidationResult,
+    pub warnings: Vec<LintWarning>,
+}
+
+impl FeedLint {
+    /// Lints the document kept on a validation result
+    ///
+    /// # Arguments
+    /// * `result` - A result from `fetch_feed_document`; its document is dropped
+    ///
+    /// # Returns
+    /// * `FeedLint` - The result and its warnings, none if no document was received
+    pub fn new(mut result: ValidationResult) -> Self {
+        let warnings = lint(&mut result);
+        FeedLint { result, warnings }
+    }
+
+    /// The highest severity among the warnings, if there are any
+    pub fn worst(&self) -> Option<Severity> {
+        self.warnings.iter().map(|w| w.severity).max()
+    }
+}
+
+/// Lints the document kept on a validation result and drops it
+///
+/// Header checks are skipped for documents answered from the cache, whose
+/// headers were not received.
+///
+/// # Arguments
+/// * `result` - A result from `fetch_feed_document`
+///
+/// # Returns
+/// * `Vec<LintWarning>` - The problems found, most severe first
+pub fn lint(result: &mut ValidationResult) -> Vec<LintWarning> {
+    let Some(document) = result.document.take() else {
+        return Vec::new();
+    };
+    let Ok(base) = Url::parse(result.final_url()) else {
+        return Vec::new();
+    };
+
+    let mut warnings = Vec::new();
+    if !result.not_modified {
+        warnings.extend(lint_headers(
+            document.content_type.as_deref(),
+            &document.body,
+        ));
+    }
+    warnings.extend(lint_body(&document.body, &base));
+    warnings.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.rule.cmp(&b.rule)));
+    warnings
+}
+
+/// Fetches a feed and lints it
+///
+/// # Arguments
+/// * `feed` - The feed to lint
+/// * `client` - The HTTP client, usually from `build_client`
+/// * `policy` - Which failures are retried, how often and after how long
+///
+/// # Returns
+/// * `Result<FeedLint>` - The validation result and the warnings for its document
+pub async fn lint_feed(feed: &Feed, client: &Client, policy: &RetryPolicy) -> Result<FeedLint> {
+    let result = fetch_feed_document(feed, client, policy, None).await?;
+    Ok(FeedLint::new(result))
+}
+
+/// Checks the `Content-Type` header against the document it came with
+///
+/// # Arguments
+/// * `content_type` - The header as received, if any
+/// * `body` - The document
+///
+/// # Returns
+/// * `Vec<LintWarning>` - Problems with the header
+pub fn lint_headers(content_type: Option<&str>, body: &str) -> Vec<LintWarning> {
+    let Some(content_type) = content_type else {
+        return vec![LintWarning::new(
+            LintRule::ContentType,
+            Severity::Warning,
+            "No Content-Type header",
+        )];
+    };
+
+    let mut warnings = Vec::new();
+    let mut params = content_type.split(';');
+    let mime = params.next().unwrap_or_default().trim().to_lowercase();
+    let is_json = looks_like_json(body);
+    let expected: &[&str] = if is_json {
+        &JSON_FEED_TYPES
+    } else {
+        &XML_FEED_TYPES
+    };
+    if !expected.contains(&mime.as_str()) {
+        warnings.push(LintWarning::new(
+            LintRule::ContentType,
+            Severity::Warning,
+            format!(
+                "Served as {}, expected {}",
+                mime,
+                if is_json {
+                    "application/feed+json"
+                } else {
+                    "application/rss+xml, application/atom+xml or application/xml"
+                }
+            ),
+        ));
+    }
+
+    let charset = params.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_string())
+    });
+    if let (Some(charset), Some(encoding)) = (charset, xml_encoding(body)) {
+        if normalize_charset(&charset) != normalize_charset(&encoding) {
+            warnings.push(LintWarning::new(
+                LintRule::CharsetMismatch,
+                Severity::Warning,
+                format!(
+                    "Content-Type says charset {}, XML declaration says {}",
+                    charset, encoding
+                ),
+            ));
+        }
+    }
+    warnings
+}
+
+/// The encoding named in a document's XML declaration
+fn xml_encoding(body: &str) -> Option<String> {
+    let body = body.trim_start_matches('\u{feff}');
+    let declaration = body.strip_prefix("<?xml")?.split("?>").next()?;
+    let (_, rest) = declaration.split_once("encoding")?;
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    rest[1..].split(quote).next().map(String::from)
+}
+
+/// Folds spelling variants of a charset name such as `UTF-8` and `utf8`
+fn normalize_charset(charset: &str) -> String {
+    charset
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Checks a document against the rules of its feed format
+///
+/// Documents that are not feeds yield no warnings; validation reports them.
+///
+/// # Arguments
+/// * `body` - The document
+/// * `base` - The URL it was fetched from
+///
+/// # Returns
+/// * `Vec<LintWarning>` - Problems with the document
+pub fn lint_body(body: &str, base: &Url) -> Vec<LintWarning> {
+    if looks_like_json(body) {
+        let body = body.trim_start_matches('\u{feff}');
+        return match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(value) if FeedFormat::from_json(&value).is_some() => lint_json(&value),
+            _ => Vec::new(),
+        };
+    }
+    let Ok(doc) = Document::parse(body) else {
+        return Vec::new();
+    };
+    match FeedFormat::from_xml(&doc) {
+        Some(format) => lint_xml(&doc, format.kind, base),
+        None => Vec::new(),
+    }
+}
+
+/// Values found by a rule, reported as one warning with a count and an example
+fn summarize(
+    warnings: &mut Vec<LintWarning>,
+    rule: LintRule,
+    severity: Severity,
+    found: &[String],
+    describe: &str,
+) {
+    if let Some(example) = found.first() {
+        warnings.push(LintWarning::new(
+            rule,
+            severity,
+            format!("{} {}, e.g. {}", found.len(), describe, example),
+        ));
+    }
+}
+
+/// Values that occur more than once, in order of first occurrence
+fn duplicates(ids: &[String]) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for id in ids {
+        *counts.entry(id.as_str()).or_insert(0) += 1;
+    }
+    let mut found = Vec::new();
+    for id in ids {
+        if counts.remove(id.as_str()).is_some_and(|count| count > 1) {
+            found.push(id.clone());
+        }
+    }
+    found
+}
+
+/// Sorts dates into those that break the format's syntax and those that cannot be read at all
+fn check_date(
+    text: &str,
+    strict: fn(&str) -> bool,
+    non_standard: &mut Vec<String>,
+    invalid: &mut Vec<String>,
+) {
+    let text = text.trim();
+    if strict(text) {
+        return;
+    }
+    let quoted = format!("\"{}\"", text);
+    if parse_date(text).is_some() {
+        non_standard.push(quoted);
+    } else {
+        invalid.push(quoted);
+    }
+}
+
+fn is_rfc2822(text: &str) -> bool {
+    DateTime::parse_from_rfc2822(text).is_ok()
+}
+
+fn is_rfc3339(text: &str) -> bool {
+    DateTime::parse_from_rfc3339(text).is_ok()
+}
+
+fn lint_xml(doc: &Document, kind: FeedKind, base: &Url) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let Some(channel) = metadata::channel(doc) else {
+        return warnings;
+    };
+    let (container, guid_severity) = match kind {
+        FeedKind::Atom => ("Feed", Severity::Error),
+        _ => ("Channel", Severity::Warning),
+    };
+
+    if child_text(channel, "title").is_none() {
+        warnings.push(LintWarning::new(
+            LintRule::MissingTitle,
+            Severity::Error,
+            format!("{} has no title", container),
+        ));
+    }
+    if alternate_link(channel, base).is_none() {
+        // RSS requires a channel link; Atom only recommends one
+        let severity = match kind {
+            FeedKind::Atom => Severity::Info,
+            _ => Severity::Warning,
+        };
+        warnings.push(LintWarning::new(
+            LintRule::MissingLink,
+            severity,
+            format!("{} has no link to its website", container),
+        ));
+    }
+
+    let items: Vec<Node> = metadata::items(doc).collect();
+    let mut ids = Vec::new();
+    let mut missing_ids = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        let id = match kind {
+            FeedKind::Atom => child_text(*item, "id"),
+            FeedKind::Rdf => item.attribute((RDF_NS, "about")).map(String::from),
+            _ => child_text(*item, "guid"),
+        };
+        match id {
+            Some(id) => ids.push(id),
+            None => missing_ids.push(
+                child_text(*item, "title")
+                    .map(|title| format!("\"{}\"", title))
+                    .unwrap_or_else(|| format!("item {}", index + 1)),
+            ),
+        }
+    }
+    summarize(
+        &mut warnings,
+        LintRule::MissingGuid,
+        guid_severity,
+        &missing_ids,
+        &format!("of {} items have no GUID", items.len()),
+    );
+    summarize(
+        &mut warnings,
+        LintRule::DuplicateGuid,
+        Severity::Warning,
+        &duplicates(&ids),
+        "GUIDs are shared by several items",
+    );
+
+    let mut non_standard = Vec::new();
+    let mut invalid = Vec::new();
+    let dated = std::iter::once(channel).chain(items.iter().copied());
+    for node in dated.flat_map(|n| n.children()).filter(|n| n.is_element()) {
+        let strict: fn(&str) -> bool = match node.tag_name().name() {
+            "pubDate" | "lastBuildDate" => is_rfc2822,
+            "date" | "updated" | "published" | "modified" | "issued" => is_rfc3339,
+            _ => continue,
+        };
+        if let Some(text) = node.text() {
+            check_date(text, strict, &mut non_standard, &mut invalid);
+        }
+    }
+    summarize(
+        &mut warnings,
+        LintRule::InvalidDate,
+        Severity::Warning,
+        &invalid,
+        "dates cannot be parsed",
+    );
+    summarize(
+        &mut warnings,
+        LintRule::NonStandardDate,
+        Severity::Info,
+        &non_standard,
+        "dates do not follow the format's date syntax",
+    );
+
+    let relative: Vec<String> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("link") || n.has_tag_name("enclosure"))
+        .filter_map(|n| {
+            let link = n
+                .attribute("href")
+                .or_else(|| n.attribute("url"))
+                .or_else(|| n.text())?
+                .trim();
+            let is_relative = !link.is_empty()
+                && matches!(
+                    Url::parse(link),
+                    Err(url::ParseError::RelativeUrlWithoutBase)
+                );
+            let has_base = n
+                .ancestors()
+                .any(|a| a.attribute((XML_NS, "base")).is_some());
+            (is_relative && !has_base).then(|| format!("\"{}\"", link))
+        })
+        .collect();
+    summarize(
+        &mut warnings,
+        LintRule::RelativeLink,
+        Severity::Warning,
+        &relative,
+        "relative links without xml:base",
+    );
+
+    warnings
+}
+
+fn lint_json(value: &serde_json::Value) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    if json_text(value, "title").is_none() {
+        warnings.push(LintWarning::new(
+            LintRule::MissingTitle,
+            Severity::Error,
+            "Feed has no title",
+        ));
+    }
+    if json_text(value, "home_page_url").is_none() {
+        warnings.push(LintWarning::new(
+            LintRule::MissingLink,
+            Severity::Info,
+            "Feed has no home_page_url",
+        ));
+    }
+
+    let items = json_items(value);
+    let mut ids = Vec::new();
+    let mut missing_ids = Vec::new();
+    let mut non_standard = Vec::new();
+    let mut invalid = Vec::new();
+    for (index, item) in items.iter().enumerate() {
+        match item.get("id") {
+            Some(serde_json::Value::Number(id)) => ids.push(id.to_string()),
+            _ => match json_text(item, "id") {
+                Some(id) => ids.push(id),
+                None => missing_ids.push(format!("item {}", index + 1)),
+            },
+        }
+        for name in ["date_published", "date_modified"] {
+            if let Some(date) = json_text(item, name) {
+                check_date(&date, is_rfc3339, &mut non_standard, &mut invalid);
+            }
+        }
+    }
+    summarize(
+        &mut warnings,
+        LintRule::MissingGuid,
+        Severity::Error,
+        &missing_ids,
+        &format!("of {} items have no id", items.len()),
+    );
+    summarize(
+        &mut warnings,
+        LintRule::DuplicateGuid,
+        Severity::Warning,
+        &duplicates(&ids),
+        "ids are shared by several items",
+    );
+    summarize(
+        &mut warnings,
+        LintRule::InvalidDate,
+        Severity::Warning,
+        &invalid,
+        "dates cannot be parsed",
+    );
+    summarize(
+        &mut warnings,
+        LintRule::NonStandardDate,
+        Severity::Info,
+        &non_standard,
+        "dates are not RFC 3339",
+    );
+    warnings
+}
+
+/// Serializable result of linting a feed list
+#[derive(Debug, Serialize)]
+pub struct LintReport {
+    pub source: String,
+    pub generated_at: String,
+    pub total_checked: usize,
+    pub severity_counts: BTreeMap<Severity, usize>,
+    pub rule_counts: BTreeMap<LintRule, usize>,
+    pub feeds: Vec<FeedLint>,
+}
+
+impl LintReport {
+    /// Collects lint results, dropping warnings below `min_severity`
+    pub fn new(source: &str, mut feeds: Vec<FeedLint>, min_severity: Severity) -> Self {
+        let mut severity_counts = BTreeMap::new();
+        let mut rule_counts = BTreeMap::new();
+        for feed in &mut feeds {
+            feed.warnings.retain(|w| w.severity >= min_severity);
+            for warning in &feed.warnings {
+                *severity_counts.entry(warning.severity).or_insert(0) += 1;
+                *rule_counts.entry(warning.rule).or_insert(0) += 1;
+            }
+        }
+
+        LintReport {
+            source: source.to_string(),
+            generated_at: Local::now().to_rfc3339(),
+            total_checked: feeds.len(),
+            severity_counts,
+            rule_counts,
+            feeds,
+        }
+    }
+}
+
+/// Formats lint warnings as a Markdown section, most severe first
+///
+/// # Arguments
+/// * `feeds` - Lint results; feeds without warnings are left out
+///
+/// # Returns
+/// * `String` - A "Lint Warnings" section with one row per warning
+pub fn format_lint_warnings_markdown(feeds: &[FeedLint]) -> String {
+    let mut output = String::from("## Lint Warnings\n\n");
+    let mut rows: Vec<(&FeedLint, &LintWarning)> = feeds
+        .iter()
+        .flat_map(|feed| feed.warnings.iter().map(move |w| (feed, w)))
+        .collect();
+    if rows.is_empty() {
+        output.push_str("No lint warnings\n\n");
+        return output;
+    }
+    rows.sort_by_key(|(_, warning)| std::cmp::Reverse(warning.severity));
+
+    output.push_str("| Feed | URL | Severity | Rule | Message |\n");
+    output.push_str("|------|-----|----------|------|---------|\n");
+    for (feed, warning) in rows {
+        output.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            feed.result.feed.replace("|", "&#124;"),
+            feed.result.url,
+            warning.severity,
+            warning.rule,
+            warning.message.replace("|", "\\|")
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+/// Formats a lint run as a Markdown report
+pub fn format_lint_markdown(report: &LintReport) -> String {
+    let mut output = String::new();
+    output.push_str("# Feed Lint Report\n\n");
+    output.push_str(&format!(
+        "Generated on: {}\n\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+    output.push_str(&format!("Source OPML: {}\n\n", report.source));
+
+    output.push_str("## Summary\n\n");
+    output.push_str(&format!(
+        "- Total feeds checked: {}\n",
+        report.total_checked
+    ));
+    let clean = report
+        .feeds
+        .iter()
+        .filter(|f| f.result.status.is_feed() && f.warnings.is_empty())
+        .count();
+    output.push_str(&format!("- Feeds without warnings: {}\n", clean));
+    for (severity, count) in report.severity_counts.iter().rev() {
+        output.push_str(&format!("- {}: {}\n", severity, count));
+    }
+    output.push('\n');
+
+    if !report.rule_counts.is_empty() {
+        output.push_str("### Rules\n\n");
+        for (rule, count) in &report.rule_counts {
+            output.push_str(&format!("- {}: {}\n", rule, count));
+        }
+        output.push('\n');
+    }
+
+    output.push_str(&format_lint_warnings_markdown(&report.feeds));
+
+    let unchecked: Vec<_> = report
+        .feeds
+        .iter()
+        .filter(|f| !f.result.status.is_feed())
+        .collect();
+    if !unchecked.is_empty() {
+        output.push_str("## Not Linted\n\n");
+        output.push_str("| Feed | URL | Status | Error |\n");
+        output.push_str("|------|-----|--------|-------|\n");
+        for feed in unchecked {
+            output.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                feed.result.feed.replace("|", "&#124;"),
+                feed.result.url,
+                feed.result.status,
+                feed.result.error.replace("|", "\\|")
+            ));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Formats a lint run as human-readable text, listing feeds with warnings
+pub fn format_lint_text(report: &LintReport) -> String {
+    let mut output = String::new();
+    output.push_str("\n🔎 Feed Lint Report\n");
+    output.push_str(&format!("Total feeds checked: {}\n", report.total_checked));
+    for (severity, count) in report.severity_counts.iter().rev() {
+        output.push_str(&format!("  {}: {}\n", severity, count));
+    }
+
+    for feed in report.feeds.iter().filter(|f| !f.warnings.is_empty()) {
+        output.push_str(&format!("\n{} ({})\n", feed.result.feed, feed.result.url));
+        for warning in &feed.warnings {
+            output.push_str(&format!(
+                "  [{}] {}: {}\n",
+                warning.severity, warning.rule, warning.message
+            ));
+        }
+    }
+
+    output
+}
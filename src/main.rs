@@ -1,62 +1,356 @@
 use clap::Parser;
-use futures::future::join_all;
+use futures::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::Client;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use std::fs;
+use std::io::IsTerminal;
 use std::path::Path;
 use std::time::Duration;
 
-use opml_manager::cli::{Cli, Commands};
-use opml_manager::opml::{generate_opml, parse_opml};
-use opml_manager::report::{format_markdown_report, generate_summary};
-use opml_manager::validation::validate_feed;
+use opml_manager::checkpoint::{Checkpoint, Resumed};
+use opml_manager::cli::{Cli, ColorChoice, Commands, ConfigCommands, OutputFormat, WriteArgs};
+use opml_manager::config::Config;
+use opml_manager::duplicates::{find_content_duplicates, format_content_duplicates_markdown};
+use opml_manager::fetcher::{Cassette, Recorder};
+use opml_manager::io::{is_stdio, read_input, write_atomic, write_output, FileLock, STDIO};
+use opml_manager::lint::{
+    format_lint_markdown, format_lint_text, format_lint_warnings_markdown, lint, FeedLint,
+    LintReport,
+};
+use opml_manager::opml::{generate_opml, parse_opml_with};
+use opml_manager::prune::{
+    format_prune_markdown, parse_validation_results, plan_prune, PruneHistory, PruneOptions,
+    PruneReason,
+};
+use opml_manager::redirects::{announced_urls, fix_redirects, format_url_changes_markdown};
+use opml_manager::report::{
+    format_analysis_text, format_markdown_report, format_markdown_report_sections,
+    format_staleness_markdown, format_validation_markdown, format_validation_text,
+    generate_summary, AnalysisSummary, ReportSection, ValidationReport, EXIT_INTERRUPTED,
+};
+use opml_manager::retry::RetryPolicy;
+use opml_manager::tree::{render_tree, CategoryNode, TreeOptions};
+use opml_manager::validation::{
+    build_client, ProgressObserver, ValidationResult, ValidationStatus, Validator,
+};
+use opml_manager::{Feed, Filter};
+
+/// Where feed responses come from, set by `--record` and `--replay`
+enum CassetteMode {
+    /// Fetch over the network only
+    Off,
+    /// Fetch over the network and record every response
+    Record(Cassette),
+    /// Replay recorded responses without touching the network
+    Replay(Cassette),
+}
+
+impl CassetteMode {
+    fn from_cli(cli: &Cli) -> Self {
+        match (&cli.record, &cli.replay) {
+            (Some(dir), _) => CassetteMode::Record(Cassette::new(dir)),
+            (_, Some(dir)) => CassetteMode::Replay(Cassette::new(dir)),
+            _ => CassetteMode::Off,
+        }
+    }
+}
+
+/// Validates feeds concurrently, showing a progress bar
+async fn validate_all(
+    feeds: &[Feed],
+    config: &Config,
+    cassette: &CassetteMode,
+) -> Result<Vec<ValidationResult>, Box<dyn Error>> {
+    fetch_all(feeds, config, cassette, false, None).await
+}
+
+/// Shows validation progress on stderr, and only when someone is watching it
+struct ProgressDisplay(ProgressBar);
+
+impl ProgressDisplay {
+    fn new() -> Self {
+        let pb = if std::io::stderr().is_terminal() {
+            ProgressBar::new(0)
+        } else {
+            ProgressBar::hidden()
+        };
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
+                )
+                .expect("Failed to create progress bar template")
+                .progress_chars("#>-"),
+        );
+        ProgressDisplay(pb)
+    }
+}
 
-fn normalize_url(url: &str) -> String {
-    let mut normalized_url = url.to_lowercase();
-    if normalized_url.ends_with('/') {
-        normalized_url.pop();
+impl ProgressObserver for ProgressDisplay {
+    fn started(&self, total: usize) {
+        self.0.set_length(total as u64);
     }
-    if normalized_url.starts_with("http://") {
-        normalized_url = normalized_url.replacen("http://", "https://", 1);
+
+    fn finished(&self, _result: &ValidationResult) {
+        self.0.inc(1);
+    }
+}
+
+/// Validates feeds concurrently, keeping the fetched documents if asked to
+///
+/// Recording and replaying bypass the HTTP cache, whose conditional requests
+/// would record `304` answers that cannot be replayed on their own. Replays
+/// are not retried, since a recording always answers the same.
+///
+/// Ctrl-C stops new requests and gives those in flight a few seconds to
+/// finish; the feeds cut off are returned as `cancelled`. A second Ctrl-C
+/// quits right away. Results are added to `checkpoint` as they arrive; if
+/// that fails, a warning is printed and the run goes on without it.
+async fn fetch_all(
+    feeds: &[Feed],
+    config: &Config,
+    cassette: &CassetteMode,
+    keep_documents: bool,
+    mut checkpoint: Option<&Checkpoint>,
+) -> Result<Vec<ValidationResult>, Box<dyn Error>> {
+    let progress = ProgressDisplay::new();
+    let pb = progress.0.clone();
+    let timeout = Duration::from_secs(config.timeout.value);
+    let builder = Validator::builder()
+        .timeout(timeout)
+        .user_agent(config.user_agent.value.as_str())
+        .limits(config.limits())
+        .retry_policy(config.retry_policy())
+        .cache(config.http_cache())
+        .stale_after(config.stale_after())
+        .max_body_size(config.max_body_size())
+        .keep_documents(keep_documents)
+        .observer(progress);
+    let validator = match cassette {
+        CassetteMode::Off => builder,
+        CassetteMode::Record(cassette) => {
+            let client = build_client(timeout, &config.user_agent.value)?;
+            builder
+                .cache(None)
+                .fetcher(Recorder::new(client, cassette.clone()))
+        }
+        CassetteMode::Replay(cassette) => builder
+            .cache(None)
+            .retry_policy(RetryPolicy::none())
+            .fetcher(cassette.clone()),
+    }
+    .build()?;
+
+    let cancel = validator.cancel_handle();
+    let interrupt = tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!("\nInterrupted: finishing requests in flight, press Ctrl-C again to quit");
+        cancel.cancel();
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+    });
+
+    let mut stream = validator.validate_all(feeds);
+    let mut results = Vec::with_capacity(feeds.len());
+    while let Some((index, result)) = stream.next().await {
+        if let Some(cp) = checkpoint {
+            if let Err(e) = cp.record(&result) {
+                pb.suspend(|| {
+                    eprintln!(
+                        "⚠️  Cannot write checkpoint {}: {}; the run cannot be resumed",
+                        cp.path().display(),
+                        e
+                    )
+                });
+                checkpoint = None;
+            }
+        }
+        results.push((index, result));
+    }
+    interrupt.abort();
+    pb.finish_with_message("Validation complete");
+    results.sort_by_key(|(index, _)| *index);
+
+    // The stream yields one result per feed, so the results line up with the input
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+/// Resolves the file a rewriting command writes to and locks it
+///
+/// The lock is taken before the input is read, so concurrent runs editing
+/// the same file are serialized instead of clobbering each other's changes.
+fn lock_target(
+    input_file: &str,
+    output_file: Option<String>,
+) -> Result<(String, Option<FileLock>), Box<dyn Error>> {
+    // Clap only allows a missing output file together with --in-place
+    let target = match output_file {
+        Some(path) => path,
+        None if is_stdio(input_file) => {
+            return Err("--in-place cannot be used when reading from stdin".into())
+        }
+        None => input_file.to_string(),
+    };
+    if is_stdio(&target) {
+        return Ok((target, None));
+    }
+
+    let path = Path::new(&target);
+    let lock = match FileLock::try_acquire(path)? {
+        Some(lock) => lock,
+        None => {
+            eprintln!("⏳ Waiting for another process editing {}", target);
+            FileLock::acquire(path)?
+        }
+    };
+    Ok((target, Some(lock)))
+}
+
+/// Writes a rewritten OPML file atomically, keeping backups if requested
+fn write_target(target: &str, content: &str, write: &WriteArgs) -> Result<(), Box<dyn Error>> {
+    if is_stdio(target) {
+        write_output(target, content)?;
+    } else {
+        write_atomic(
+            Path::new(target),
+            content.as_bytes(),
+            write.backup.unwrap_or(0),
+        )?;
+    }
+    Ok(())
+}
+
+/// Keeps only the feeds, and their validation results, that match the filter
+fn apply_filter(filter: &Filter, feeds: &mut Vec<Feed>, results: &mut Vec<ValidationResult>) {
+    let statuses = statuses_by_url(results);
+    feeds.retain(|f| filter.matches_with_status(f, statuses.get(f.xml_url.as_str()).copied()));
+
+    let kept: HashSet<&str> = feeds.iter().map(|f| f.xml_url.as_str()).collect();
+    results.retain(|r| kept.contains(r.url.as_str()));
+}
+
+/// Maps each feed URL to the status it was validated with
+fn statuses_by_url(results: &[ValidationResult]) -> HashMap<&str, &str> {
+    results
+        .iter()
+        .map(|r| (r.url.as_str(), r.status.as_str()))
+        .collect()
+}
+
+/// Validates the feeds if the filter compares their status, so it never sees an empty one
+async fn results_for_filter(
+    filter: &Filter,
+    feeds: &[Feed],
+    config: &Config,
+    cassette: &CassetteMode,
+) -> Result<Vec<ValidationResult>, Box<dyn Error>> {
+    if filter.uses_status() {
+        validate_all(feeds, config, cassette).await
+    } else {
+        Ok(Vec::new())
     }
-    normalized_url
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
+    let config = Config::load(cli.profile.as_deref(), &cli.settings())?;
+    let cassette = CassetteMode::from_cli(&cli);
+    let canonical = config.canonicalizer();
 
     match cli.command {
-        Commands::Analyze { input_file } => {
-            let content = fs::read_to_string(&input_file)?;
-            let feeds = parse_opml(&content)?;
-
-            let (seen_urls, duplicates, categories, _) = generate_summary(&feeds);
-
-            println!("\n📊 OPML Analysis Report");
-            println!("Total Feeds: {}", feeds.len());
-            println!("Unique Feeds: {}", seen_urls.len());
-            println!("Duplicates: {}", duplicates.len());
-            println!("Total Categories: {}", categories.len());
-
-            if !duplicates.is_empty() {
-                println!("\n🔄 Duplicate Feeds:");
-                for feed in duplicates {
-                    println!("  - {} ({})", feed.title, feed.xml_url);
-                    if !feed.category.is_empty() {
-                        println!("    Categories: {}", feed.category.join(" > "));
+        Commands::Analyze {
+            input_file,
+            content: content_args,
+        } => {
+            let content = read_input(&input_file)?;
+            let feeds = parse_opml_with(&content, &canonical)?;
+
+            let mut summary = AnalysisSummary::from_feeds(&feeds, &canonical);
+            if content_args.content {
+                let results = validate_all(&feeds, &config, &cassette).await?;
+                summary.content_duplicates = find_content_duplicates(
+                    &feeds,
+                    &results,
+                    &canonical,
+                    content_args.min_confidence,
+                );
+            }
+
+            let output = match config.format.value.unwrap_or(OutputFormat::Text) {
+                OutputFormat::Text => format_analysis_text(&summary),
+                OutputFormat::Json => serde_json::to_string_pretty(&summary)? + "\n",
+                OutputFormat::Ndjson => serde_json::to_string(&summary)? + "\n",
+                OutputFormat::Markdown => {
+                    let (seen_urls, duplicates, categories, domain_counter) =
+                        generate_summary(&feeds, &canonical);
+                    let mut report = format_markdown_report(
+                        &feeds,
+                        &seen_urls,
+                        &duplicates,
+                        &categories,
+                        &domain_counter,
+                    );
+                    if content_args.content {
+                        report.push_str(&format_content_duplicates_markdown(
+                            &summary.content_duplicates,
+                        ));
                     }
+                    report
                 }
+            };
+
+            let path = cli.output.as_deref().unwrap_or(STDIO);
+            write_output(path, &output)?;
+            if !is_stdio(path) {
+                eprintln!("✅ Analysis saved: {}", path);
             }
         }
 
+        Commands::Tree {
+            input_file,
+            feeds: show_feeds,
+            depth,
+            color,
+            validate,
+        } => {
+            let content = read_input(&input_file)?;
+            let feeds = parse_opml_with(&content, &canonical)?;
+
+            let mut statuses = HashMap::new();
+            if validate {
+                for result in validate_all(&feeds, &config, &cassette).await? {
+                    statuses.insert(result.url, result.status.to_string());
+                }
+            }
+
+            let options = TreeOptions {
+                show_feeds: show_feeds || validate,
+                max_depth: depth,
+                color: match color {
+                    ColorChoice::Always => true,
+                    ColorChoice::Never => false,
+                    ColorChoice::Auto => {
+                        std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+                    }
+                },
+            };
+            let root = CategoryNode::from_feeds(&feeds);
+            print!("{}", render_tree(&root, &options, &statuses));
+        }
+
         Commands::Dedupe {
             input_file,
             output_file,
+            content: content_args,
+            write,
         } => {
-            let content = fs::read_to_string(&input_file)?;
-            let feeds = parse_opml(&content)?;
+            let (target, _lock) = lock_target(&input_file, output_file)?;
+            let content = read_input(&input_file)?;
+            let feeds = parse_opml_with(&content, &canonical)?;
 
             // Store the original length
             let original_len = feeds.len();
@@ -65,180 +359,503 @@ async fn main() -> Result<(), Box<dyn Error>> {
             let mut seen = std::collections::HashSet::new();
 
             for feed in feeds {
-                if seen.insert(normalize_url(&feed.xml_url)) {
+                if seen.insert(canonical.canonicalize(&feed.xml_url)) {
                     unique_feeds.push(feed);
                 }
             }
 
-            let opml_content = generate_opml(&unique_feeds)?;
-            fs::write(&output_file, opml_content)?;
+            let mut content_removed = 0;
+            if content_args.content {
+                let results = validate_all(&unique_feeds, &config, &cassette).await?;
+                let duplicates = find_content_duplicates(
+                    &unique_feeds,
+                    &results,
+                    &canonical,
+                    content_args.min_confidence,
+                );
+                for duplicate in &duplicates {
+                    eprintln!(
+                        "  - {} ({}): same as {} ({}), {:.0}% confidence",
+                        duplicate.title,
+                        duplicate.url,
+                        duplicate.duplicate_of_title,
+                        duplicate.duplicate_of,
+                        duplicate.confidence * 100.0
+                    );
+                }
+                let removed: HashSet<&str> = duplicates.iter().map(|d| d.url.as_str()).collect();
+                unique_feeds.retain(|f| !removed.contains(f.xml_url.as_str()));
+                content_removed = duplicates.len();
+            }
 
-            println!(
-                "✅ Removed {} duplicates",
-                original_len - unique_feeds.len()
-            );
+            let opml_content = generate_opml(&unique_feeds)?;
+            write_target(&target, &opml_content, &write)?;
+
+            let removed = original_len - unique_feeds.len();
+            if content_args.content {
+                eprintln!(
+                    "✅ Removed {} duplicates ({} by content)",
+                    removed, content_removed
+                );
+            } else {
+                eprintln!("✅ Removed {} duplicates", removed);
+            }
         }
 
         Commands::Validate {
             input_file,
-            timeout,
+            filter,
+            resume,
+            checkpoint: checkpoint_path,
         } => {
-            let content = fs::read_to_string(&input_file)?;
-            let feeds = parse_opml(&content)?;
+            let content = read_input(&input_file)?;
+            let mut feeds = parse_opml_with(&content, &canonical)?;
 
-            let client = Client::builder()
-                .timeout(Duration::from_secs(timeout))
-                .build()?;
+            if let Some(filter) = filter.as_ref().filter(|f| !f.uses_status()) {
+                feeds.retain(|f| filter.matches(f));
+            }
 
-            let mut tasks = Vec::new();
-            let pb = ProgressBar::new(feeds.len() as u64);
-            pb.set_style(ProgressStyle::default_bar()
-                .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
-                .expect("Failed to create progress bar template")
-                .progress_chars("#>-"));
+            let checkpoint_path = checkpoint_path.or_else(|| {
+                (!is_stdio(&input_file)).then(|| Checkpoint::default_path(&input_file))
+            });
+            let previous = match (&checkpoint_path, resume) {
+                (Some(path), true) => Checkpoint::load(path)?,
+                (None, true) => {
+                    return Err("--resume needs --checkpoint when reading from stdin".into())
+                }
+                (_, false) => Vec::new(),
+            };
+            let resumed = Resumed::new(&feeds, previous);
+            if resume {
+                eprintln!(
+                    "Resuming: {} of {} feeds already checked",
+                    feeds.len() - resumed.pending.len(),
+                    feeds.len()
+                );
+            }
+            // Without a checkpoint the run merely cannot be resumed
+            let checkpoint = checkpoint_path.and_then(|path| {
+                Checkpoint::create(&path, &resumed.carried_over())
+                    .map_err(|e| {
+                        eprintln!(
+                            "⚠️  Cannot write checkpoint {}: {}; the run cannot be resumed",
+                            path.display(),
+                            e
+                        )
+                    })
+                    .ok()
+            });
+
+            let results = fetch_all(
+                &resumed.pending,
+                &config,
+                &cassette,
+                false,
+                checkpoint.as_ref(),
+            )
+            .await?;
+            let mut validation_results = resumed.merge(results);
+            let interrupted = validation_results
+                .iter()
+                .any(|r| r.status == ValidationStatus::Cancelled);
+            if let Some(filter) = &filter {
+                apply_filter(filter, &mut feeds, &mut validation_results);
+            }
 
-            for feed in &feeds {
-                let feed_clone = feed.clone();
-                let client_clone = client.clone();
-                let pb_clone = pb.clone();
-                tasks.push(tokio::spawn(async move {
-                    let result = validate_feed(&feed_clone, &client_clone).await;
-                    pb_clone.inc(1);
-                    result
-                }));
+            let report = ValidationReport::new(&input_file, feeds.len(), validation_results);
+            let format = config.format.value.unwrap_or(OutputFormat::Markdown);
+
+            let output = match format {
+                OutputFormat::Text => format_validation_text(&report),
+                OutputFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+                OutputFormat::Ndjson => {
+                    let mut lines = String::new();
+                    for result in &report.results {
+                        lines.push_str(&serde_json::to_string(result)?);
+                        lines.push('\n');
+                    }
+                    lines
+                }
+                OutputFormat::Markdown => format_validation_markdown(&report),
+            };
+
+            // Markdown keeps the historical behaviour of writing next to the input
+            let report_path = match (cli.output, format) {
+                (Some(path), _) => path,
+                (None, OutputFormat::Markdown) if !is_stdio(&input_file) => {
+                    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+                    Path::new(&input_file)
+                        .with_file_name(format!("validation_report_{}.md", timestamp))
+                        .to_string_lossy()
+                        .into_owned()
+                }
+                (None, _) => STDIO.to_string(),
+            };
+
+            write_output(&report_path, &output)?;
+            if !is_stdio(&report_path) {
+                eprintln!("\n✅ Validation report saved: {}", report_path);
+            }
+            match checkpoint {
+                Some(checkpoint) if interrupted => {
+                    eprintln!(
+                        "⚠️  Partial report; run again with --resume to continue from {}",
+                        checkpoint.path().display()
+                    );
+                }
+                Some(checkpoint) => checkpoint.remove()?,
+                None => {}
             }
 
-            let results = join_all(tasks).await;
-            pb.finish_with_message("Validation complete");
+            let code = report.exit_code();
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
 
-            let mut validation_results = Vec::new();
+        Commands::LintFeeds {
+            input_file,
+            filter,
+            min_severity,
+        } => {
+            let content = read_input(&input_file)?;
+            let mut feeds = parse_opml_with(&content, &canonical)?;
 
-            for result in results {
-                if let Ok(Ok(validation)) = result {
-                    validation_results.push(validation);
-                }
+            if let Some(filter) = filter.as_ref().filter(|f| !f.uses_status()) {
+                feeds.retain(|f| filter.matches(f));
             }
 
-            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-            let report_path = Path::new(&input_file)
-                .with_file_name(format!("validation_report_{}.md", timestamp));
-
-            let mut report = String::new();
-            report.push_str("# Feed Validation Report\n\n");
-            report.push_str(&format!(
-                "Generated on: {}\n\n",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-            ));
-            report.push_str(&format!("Source OPML: {}\n\n", input_file));
-
-            let mut status_counts = std::collections::HashMap::new();
-            for result in &validation_results {
-                *status_counts.entry(&result.status).or_insert(0) += 1;
-            }
-
-            report.push_str("## Summary\n\n");
-            report.push_str(&format!(
-                "- Total feeds checked: {}\n",
-                validation_results.len()
-            ));
-            for (status, count) in &status_counts {
-                report.push_str(&format!("- {}: {}\n", status, count));
-            }
-            report.push_str("\n");
-
-            for status in &["valid", "invalid", "error"] {
-                let status_results: Vec<_> = validation_results
-                    .iter()
-                    .filter(|r| r.status == *status)
-                    .collect();
-
-                if !status_results.is_empty() {
-                    let status_capitalized = status[0..1].to_uppercase() + &status[1..];
-                    report.push_str(&format!("## {} Feeds\n\n", status_capitalized));
-                    report.push_str("| Feed | URL | Error | Categories |\n");
-                    report.push_str("|------|-----|-------|------------|\n");
-
-                    for result in status_results {
-                        let categories = result.categories.join(" > ");
-                        let error = result.error.replace("|", "\\|");
-                        let feed = result.feed.replace("|", "&#124;");
-                        report.push_str(&format!(
-                            "| {} | {} | {} | {} |\n",
-                            feed, result.url, error, categories
-                        ));
+            let mut results = fetch_all(&feeds, &config, &cassette, true, None).await?;
+            if let Some(filter) = &filter {
+                apply_filter(filter, &mut feeds, &mut results);
+            }
+
+            let lints = results.into_iter().map(FeedLint::new).collect();
+            let report = LintReport::new(&input_file, lints, min_severity);
+            let output = match config.format.value.unwrap_or(OutputFormat::Markdown) {
+                OutputFormat::Text => format_lint_text(&report),
+                OutputFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+                OutputFormat::Ndjson => {
+                    let mut lines = String::new();
+                    for feed in &report.feeds {
+                        lines.push_str(&serde_json::to_string(feed)?);
+                        lines.push('\n');
                     }
-                    report.push_str("\n");
+                    lines
                 }
-            }
+                OutputFormat::Markdown => format_lint_markdown(&report),
+            };
 
-            fs::write(&report_path, report)?;
-            println!("\n✅ Validation report saved: {}", report_path.display());
+            let report_path = cli.output.unwrap_or_else(|| STDIO.to_string());
+            write_output(&report_path, &output)?;
+            if !is_stdio(&report_path) {
+                eprintln!("\n✅ Lint report saved: {}", report_path);
+            }
         }
 
         Commands::Report {
             input_file,
             output_file,
             validate_feeds,
-            timeout,
+            lint_feeds,
+            filter,
+            sections: _,
         } => {
-            let content = fs::read_to_string(&input_file)?;
-            let feeds = parse_opml(&content)?;
+            let validate_feeds = validate_feeds || lint_feeds;
+            let sections = &config.report_sections.value;
+            let content = read_input(&input_file)?;
+            let mut feeds = parse_opml_with(&content, &canonical)?;
 
-            let (seen_urls, duplicates, categories, domain_counter) = generate_summary(&feeds);
+            if let Some(filter) = filter.as_ref().filter(|f| !f.uses_status()) {
+                feeds.retain(|f| filter.matches(f));
+            }
 
-            let mut report = format_markdown_report(
+            // Filters on status need validation results before they can be applied
+            let mut validation_results = Vec::new();
+            if validate_feeds || filter.as_ref().is_some_and(Filter::uses_status) {
+                validation_results =
+                    fetch_all(&feeds, &config, &cassette, lint_feeds, None).await?;
+            }
+            if let Some(filter) = &filter {
+                apply_filter(filter, &mut feeds, &mut validation_results);
+            }
+            let lints: Vec<FeedLint> = validation_results
+                .iter_mut()
+                .filter(|_| lint_feeds)
+                .map(|result| FeedLint {
+                    warnings: lint(result),
+                    result: result.clone(),
+                })
+                .collect();
+
+            let (seen_urls, duplicates, categories, domain_counter) =
+                generate_summary(&feeds, &canonical);
+
+            let mut report = format_markdown_report_sections(
                 &feeds,
                 &seen_urls,
                 &duplicates,
                 &categories,
                 &domain_counter,
+                sections,
             );
 
-            if validate_feeds {
-                let client = Client::builder()
-                    .timeout(Duration::from_secs(timeout))
-                    .build()?;
-
+            if validate_feeds && sections.contains(&ReportSection::Validation) {
                 report.push_str("## Feed Validation Results\n\n");
+                let summary =
+                    ValidationReport::new(&input_file, feeds.len(), validation_results.clone());
+                report.push_str(&format!("{}\n\n", summary.reconciliation()));
                 report.push_str("| Feed | Status | Error |\n");
                 report.push_str("|------|--------|-------|\n");
 
-                let mut tasks = Vec::new();
-                let pb = ProgressBar::new(feeds.len() as u64);
-                pb.set_style(ProgressStyle::default_bar()
-                    .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
-                    .expect("Failed to create progress bar template")
-                    .progress_chars("#>-"));
-
-                for feed in &feeds {
-                    let feed_clone = feed.clone();
-                    let client_clone = client.clone();
-                    let pb_clone = pb.clone();
-                    tasks.push(tokio::spawn(async move {
-                        let result = validate_feed(&feed_clone, &client_clone).await;
-                        pb_clone.inc(1);
-                        result
-                    }));
+                for validation in &validation_results {
+                    let error = validation.error.replace("|", "\\|");
+                    let feed = validation.feed.replace("|", "&#124;");
+                    report.push_str(&format!(
+                        "| {} | {} | {} |\n",
+                        feed, validation.status, error
+                    ));
                 }
+                report.push('\n');
+            }
+            if validate_feeds && sections.contains(&ReportSection::Staleness) {
+                report.push_str(&format_staleness_markdown(
+                    &validation_results,
+                    chrono::Utc::now(),
+                ));
+            }
+            if lint_feeds && sections.contains(&ReportSection::Lint) {
+                report.push_str(&format_lint_warnings_markdown(&lints));
+            }
 
-                let results = join_all(tasks).await;
-                pb.finish_with_message("Validation complete");
+            write_output(&output_file, &report)?;
+            if !is_stdio(&output_file) {
+                eprintln!("✅ Report generated: {}", output_file);
+            }
+        }
 
-                for result in results {
-                    if let Ok(Ok(validation)) = result {
-                        let error = validation.error.replace("|", "\\|");
-                        let feed = validation.feed.replace("|", "&#124;");
-                        report.push_str(&format!(
-                            "| {} | {} | {} |\n",
-                            feed, validation.status, error
-                        ));
+        Commands::Prune {
+            input_file,
+            output_file,
+            mut reasons,
+            results,
+            grace,
+            history,
+            log,
+            dry_run,
+            write,
+        } => {
+            let target = match dry_run {
+                true => None,
+                false => Some(lock_target(&input_file, output_file)?),
+            };
+            let history_path = history.or_else(|| {
+                (!is_stdio(&input_file)).then(|| format!("{}.prune-history.json", input_file))
+            });
+            if grace > 1 && history_path.is_none() {
+                return Err("--grace needs --history when reading from stdin".into());
+            }
+
+            let content = read_input(&input_file)?;
+            let feeds = parse_opml_with(&content, &canonical)?;
+
+            let validation_results = match &results {
+                Some(path) => parse_validation_results(&read_input(path)?)?,
+                None => validate_all(&feeds, &config, &cassette).await?,
+            };
+
+            let mut prune_history = match &history_path {
+                Some(path) if Path::new(path).exists() => {
+                    PruneHistory::from_json(&read_input(path)?)?
+                }
+                _ => PruneHistory::default(),
+            };
+
+            if config.stale_after_from_cli() && !reasons.contains(&PruneReason::Stale) {
+                eprintln!("Also pruning stale feeds, since --stale-after was given");
+                reasons.push(PruneReason::Stale);
+            }
+            let options = PruneOptions {
+                reasons,
+                stale_after: config.stale_after(),
+                grace,
+            };
+            let (kept, removed) = plan_prune(
+                feeds,
+                &validation_results,
+                &options,
+                &mut prune_history,
+                chrono::Local::now(),
+            );
+
+            for feed in &removed {
+                eprintln!("  - {} ({}): {}", feed.title, feed.url, feed.reason);
+            }
+
+            if let Some(log) = &log {
+                let listing = match config.format.value {
+                    Some(OutputFormat::Json | OutputFormat::Ndjson) => {
+                        serde_json::to_string_pretty(&removed)? + "\n"
+                    }
+                    _ => format_prune_markdown(&input_file, &removed, dry_run),
+                };
+                write_output(log, &listing)?;
+            }
+
+            match target {
+                None => eprintln!("🔍 Dry run: {} feeds would be removed", removed.len()),
+                Some((target, _lock)) => {
+                    write_target(&target, &generate_opml(&kept)?, &write)?;
+                    if let Some(path) = &history_path {
+                        write_atomic(Path::new(path), prune_history.to_json()?.as_bytes(), 0)?;
                     }
+                    eprintln!("✅ Pruned {} feeds", removed.len());
                 }
-                report.push_str("\n");
+            }
+        }
+
+        Commands::FixRedirects {
+            input_file,
+            output_file,
+            sources,
+            results,
+            log,
+            dry_run,
+            write,
+        } => {
+            let target = match dry_run {
+                true => None,
+                false => Some(lock_target(&input_file, output_file)?),
+            };
+            let content = read_input(&input_file)?;
+            let mut feeds = parse_opml_with(&content, &canonical)?;
+
+            let validation_results = match &results {
+                Some(path) => parse_validation_results(&read_input(path)?)?,
+                None => validate_all(&feeds, &config, &cassette).await?,
+            };
+            // Announced URLs come from the documents and were never fetched
+            let announced: Vec<Feed> = announced_urls(&validation_results, &sources)
+                .into_iter()
+                .map(|url| Feed::new(url.clone(), url, None, vec![]))
+                .collect();
+            let targets = validate_all(&announced, &config, &cassette).await?;
+            let changes = fix_redirects(&mut feeds, &validation_results, &sources, &targets);
+
+            for change in &changes {
+                eprintln!(
+                    "  ~ {}: {} → {} ({})",
+                    change.title, change.from, change.to, change.detail
+                );
+            }
+
+            if let Some(log) = &log {
+                let listing = match config.format.value {
+                    Some(OutputFormat::Json | OutputFormat::Ndjson) => {
+                        serde_json::to_string_pretty(&changes)? + "\n"
+                    }
+                    _ => format_url_changes_markdown(&input_file, &changes, dry_run),
+                };
+                write_output(log, &listing)?;
+            }
+
+            match target {
+                None => eprintln!("🔍 Dry run: {} feed URLs would change", changes.len()),
+                Some((target, _lock)) => {
+                    write_target(&target, &generate_opml(&feeds)?, &write)?;
+                    eprintln!("✅ Updated {} feed URLs", changes.len());
+                }
+            }
+        }
+
+        Commands::Config {
+            command: ConfigCommands::Show,
+        } => {
+            print!("{}", config.describe());
+        }
+
+        Commands::Filter {
+            input_file,
+            expression,
+            output_file,
+        } => {
+            let content = read_input(&input_file)?;
+            let mut feeds = parse_opml_with(&content, &canonical)?;
+
+            let mut validation_results = Vec::new();
+            if expression.uses_status() {
+                validation_results = validate_all(&feeds, &config, &cassette).await?;
+            }
+            apply_filter(&expression, &mut feeds, &mut validation_results);
+
+            match output_file {
+                Some(output_file) => {
+                    write_output(&output_file, &generate_opml(&feeds)?)?;
+                    eprintln!("✅ Wrote {} matching feeds", feeds.len());
+                }
+                None => {
+                    for feed in &feeds {
+                        println!("{} ({})", feed.title, feed.xml_url);
+                        if !feed.category.is_empty() {
+                            println!("    Categories: {}", feed.category.join(" > "));
+                        }
+                    }
+                    eprintln!("\n🔎 {} matching feeds", feeds.len());
+                }
+            }
+        }
+
+        Commands::Remove {
+            input_file,
+            output_file,
+            filter,
+            write,
+        } => {
+            let (target, _lock) = lock_target(&input_file, output_file)?;
+            let content = read_input(&input_file)?;
+            let mut feeds = parse_opml_with(&content, &canonical)?;
+
+            let results = results_for_filter(&filter, &feeds, &config, &cassette).await?;
+            let statuses = statuses_by_url(&results);
+            let original_len = feeds.len();
+            feeds.retain(|f| {
+                !filter.matches_with_status(f, statuses.get(f.xml_url.as_str()).copied())
+            });
+
+            write_target(&target, &generate_opml(&feeds)?, &write)?;
+            eprintln!("✅ Removed {} feeds", original_len - feeds.len());
+        }
+
+        Commands::Move {
+            input_file,
+            output_file,
+            filter,
+            to,
+            write,
+        } => {
+            let (target, _lock) = lock_target(&input_file, output_file)?;
+            let content = read_input(&input_file)?;
+            let mut feeds = parse_opml_with(&content, &canonical)?;
+
+            let destination: Vec<String> = to
+                .split('/')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(String::from)
+                .collect();
+
+            let results = results_for_filter(&filter, &feeds, &config, &cassette).await?;
+            let statuses = statuses_by_url(&results);
+            let mut moved = 0;
+            for feed in feeds.iter_mut().filter(|f| {
+                filter.matches_with_status(f, statuses.get(f.xml_url.as_str()).copied())
+            }) {
+                feed.category = destination.clone();
+                moved += 1;
             }
 
-            fs::write(&output_file, report)?;
-            println!("✅ Report generated: {}", output_file);
+            write_target(&target, &generate_opml(&feeds)?, &write)?;
+            eprintln!("✅ Moved {} feeds to {}", moved, destination.join(" > "));
         }
     }
 
@@ -0,0 +1,284 @@
+//! What a feed document says about itself
+//!
+//! RSS declares its metadata in `<channel>`, Atom directly in `<feed>` and
+//! JSON Feed in top-level members; the helpers here look in the right place
+//! for each, so validation and duplicate detection read feeds the same way.
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Channel metadata and item statistics of a fetched feed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeedMetadata {
+    /// The title the feed declares, which may differ from the OPML title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The website the feed belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    /// The RSS description or Atom subtitle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The software that produced the feed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+    pub item_count: usize,
+    /// Publication date of the newest item, when the feed declares one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub newest_item: Option<DateTime<FixedOffset>>,
+    /// Publication date of the oldest item, when the feed declares one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oldest_item: Option<DateTime<FixedOffset>>,
+}
+
+impl FeedMetadata {
+    /// Reads the metadata of a parsed RSS or Atom document
+    ///
+    /// # Arguments
+    /// * `doc` - The parsed feed
+    /// * `base` - The URL the feed was fetched from, for relative links
+    ///
+    /// # Returns
+    /// * `FeedMetadata` - What the feed declares; fields it lacks are `None`
+    pub fn from_document(doc: &Document, base: &Url) -> Self {
+        let root = doc.root_element();
+        let channel = channel(doc);
+        let field = |name: &str| channel.and_then(|c| child_text(c, name));
+
+        let items: Vec<Node> = items(doc).collect();
+        let dates: Vec<DateTime<FixedOffset>> =
+            items.iter().filter_map(|item| item_date(*item)).collect();
+
+        FeedMetadata {
+            title: field("title"),
+            link: channel.and_then(|c| alternate_link(c, base)),
+            description: field("description")
+                .or_else(|| field("subtitle"))
+                .or_else(|| field("tagline")),
+            language: field("language").or_else(|| {
+                root.attribute((XML_NS, "lang"))
+                    .map(str::trim)
+                    .filter(|lang| !lang.is_empty())
+                    .map(String::from)
+            }),
+            generator: field("generator"),
+            item_count: items.len(),
+            newest_item: dates.iter().max().copied(),
+            oldest_item: dates.iter().min().copied(),
+        }
+    }
+
+    /// Reads the metadata of a parsed JSON Feed
+    ///
+    /// # Arguments
+    /// * `value` - The parsed feed
+    /// * `base` - The URL the feed was fetched from, for relative links
+    ///
+    /// # Returns
+    /// * `FeedMetadata` - What the feed declares; fields it lacks are `None`
+    pub fn from_json(value: &serde_json::Value, base: &Url) -> Self {
+        let items = json_items(value);
+        let dates: Vec<DateTime<FixedOffset>> = items
+            .iter()
+            .filter_map(|item| {
+                ["date_published", "date_modified"]
+                    .iter()
+                    .filter_map(|name| json_text(item, name))
+                    .filter_map(|date| parse_date(&date))
+                    .max()
+            })
+            .collect();
+
+        FeedMetadata {
+            title: json_text(value, "title"),
+            link: json_text(value, "home_page_url")
+                .and_then(|link| base.join(&link).ok())
+                .map(String::from),
+            description: json_text(value, "description"),
+            language: json_text(value, "language"),
+            generator: None,
+            item_count: items.len(),
+            newest_item: dates.iter().max().copied(),
+            oldest_item: dates.iter().min().copied(),
+        }
+    }
+}
+
+/// The element holding a feed's metadata: RSS `<channel>` or the Atom `<feed>` root
+pub(crate) fn channel<'a, 'input>(doc: &'a Document<'input>) -> Option<Node<'a, 'input>> {
+    let root = doc.root_element();
+    if root.has_tag_name("feed") {
+        Some(root)
+    } else {
+        root.children().find(|n| n.has_tag_name("channel"))
+    }
+}
+
+/// The feed's RSS items or Atom entries, in document order
+pub(crate) fn items<'a, 'input: 'a>(
+    doc: &'a Document<'input>,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    doc.descendants()
+        .filter(|n| n.has_tag_name("item") || n.has_tag_name("entry"))
+}
+
+/// The trimmed, non-empty text of the first child element with a local name
+pub(crate) fn child_text(node: Node, name: &str) -> Option<String> {
+    node.children()
+        .find(|n| n.tag_name().name() == name)
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+}
+
+/// The items of a JSON Feed
+pub(crate) fn json_items(value: &serde_json::Value) -> &[serde_json::Value] {
+    value
+        .get("items")
+        .and_then(|items| items.as_array())
+        .map_or(&[], Vec::as_slice)
+}
+
+/// The trimmed, non-empty string value of a JSON Feed member
+pub(crate) fn json_text(value: &serde_json::Value, name: &str) -> Option<String> {
+    value
+        .get(name)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+}
+
+/// The RSS `<link>` text, or the href of an Atom `rel="alternate"` link
+pub(crate) fn alternate_link(node: Node, base: &Url) -> Option<String> {
+    node.children()
+        .filter(|n| n.tag_name().name() == "link")
+        .find_map(|n| match n.attribute("href") {
+            Some(href) if matches!(n.attribute("rel"), None | Some("alternate")) => {
+                base.join(href.trim()).ok().map(String::from)
+            }
+            Some(_) => None,
+            None => n
+                .text()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(String::from),
+        })
+}
+
+/// The newest `pubDate`, `dc:date`, `updated` or `published` date of an item
+///
+/// Atom 0.3 names its dates `modified` and `issued`.
+fn item_date(item: Node) -> Option<DateTime<FixedOffset>> {
+    item.children()
+        .filter(|n| {
+            matches!(
+                n.tag_name().name(),
+                "pubDate" | "date" | "updated" | "published" | "modified" | "issued"
+            )
+        })
+        .filter_map(|n| n.text())
+        .filter_map(parse_date)
+        .max()
+}
+
+/// Parses a feed date, tolerating the usual deviations from the standards
+///
+/// RSS uses RFC 822 dates and Atom RFC 3339 dates, but feeds in the wild
+/// write full month and weekday names, weekdays that do not match the date,
+/// time zone names such as `UTC` or `CEST`, no seconds, no time zone (taken
+/// as UTC), a space instead of `T`, or only a date.
+///
+/// # Arguments
+/// * `text` - The date as written in the feed
+///
+/// # Returns
+/// * `Option<DateTime<FixedOffset>>` - The date, or `None` if it cannot be made sense of
+pub fn parse_date(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if let Ok(date) = DateTime::parse_from_rfc2822(&text) {
+        return Some(date);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(&text) {
+        return Some(date);
+    }
+    parse_rfc822_like(&text).or_else(|| parse_iso_like(&text))
+}
+
+/// Time zone names seen in feeds that RFC 822 does not define
+const ZONE_NAMES: [(&str, &str); 9] = [
+    ("UTC", "+0000"),
+    ("Z", "+0000"),
+    ("BST", "+0100"),
+    ("CET", "+0100"),
+    ("CEST", "+0200"),
+    ("EET", "+0200"),
+    ("EEST", "+0300"),
+    ("IST", "+0530"),
+    ("JST", "+0900"),
+];
+
+fn parse_rfc822_like(text: &str) -> Option<DateTime<FixedOffset>> {
+    // The weekday carries no information and is often wrong, so drop it
+    let text = match text.split_once(',') {
+        Some((weekday, rest)) if weekday.chars().all(|c| c.is_ascii_alphabetic()) => rest,
+        _ => text,
+    };
+    let mut parts: Vec<String> = text.split_whitespace().map(String::from).collect();
+    if parts.len() < 3 {
+        return None;
+    }
+    // Full or oddly cased month names become the three-letter form
+    let month = &parts[1];
+    if month.len() >= 3 && month.chars().all(|c| c.is_ascii_alphabetic()) {
+        parts[1] = month[..1].to_uppercase() + &month[1..3].to_lowercase();
+    }
+    if let Some(zone) = parts.get_mut(4) {
+        if let Some((_, offset)) = ZONE_NAMES.iter().find(|(name, _)| zone == name) {
+            *zone = offset.to_string();
+        }
+    }
+    let text = parts.join(" ");
+
+    for format in ["%d %b %Y %H:%M:%S %z", "%d %b %Y %H:%M %z"] {
+        if let Ok(date) = DateTime::parse_from_str(&text, format) {
+            return Some(date);
+        }
+    }
+    if let Ok(date) = DateTime::parse_from_rfc2822(&text) {
+        return Some(date);
+    }
+    for format in ["%d %b %Y %H:%M:%S", "%d %b %Y %H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(&text, format) {
+            return Some(date.and_utc().fixed_offset());
+        }
+    }
+    NaiveDate::parse_from_str(&text, "%d %b %Y")
+        .ok()
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc().fixed_offset())
+}
+
+fn parse_iso_like(text: &str) -> Option<DateTime<FixedOffset>> {
+    let text = text.replacen(' ', "T", 1);
+    for format in ["%Y-%m-%dT%H:%M:%S%.f%:z", "%Y-%m-%dT%H:%M:%S%.f%z"] {
+        if let Ok(date) = DateTime::parse_from_str(&text, format) {
+            return Some(date);
+        }
+    }
+    let naive = text.trim_end_matches('Z');
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%dT%H:%M"] {
+        if let Ok(date) = NaiveDateTime::parse_from_str(naive, format) {
+            return Some(date.and_utc().fixed_offset());
+        }
+    }
+    NaiveDate::parse_from_str(naive, "%Y-%m-%d")
+        .ok()
+        .map(|date| date.and_time(NaiveTime::MIN).and_utc().fixed_offset())
+}
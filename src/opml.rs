@@ -1,8 +1,9 @@
+use crate::canonical::Canonicalizer;
 use crate::error::Result;
 use crate::Feed;
 use chrono::{DateTime, Local};
 use roxmltree::Node;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Parses an OPML file content into a vector of Feed structs
 ///
@@ -12,17 +13,34 @@ use std::collections::HashMap;
 /// # Returns
 /// * `Result<Vec<Feed>>` - A vector of Feed structs if successful
 pub fn parse_opml(content: &str) -> Result<Vec<Feed>> {
+    parse_opml_with(content, &Canonicalizer::default())
+}
+
+/// Parses an OPML file, treating feeds whose URLs canonicalize alike as duplicates
+///
+/// # Arguments
+/// * `content` - The string content of the OPML file
+/// * `canonical` - Decides which feed URLs are the same; only the first
+///   occurrence of a feed is kept
+///
+/// # Returns
+/// * `Result<Vec<Feed>>` - A vector of Feed structs if successful
+pub fn parse_opml_with(content: &str, canonical: &Canonicalizer) -> Result<Vec<Feed>> {
     let doc = roxmltree::Document::parse(content)?;
-    let mut feeds = Vec::new();
 
     // Recursively process outline nodes
     const MAX_CATEGORY_DEPTH: usize = 100;
+    // Attributes mapped onto dedicated Feed fields
+    const KNOWN_ATTRIBUTES: [&str; 6] = ["type", "text", "title", "xmlUrl", "htmlUrl", "category"];
 
-    fn process_outline(
-        node: Node,
-        current_categories: &[String],
-        feeds: &mut Vec<Feed>,
-    ) -> Result<()> {
+    struct Context<'a> {
+        canonical: &'a Canonicalizer,
+        feeds: Vec<Feed>,
+        // Canonical URLs of the feeds found so far
+        seen: HashSet<String>,
+    }
+
+    fn process_outline(node: Node, current_categories: &[String], cx: &mut Context) -> Result<()> {
         if current_categories.len() >= MAX_CATEGORY_DEPTH {
             return Err(crate::error::OPMLError::CategoryNestingTooDeep(
                 MAX_CATEGORY_DEPTH,
@@ -40,38 +58,47 @@ pub fn parse_opml(content: &str) -> Result<Vec<Feed>> {
                     // Category node (no type or xmlUrl, but has text/title)
                     (None, None, Some(title)) => {
                         categories.push(title.to_string());
-                        process_outline(child, &categories, feeds)?;
+                        process_outline(child, &categories, cx)?;
                     }
                     // Feed node (has xmlUrl or type="rss")
                     (type_attr, Some(xml_url), Some(title))
                         if type_attr.is_none() || type_attr == Some("rss") =>
                     {
-                        // Normalize URL: lowercase, remove trailing slash, standardize to https
-                        let mut normalized_url = xml_url.to_lowercase();
-                        if normalized_url.ends_with('/') {
-                            normalized_url.pop();
-                        }
-                        if normalized_url.starts_with("http://") {
-                            normalized_url = normalized_url.replacen("http://", "https://", 1);
-                        }
-
-                        // Check if we've already seen this normalized URL
-                        if !feeds.iter().any(|f| {
-                            let mut existing = f.xml_url.to_lowercase();
-                            if existing.ends_with('/') {
-                                existing.pop();
-                            }
-                            if existing.starts_with("http://") {
-                                existing = existing.replacen("http://", "https://", 1);
-                            }
-                            existing == normalized_url
-                        }) {
-                            feeds.push(Feed::new(
+                        if cx.seen.insert(cx.canonical.canonicalize(xml_url)) {
+                            let mut feed = Feed::new(
                                 title.to_string(),
                                 xml_url.to_string(),
                                 child.attribute("htmlUrl").map(String::from),
                                 categories.clone(),
-                            ));
+                            );
+                            feed.tags = child
+                                .attribute("category")
+                                .map(|tags| {
+                                    tags.split(',')
+                                        .map(|tag| tag.trim().to_string())
+                                        .filter(|tag| !tag.is_empty())
+                                        .collect()
+                                })
+                                .unwrap_or_default();
+                            for attr in child.attributes() {
+                                let name = match attr.namespace() {
+                                    None if KNOWN_ATTRIBUTES.contains(&attr.name()) => continue,
+                                    None => attr.name().to_string(),
+                                    Some(uri) => {
+                                        let Some(prefix) = child.lookup_prefix(uri) else {
+                                            continue;
+                                        };
+                                        // The xml prefix is bound implicitly
+                                        if prefix != "xml" {
+                                            feed.namespaces
+                                                .insert(prefix.to_string(), uri.to_string());
+                                        }
+                                        format!("{}:{}", prefix, attr.name())
+                                    }
+                                };
+                                feed.attributes.insert(name, attr.value().to_string());
+                            }
+                            cx.feeds.push(feed);
                         }
                     }
                     // Invalid or ignored node
@@ -89,8 +116,20 @@ pub fn parse_opml(content: &str) -> Result<Vec<Feed>> {
         .find(|n| n.has_tag_name("body"))
         .ok_or(crate::error::OPMLError::NoBodyTag)?;
 
-    process_outline(body, &[], &mut feeds)?;
-    Ok(feeds)
+    let mut cx = Context {
+        canonical,
+        feeds: Vec::new(),
+        seen: HashSet::new(),
+    };
+    process_outline(body, &[], &mut cx)?;
+    Ok(cx.feeds)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 pub fn generate_opml(feeds: &[Feed]) -> Result<String> {
@@ -118,16 +157,30 @@ pub fn generate_opml(feeds: &[Feed]) -> Result<String> {
     fn write_feeds(output: &mut String, feeds: &[&Feed], indent: usize) {
         let indent_str = " ".repeat(indent);
         for feed in feeds {
+            let mut extra = String::new();
+            if let Some(url) = &feed.html_url {
+                extra.push_str(&format!(" htmlUrl=\"{}\"", escape_xml(url)));
+            }
+            if !feed.tags.is_empty() {
+                extra.push_str(&format!(
+                    " category=\"{}\"",
+                    escape_xml(&feed.tags.join(","))
+                ));
+            }
+            // Declared on the outline itself, so prefixes of different feeds never clash
+            for (prefix, uri) in &feed.namespaces {
+                extra.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape_xml(uri)));
+            }
+            for (name, value) in &feed.attributes {
+                extra.push_str(&format!(" {}=\"{}\"", name, escape_xml(value)));
+            }
             output.push_str(&format!(
                 r#"{}<outline type="rss" text="{}" title="{}" xmlUrl="{}"{}/>\n"#,
                 indent_str,
-                feed.title,
-                feed.title,
-                feed.xml_url,
-                feed.html_url
-                    .as_ref()
-                    .map(|url| format!(" htmlUrl=\"{}\"", url))
-                    .unwrap_or_default()
+                escape_xml(&feed.title),
+                escape_xml(&feed.title),
+                escape_xml(&feed.xml_url),
+                extra
             ));
         }
     }
@@ -147,7 +200,7 @@ pub fn generate_opml(feeds: &[Feed]) -> Result<String> {
             output.push_str(&format!(
                 "{}<outline text=\"{}\">\n",
                 " ".repeat(current_indent),
-                category
+                escape_xml(category)
             ));
             current_indent += 2;
         }
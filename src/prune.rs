@@ -0,0 +1,279 @@
+//! Removing dead feeds based on validation results
+
+use crate::error::{OPMLError, Result};
+use crate::report::ValidationReport;
+use crate::validation::{IssueKind, ValidationResult, ValidationStatus};
+use crate::Feed;
+use chrono::{DateTime, Local, TimeDelta};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Why a feed is considered dead
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum PruneReason {
+    /// The server answered 404 Not Found
+    #[value(name = "http-404")]
+    #[serde(rename = "http-404")]
+    Http404,
+    /// The server answered 410 Gone
+    #[value(name = "http-410")]
+    #[serde(rename = "http-410")]
+    Http410,
+    /// The host name does not resolve
+    Dns,
+    /// The URL serves something that is not a feed
+    NotAFeed,
+    /// The URL serves audio, video or another binary file
+    BinaryContent,
+    /// The domain serves a parking or for-sale page
+    Parked,
+    /// The newest item is older than the staleness threshold
+    Stale,
+}
+
+impl fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
+}
+
+impl PruneReason {
+    /// Reasons pruned when none are selected explicitly
+    ///
+    /// `dns` is left out: during a resolver or network outage every feed
+    /// fails to resolve, and a single run would empty the whole list.
+    /// `binary-content` is left out too, since a podcast feed whose server
+    /// sends the wrong `Content-Type` looks the same as a dead one.
+    pub fn defaults() -> Vec<PruneReason> {
+        vec![
+            PruneReason::Http404,
+            PruneReason::Http410,
+            PruneReason::NotAFeed,
+            PruneReason::Parked,
+        ]
+    }
+}
+
+/// Determines which prune reason, if any, applies to a validation result
+///
+/// Results saved by versions that did not record an issue kind are
+/// classified by their error message.
+///
+/// # Arguments
+/// * `result` - The validation result for a feed
+/// * `stale_after` - Age of the newest item beyond which a valid feed is stale
+/// * `now` - The reference time for staleness
+pub fn classify(
+    result: &ValidationResult,
+    stale_after: Option<TimeDelta>,
+    now: DateTime<Local>,
+) -> Option<PruneReason> {
+    match (result.status, result.issue_kind()) {
+        (ValidationStatus::Valid, _) => {
+            let newest = result.newest_item()?;
+            let threshold = stale_after?;
+            (now.fixed_offset() - newest > threshold).then_some(PruneReason::Stale)
+        }
+        (ValidationStatus::Stale, _) => Some(PruneReason::Stale),
+        (ValidationStatus::Parked, _) => Some(PruneReason::Parked),
+        (ValidationStatus::Invalid, Some(IssueKind::BinaryContent)) => {
+            Some(PruneReason::BinaryContent)
+        }
+        (ValidationStatus::Invalid, _) => Some(PruneReason::NotAFeed),
+        (_, Some(IssueKind::NotFound)) => Some(PruneReason::Http404),
+        (_, Some(IssueKind::Gone)) => Some(PruneReason::Http410),
+        (_, Some(IssueKind::Dns)) => Some(PruneReason::Dns),
+        (_, Some(_)) => None,
+        _ if result.error.starts_with("HTTP 404") => Some(PruneReason::Http404),
+        _ if result.error.starts_with("HTTP 410") => Some(PruneReason::Http410),
+        _ if result.error.contains("dns error") => Some(PruneReason::Dns),
+        _ => None,
+    }
+}
+
+/// Consecutive failure counts per feed URL, persisted between prune runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PruneHistory {
+    pub feeds: BTreeMap<String, HistoryEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub consecutive_failures: u32,
+    pub last_reason: Option<PruneReason>,
+    pub last_checked: DateTime<Local>,
+}
+
+impl PruneHistory {
+    /// Parses a history file written by `to_json`
+    pub fn from_json(content: &str) -> Result<PruneHistory> {
+        serde_json::from_str(content)
+            .map_err(|e| OPMLError::InvalidStructure(format!("prune history: {}", e)))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| OPMLError::InvalidStructure(format!("prune history: {}", e)))
+    }
+
+    /// Records a check of a feed and returns its consecutive failure count
+    pub fn record(&mut self, url: &str, reason: Option<PruneReason>, now: DateTime<Local>) -> u32 {
+        let entry = self
+            .feeds
+            .entry(url.to_string())
+            .or_insert_with(|| HistoryEntry {
+                consecutive_failures: 0,
+                last_reason: None,
+                last_checked: now,
+            });
+        entry.consecutive_failures = match reason {
+            Some(_) => entry.consecutive_failures + 1,
+            None => 0,
+        };
+        entry.last_reason = reason;
+        entry.last_checked = now;
+        entry.consecutive_failures
+    }
+}
+
+/// Options controlling which feeds `plan_prune` removes
+#[derive(Debug, Clone)]
+pub struct PruneOptions {
+    /// Reasons that cause a feed to be removed
+    pub reasons: Vec<PruneReason>,
+    /// Age of the newest item beyond which a feed is stale
+    pub stale_after: Option<TimeDelta>,
+    /// Consecutive failing runs required before a feed is removed
+    pub grace: u32,
+}
+
+/// A feed selected for removal
+#[derive(Debug, Clone, Serialize)]
+pub struct PrunedFeed {
+    pub title: String,
+    pub url: String,
+    pub categories: Vec<String>,
+    pub reason: PruneReason,
+    pub detail: String,
+    pub consecutive_failures: u32,
+}
+
+/// Splits feeds into those to keep and those to remove
+///
+/// Every checked feed is recorded in `history`, so a feed only goes once it
+/// has failed `options.grace` runs in a row. Feeds without a validation
+/// result are kept.
+///
+/// # Returns
+/// * `(Vec<Feed>, Vec<PrunedFeed>)` - The remaining feeds and the removed ones
+pub fn plan_prune(
+    feeds: Vec<Feed>,
+    results: &[ValidationResult],
+    options: &PruneOptions,
+    history: &mut PruneHistory,
+    now: DateTime<Local>,
+) -> (Vec<Feed>, Vec<PrunedFeed>) {
+    let by_url: HashMap<&str, &ValidationResult> =
+        results.iter().map(|r| (r.url.as_str(), r)).collect();
+
+    let mut kept = Vec::new();
+    let mut removed = Vec::new();
+
+    for feed in feeds {
+        let Some(result) = by_url.get(feed.xml_url.as_str()) else {
+            kept.push(feed);
+            continue;
+        };
+        // The feed was never checked, so its failure history stays as it is
+        if matches!(
+            result.status,
+            ValidationStatus::Cancelled | ValidationStatus::InternalError
+        ) {
+            kept.push(feed);
+            continue;
+        }
+
+        let reason = classify(result, options.stale_after, now)
+            .filter(|reason| options.reasons.contains(reason));
+        let failures = history.record(&feed.xml_url, reason, now);
+
+        match reason {
+            Some(reason) if failures >= options.grace => {
+                let detail = match (reason, result.newest_item()) {
+                    (PruneReason::Stale, Some(newest)) => {
+                        format!("Newest item published {}", newest.format("%Y-%m-%d"))
+                    }
+                    _ => result.error.clone(),
+                };
+                removed.push(PrunedFeed {
+                    title: feed.title,
+                    url: feed.xml_url,
+                    categories: feed.category,
+                    reason,
+                    detail,
+                    consecutive_failures: failures,
+                });
+            }
+            _ => kept.push(feed),
+        }
+    }
+
+    (kept, removed)
+}
+
+/// Formats the removed feeds as a Markdown report
+pub fn format_prune_markdown(source: &str, removed: &[PrunedFeed], dry_run: bool) -> String {
+    let mut output = String::new();
+    output.push_str("# Pruned Feeds\n\n");
+    output.push_str(&format!(
+        "Generated on: {}\n\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+    output.push_str(&format!("Source OPML: {}\n\n", source));
+    if dry_run {
+        output.push_str("Dry run: no feeds were removed.\n\n");
+    }
+
+    if removed.is_empty() {
+        output.push_str("No feeds matched the prune criteria\n");
+        return output;
+    }
+
+    output.push_str("| Feed | URL | Reason | Detail | Consecutive Failures |\n");
+    output.push_str("|------|-----|--------|--------|----------------------|\n");
+    for feed in removed {
+        output.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            feed.title.replace("|", "&#124;"),
+            feed.url,
+            feed.reason,
+            feed.detail.replace("|", "\\|"),
+            feed.consecutive_failures
+        ));
+    }
+    output
+}
+
+/// Reads validation results saved by `validate --format json` or `--format ndjson`
+pub fn parse_validation_results(content: &str) -> Result<Vec<ValidationResult>> {
+    let invalid =
+        |e: serde_json::Error| OPMLError::InvalidStructure(format!("validation results: {}", e));
+
+    if content.trim_start().starts_with('{') {
+        if let Ok(report) = serde_json::from_str::<ValidationReport>(content) {
+            return Ok(report.results);
+        }
+    }
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(invalid))
+        .collect()
+}
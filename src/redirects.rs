@@ -0,0 +1,231 @@
+//! Updating feed URLs that have moved
+
+use crate::validation::ValidationResult;
+use crate::Feed;
+use chrono::Local;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Where a feed's new URL comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum UrlSource {
+    /// The feed announces its new home with `<itunes:new-feed-url>`
+    NewFeedUrl,
+    /// Fetching the feed was answered with 301 or 308 redirects
+    Redirect,
+    /// The feed's atom `link rel="self"` names another URL
+    SelfLink,
+}
+
+impl fmt::Display for UrlSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        write!(f, "{}", name)
+    }
+}
+
+impl UrlSource {
+    /// All sources, from most to least authoritative
+    pub fn all() -> Vec<UrlSource> {
+        vec![
+            UrlSource::NewFeedUrl,
+            UrlSource::Redirect,
+            UrlSource::SelfLink,
+        ]
+    }
+
+    /// Returns true if URLs from this source are only announced by the feed,
+    /// and were never fetched while validating it
+    pub fn is_announced(&self) -> bool {
+        matches!(self, UrlSource::NewFeedUrl | UrlSource::SelfLink)
+    }
+}
+
+/// A feed URL that was rewritten
+#[derive(Debug, Clone, Serialize)]
+pub struct UrlChange {
+    pub title: String,
+    pub from: String,
+    pub to: String,
+    pub source: UrlSource,
+    pub detail: String,
+}
+
+/// Lists the URLs feeds announce as their new home, which need validating
+/// before a feed is moved there
+///
+/// # Arguments
+/// * `results` - Validation results for the feeds
+/// * `sources` - The sources to take new URLs from
+///
+/// # Returns
+/// * `Vec<String>` - Each announced URL once, in order of first appearance
+pub fn announced_urls(results: &[ValidationResult], sources: &[UrlSource]) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for result in results.iter().filter(|r| r.status.is_feed()) {
+        for source in sources.iter().filter(|s| s.is_announced()) {
+            let url = match source {
+                UrlSource::NewFeedUrl => result.new_feed_url.as_ref(),
+                _ => result.self_url.as_ref(),
+            };
+            if let Some(url) = url.filter(|url| !urls.contains(url)) {
+                urls.push(url.clone());
+            }
+        }
+    }
+    urls
+}
+
+/// Determines the URL a feed has moved to, if any
+///
+/// Only feeds that served a well-formed feed, stale or not, are considered.
+/// Redirect targets served that feed; URLs the feed merely announces are
+/// only taken if `serves_feed` confirms they serve a feed too, so a feed is
+/// never moved to a URL that does not. The publisher's explicit
+/// `<itunes:new-feed-url>` wins over redirects, which win over the self link.
+/// A self link is ignored if it would downgrade https to http, since many
+/// feeds carry a stale one.
+///
+/// # Arguments
+/// * `result` - The validation result for the feed
+/// * `sources` - The sources to take new URLs from
+/// * `serves_feed` - Whether an announced URL was validated as a feed
+///
+/// # Returns
+/// * `Option<(String, UrlSource, String)>` - The new URL, its source and a description
+pub fn moved_url(
+    result: &ValidationResult,
+    sources: &[UrlSource],
+    serves_feed: &dyn Fn(&str) -> bool,
+) -> Option<(String, UrlSource, String)> {
+    if !result.status.is_feed() {
+        return None;
+    }
+
+    UrlSource::all()
+        .into_iter()
+        .filter(|source| sources.contains(source))
+        .find_map(|source| {
+            let (url, detail) = match source {
+                UrlSource::NewFeedUrl => (
+                    result.new_feed_url.clone()?,
+                    "itunes:new-feed-url".to_string(),
+                ),
+                UrlSource::Redirect => {
+                    let url = result.permanent_url()?;
+                    let statuses: Vec<String> = result
+                        .redirects
+                        .iter()
+                        .take_while(|r| r.permanent)
+                        .map(|r| r.status.to_string())
+                        .collect();
+                    (
+                        url.to_string(),
+                        format!("permanent redirect ({})", statuses.join(" → ")),
+                    )
+                }
+                UrlSource::SelfLink => {
+                    let url = result.self_url.clone()?;
+                    let downgrade =
+                        url.starts_with("http:") && result.final_url().starts_with("https:");
+                    if downgrade || url == result.final_url() {
+                        return None;
+                    }
+                    (url, "atom self link".to_string())
+                }
+            };
+            if source.is_announced() && !serves_feed(&url) {
+                return None;
+            }
+            (url != result.url).then_some((url, source, detail))
+        })
+}
+
+/// Rewrites the URLs of feeds that have moved
+///
+/// A feed is left alone when its new URL is already in the list, as another
+/// feed's URL or the new URL of an earlier feed, so that no two feeds end up
+/// with the same URL; `dedupe` can then merge them.
+///
+/// # Arguments
+/// * `feeds` - The feeds to update
+/// * `results` - Validation results for the feeds, matched by URL
+/// * `sources` - The sources to take new URLs from
+/// * `targets` - Validation results for the URLs from `announced_urls`
+///
+/// # Returns
+/// * `Vec<UrlChange>` - One entry per rewritten feed
+pub fn fix_redirects(
+    feeds: &mut [Feed],
+    results: &[ValidationResult],
+    sources: &[UrlSource],
+    targets: &[ValidationResult],
+) -> Vec<UrlChange> {
+    let by_url: HashMap<&str, &ValidationResult> =
+        results.iter().map(|r| (r.url.as_str(), r)).collect();
+    let serves_feed = |url: &str| {
+        targets
+            .iter()
+            .any(|target| target.url == url && target.status.is_feed())
+    };
+
+    let mut taken: HashSet<String> = feeds.iter().map(|feed| feed.xml_url.clone()).collect();
+    let mut changes = Vec::new();
+    for feed in feeds.iter_mut() {
+        let Some(result) = by_url.get(feed.xml_url.as_str()) else {
+            continue;
+        };
+        let Some((url, source, detail)) = moved_url(result, sources, &serves_feed) else {
+            continue;
+        };
+        if !taken.insert(url.clone()) {
+            continue;
+        }
+        changes.push(UrlChange {
+            title: feed.title.clone(),
+            from: std::mem::replace(&mut feed.xml_url, url.clone()),
+            to: url,
+            source,
+            detail,
+        });
+    }
+    changes
+}
+
+/// Formats the rewritten URLs as a Markdown report
+pub fn format_url_changes_markdown(source: &str, changes: &[UrlChange], dry_run: bool) -> String {
+    let mut output = String::new();
+    output.push_str("# Updated Feed URLs\n\n");
+    output.push_str(&format!(
+        "Generated on: {}\n\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+    output.push_str(&format!("Source OPML: {}\n\n", source));
+    if dry_run {
+        output.push_str("Dry run: no URLs were changed.\n\n");
+    }
+
+    if changes.is_empty() {
+        output.push_str("No feeds have moved\n");
+        return output;
+    }
+
+    output.push_str("| Feed | Old URL | New URL | Reason |\n");
+    output.push_str("|------|---------|---------|--------|\n");
+    for change in changes {
+        output.push_str(&format!(
+            "| {} | {} | {} | {} |\n",
+            change.title.replace("|", "&#124;"),
+            change.from,
+            change.to,
+            change.detail
+        ));
+    }
+    output
+}
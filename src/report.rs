@@ -1,8 +1,249 @@
+use crate::canonical::Canonicalizer;
+use crate::duplicates::ContentDuplicate;
+use crate::validation::{IssueKind, ValidationResult, ValidationStatus};
 use crate::Feed;
-use chrono::Local;
-use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use url::Url;
 
+/// A section of the Markdown report
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportSection {
+    Summary,
+    Categories,
+    Domains,
+    Duplicates,
+    /// Feeds that are probably the same or belong to a site already subscribed
+    NearDuplicates,
+    Feeds,
+    /// Feed validation results, when validation is requested
+    Validation,
+    /// Feeds that stopped publishing, when validation is requested
+    Staleness,
+    /// Lint warnings, when linting is requested
+    Lint,
+}
+
+impl ReportSection {
+    /// Every section, in report order
+    pub fn all() -> &'static [ReportSection] {
+        &[
+            ReportSection::Summary,
+            ReportSection::Categories,
+            ReportSection::Domains,
+            ReportSection::Duplicates,
+            ReportSection::NearDuplicates,
+            ReportSection::Feeds,
+            ReportSection::Validation,
+            ReportSection::Staleness,
+            ReportSection::Lint,
+        ]
+    }
+}
+
+/// Serializable result of analyzing a feed list
+#[derive(Debug, Serialize)]
+pub struct AnalysisSummary {
+    pub total_feeds: usize,
+    pub unique_feeds: usize,
+    pub duplicate_count: usize,
+    pub category_count: usize,
+    pub duplicates: Vec<FeedSummary>,
+    pub categories: Vec<String>,
+    pub domains: BTreeMap<String, usize>,
+    pub near_duplicates: Vec<NearDuplicate>,
+    /// Feeds serving the same content as another feed, when content was checked
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub content_duplicates: Vec<ContentDuplicate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FeedSummary {
+    pub title: String,
+    pub url: String,
+    pub categories: Vec<String>,
+}
+
+impl From<&Feed> for FeedSummary {
+    fn from(feed: &Feed) -> Self {
+        FeedSummary {
+            title: feed.title.clone(),
+            url: feed.xml_url.clone(),
+            categories: feed.category.clone(),
+        }
+    }
+}
+
+impl AnalysisSummary {
+    pub fn from_feeds(feeds: &[Feed], canonical: &Canonicalizer) -> Self {
+        let (seen_urls, duplicates, categories, domain_counter) =
+            generate_summary(feeds, canonical);
+        let mut categories: Vec<String> = categories.into_iter().collect();
+        categories.sort();
+
+        AnalysisSummary {
+            total_feeds: feeds.len(),
+            unique_feeds: seen_urls.len(),
+            duplicate_count: duplicates.len(),
+            category_count: categories.len(),
+            duplicates: duplicates.into_iter().map(FeedSummary::from).collect(),
+            categories,
+            domains: domain_counter.into_iter().collect(),
+            near_duplicates: find_near_duplicates(feeds),
+            content_duplicates: Vec::new(),
+        }
+    }
+}
+
+/// Serializable result of a validation run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub source: String,
+    pub generated_at: String,
+    /// Feeds given to validation, each of which should have exactly one result
+    #[serde(default)]
+    pub total_input: usize,
+    /// Feeds actually checked, leaving out internal errors and cancelled feeds
+    pub total_checked: usize,
+    /// Feeds whose validation failed with an internal error
+    #[serde(default)]
+    pub total_failed: usize,
+    /// Feeds left unchecked because the run was interrupted
+    #[serde(default)]
+    pub total_cancelled: usize,
+    pub status_counts: BTreeMap<ValidationStatus, usize>,
+    #[serde(default)]
+    pub issue_counts: BTreeMap<IssueKind, usize>,
+    /// Parsed feeds by detected format and version, such as "RSS 2.0"
+    #[serde(default)]
+    pub format_counts: BTreeMap<String, usize>,
+    pub results: Vec<ValidationResult>,
+}
+
+/// Exit code of `validate` when some feeds are invalid
+pub const EXIT_INVALID_FEEDS: i32 = 3;
+
+/// Exit code of `validate` when some feeds could not be fetched
+pub const EXIT_FEED_ERRORS: i32 = 4;
+
+/// Exit code of `validate` when the run was interrupted, as for SIGINT
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+impl ValidationReport {
+    /// Summarizes the results of validating `total_input` feeds
+    ///
+    /// # Arguments
+    /// * `source` - Where the feeds came from
+    /// * `total_input` - How many feeds were given to validation
+    /// * `results` - The results, one per feed
+    pub fn new(source: &str, total_input: usize, results: Vec<ValidationResult>) -> Self {
+        let mut status_counts = BTreeMap::new();
+        let mut issue_counts = BTreeMap::new();
+        let mut format_counts = BTreeMap::new();
+        for result in &results {
+            *status_counts.entry(result.status).or_insert(0) += 1;
+            if let Some(kind) = result.issue_kind() {
+                *issue_counts.entry(kind).or_insert(0) += 1;
+            }
+            if let Some(format) = &result.format {
+                *format_counts.entry(format.to_string()).or_insert(0) += 1;
+            }
+        }
+
+        let count = |status| status_counts.get(&status).copied().unwrap_or(0);
+        let total_failed = count(ValidationStatus::InternalError);
+        let total_cancelled = count(ValidationStatus::Cancelled);
+        ValidationReport {
+            source: source.to_string(),
+            generated_at: Local::now().to_rfc3339(),
+            total_input,
+            total_checked: results
+                .len()
+                .saturating_sub(total_failed)
+                .saturating_sub(total_cancelled),
+            total_failed,
+            total_cancelled,
+            status_counts,
+            issue_counts,
+            format_counts,
+            results,
+        }
+    }
+
+    /// Input feeds that got no result at all
+    pub fn total_missing(&self) -> usize {
+        self.total_input.saturating_sub(self.results.len())
+    }
+
+    /// Returns true if every input feed has exactly one result
+    pub fn is_reconciled(&self) -> bool {
+        self.results.len() == self.total_input
+    }
+
+    /// A line accounting for every input feed: validated, failed, cancelled or missing
+    pub fn reconciliation(&self) -> String {
+        // Counts may come from a hand-edited or older report, so never underflow
+        let validated = self
+            .results
+            .len()
+            .saturating_sub(self.total_failed)
+            .saturating_sub(self.total_cancelled);
+        let mut line = format!(
+            "{} input feeds = {} validated + {} failed",
+            self.total_input, validated, self.total_failed
+        );
+        if self.total_cancelled > 0 {
+            line.push_str(&format!(" + {} cancelled", self.total_cancelled));
+        }
+        if self.total_missing() > 0 {
+            line.push_str(&format!(" + {} missing", self.total_missing()));
+        }
+        let extra = self.results.len().saturating_sub(self.total_input);
+        if extra > 0 {
+            line.push_str(&format!(" (with {} results too many)", extra));
+        }
+        line
+    }
+
+    /// Returns true if the run was interrupted before every feed was checked
+    pub fn is_partial(&self) -> bool {
+        self.total_cancelled > 0
+    }
+
+    /// Process exit code summarizing the run
+    ///
+    /// # Returns
+    /// * `i32` - 0 if every feed is valid, `EXIT_INTERRUPTED` if the run was
+    ///   interrupted, `EXIT_FEED_ERRORS` otherwise if any feed could not be
+    ///   fetched or checked or has no result, `EXIT_INVALID_FEEDS` otherwise if any feed is
+    ///   invalid, moved or parked
+    pub fn exit_code(&self) -> i32 {
+        let has = |status| self.status_counts.get(&status).is_some_and(|&n| n > 0);
+        if self.is_partial() {
+            EXIT_INTERRUPTED
+        } else if has(ValidationStatus::Error)
+            || has(ValidationStatus::InternalError)
+            || !self.is_reconciled()
+        {
+            EXIT_FEED_ERRORS
+        } else if [
+            ValidationStatus::Invalid,
+            ValidationStatus::Moved,
+            ValidationStatus::Parked,
+        ]
+        .into_iter()
+        .any(has)
+        {
+            EXIT_INVALID_FEEDS
+        } else {
+            0
+        }
+    }
+}
+
 fn escape_special_chars(text: &str) -> String {
     text.replace("&", "&amp;")
         .replace("<", "&lt;")
@@ -11,11 +252,17 @@ fn escape_special_chars(text: &str) -> String {
         .replace("|", "&#124;")
 }
 
-pub fn generate_summary(
-    feeds: &[Feed],
+/// Collects the unique URLs, duplicate feeds, categories and domain counts of a feed list
+///
+/// # Arguments
+/// * `feeds` - The feeds to summarize
+/// * `canonical` - Decides which feed URLs are the same
+pub fn generate_summary<'a>(
+    feeds: &'a [Feed],
+    canonical: &Canonicalizer,
 ) -> (
     HashSet<String>,
-    Vec<&Feed>,
+    Vec<&'a Feed>,
     HashSet<String>,
     HashMap<String, usize>,
 ) {
@@ -25,7 +272,7 @@ pub fn generate_summary(
     let mut domain_counter = HashMap::new();
 
     for feed in feeds {
-        if !seen_urls.insert(feed.xml_url.to_lowercase()) {
+        if !seen_urls.insert(canonical.canonicalize(&feed.xml_url)) {
             duplicates.push(feed);
         }
 
@@ -40,7 +287,231 @@ pub fn generate_summary(
     (seen_urls, duplicates, categories, domain_counter)
 }
 
-// In src/report.rs, modify format_markdown_report:
+/// Share of similarity between normalized titles from which feeds of one site are grouped
+const SAME_SITE_SIMILARITY: f64 = 0.75;
+
+/// Share of similarity from which feeds of different sites are grouped
+const CROSS_SITE_SIMILARITY: f64 = 0.9;
+
+/// Second-level labels under which country domains are registered, as in `example.co.uk`
+const SECOND_LEVEL_LABELS: [&str; 8] = ["co", "com", "net", "org", "gov", "edu", "ac", "ne"];
+
+/// Hosting domains whose subdomains belong to unrelated sites
+const SHARED_HOSTS: [&str; 7] = [
+    "blogspot.com",
+    "wordpress.com",
+    "github.io",
+    "substack.com",
+    "tumblr.com",
+    "feedburner.com",
+    "medium.com",
+];
+
+/// Why a feed is considered a near-duplicate of another
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NearDuplicateReason {
+    /// Comments feed of a site whose main feed is subscribed
+    CommentsFeed,
+    /// Category or tag feed of a site whose main feed is subscribed
+    CategoryFeed,
+    /// Same site with a similar title
+    SimilarTitle,
+    /// Different sites with nearly the same title, e.g. after a move
+    SameTitle,
+}
+
+impl std::fmt::Display for NearDuplicateReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            NearDuplicateReason::CommentsFeed => "comments feed",
+            NearDuplicateReason::CategoryFeed => "category feed",
+            NearDuplicateReason::SimilarTitle => "similar title",
+            NearDuplicateReason::SameTitle => "same title on another site",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// A feed that probably duplicates another feed in the list
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDuplicate {
+    pub title: String,
+    pub url: String,
+    pub similar_to_title: String,
+    pub similar_to: String,
+    pub reason: NearDuplicateReason,
+    /// Similarity of the normalized titles, between 0 and 1
+    pub similarity: f64,
+}
+
+/// What part of a site a feed covers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FeedKind {
+    Main,
+    Comments,
+    Category,
+}
+
+/// Returns the registrable domain of a host, e.g. `example.co.uk` for `blog.example.co.uk`
+///
+/// This is an approximation of the public suffix list: two labels are kept,
+/// or three under common second-level labels of country domains and under
+/// hosting domains shared by unrelated sites.
+pub fn registrable_domain(host: &str) -> String {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() <= 2 || host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+
+    let n = labels.len();
+    let last_two = labels[n - 2..].join(".");
+    let keep = if SHARED_HOSTS.contains(&last_two.as_str())
+        || (labels[n - 1].len() == 2 && SECOND_LEVEL_LABELS.contains(&labels[n - 2]))
+    {
+        3
+    } else {
+        2
+    };
+    labels[n - keep.min(n)..].join(".")
+}
+
+/// Lowercases a title and reduces it to words, dropping punctuation
+fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn feed_kind(feed: &Feed, title: &str) -> FeedKind {
+    let path = Url::parse(&feed.xml_url)
+        .map(|url| url.path().to_lowercase())
+        .unwrap_or_default();
+    let has_segment = |names: &[&str]| path.split('/').any(|segment| names.contains(&segment));
+
+    if has_segment(&["comments"]) || title.split(' ').any(|word| word == "comments") {
+        FeedKind::Comments
+    } else if has_segment(&["category", "categories", "tag", "tags", "topics"]) {
+        FeedKind::Category
+    } else {
+        FeedKind::Main
+    }
+}
+
+/// The site a feed belongs to: the registrable domain of its htmlUrl, or of its xmlUrl
+///
+/// A shared hosting domain itself, such as `medium.com` or
+/// `feeds.feedburner.com`, serves unrelated sites under different paths, so
+/// there the first path segment after any `feed` is part of the site, as in
+/// `medium.com/@alice`.
+fn feed_site(feed: &Feed) -> Option<String> {
+    let url = feed
+        .html_url
+        .as_deref()
+        .and_then(|url| Url::parse(url).ok())
+        .or_else(|| Url::parse(&feed.xml_url).ok())?;
+    let host = url.host_str()?.to_lowercase();
+    let bare = host.strip_prefix("www.").unwrap_or(&host);
+    let shared = SHARED_HOSTS
+        .iter()
+        .any(|shared| bare == *shared || bare.strip_prefix("feeds.") == Some(*shared));
+    if !shared {
+        return Some(registrable_domain(&host));
+    }
+
+    let mut segments = url.path_segments()?.filter(|segment| !segment.is_empty());
+    let segment = match segments.next() {
+        Some("feed") => segments.next(),
+        other => other,
+    };
+    Some(match segment {
+        Some(segment) => format!("{}/{}", bare, segment.to_lowercase()),
+        None => bare.to_string(),
+    })
+}
+
+/// Finds feeds that are probably the same as, or part of, an earlier feed
+///
+/// Feeds are grouped by site (the registrable domain of their htmlUrl or
+/// xmlUrl, plus the first path segment on shared hosts) and by the edit
+/// distance of their normalized titles. Within a site, comment and category
+/// feeds are reported against the main feed, whichever comes first, and
+/// feeds whose titles are similar or extend one another ("Foo Blog" and "Foo
+/// Blog - Posts") are reported together. Across sites only nearly identical
+/// titles are reported.
+///
+/// # Returns
+/// * `Vec<NearDuplicate>` - Each later feed with the earlier feed it
+///   resembles most, except that the main feed of a site is always the one
+///   resembled
+pub fn find_near_duplicates(feeds: &[Feed]) -> Vec<NearDuplicate> {
+    let info: Vec<(Option<String>, String, FeedKind)> = feeds
+        .iter()
+        .map(|feed| {
+            let title = normalize_title(&feed.title);
+            let kind = feed_kind(feed, &title);
+            (feed_site(feed), title, kind)
+        })
+        .collect();
+
+    let mut near_duplicates = Vec::new();
+    for (j, feed) in feeds.iter().enumerate() {
+        let (site_j, title_j, kind_j) = &info[j];
+        let mut best: Option<(usize, NearDuplicateReason, f64)> = None;
+
+        for (i, (site_i, title_i, kind_i)) in info[..j].iter().enumerate() {
+            let similarity = strsim::normalized_levenshtein(title_i, title_j);
+            let extends = |a: &str, b: &str| !a.is_empty() && b.starts_with(&format!("{} ", a));
+            let same_site = site_i.is_some() && site_i == site_j;
+
+            let reason = match (kind_i, kind_j) {
+                _ if !same_site => (similarity >= CROSS_SITE_SIMILARITY && title_j.len() >= 6)
+                    .then_some(NearDuplicateReason::SameTitle),
+                (FeedKind::Main, FeedKind::Comments) | (FeedKind::Comments, FeedKind::Main) => {
+                    Some(NearDuplicateReason::CommentsFeed)
+                }
+                (FeedKind::Main, FeedKind::Category) | (FeedKind::Category, FeedKind::Main) => {
+                    Some(NearDuplicateReason::CategoryFeed)
+                }
+                _ => (similarity >= SAME_SITE_SIMILARITY
+                    || extends(title_i, title_j)
+                    || extends(title_j, title_i))
+                .then_some(NearDuplicateReason::SimilarTitle),
+            };
+
+            if let Some(reason) = reason {
+                if best.is_none_or(|(_, _, s)| similarity > s) {
+                    best = Some((i, reason, similarity));
+                }
+            }
+        }
+
+        if let Some((i, reason, similarity)) = best {
+            // The main feed is the one to keep, even when it comes later
+            let (duplicate, kept) = match reason {
+                NearDuplicateReason::CommentsFeed | NearDuplicateReason::CategoryFeed
+                    if *kind_j == FeedKind::Main =>
+                {
+                    (&feeds[i], feed)
+                }
+                _ => (feed, &feeds[i]),
+            };
+            near_duplicates.push(NearDuplicate {
+                title: duplicate.title.clone(),
+                url: duplicate.xml_url.clone(),
+                similar_to_title: kept.title.clone(),
+                similar_to: kept.xml_url.clone(),
+                reason,
+                similarity,
+            });
+        }
+    }
+    near_duplicates
+}
 
 pub fn format_markdown_report(
     feeds: &[Feed],
@@ -48,6 +519,25 @@ pub fn format_markdown_report(
     duplicates: &[&Feed],
     categories: &HashSet<String>,
     domain_counter: &HashMap<String, usize>,
+) -> String {
+    format_markdown_report_sections(
+        feeds,
+        seen_urls,
+        duplicates,
+        categories,
+        domain_counter,
+        ReportSection::all(),
+    )
+}
+
+/// Formats the Markdown report, including only the given sections
+pub fn format_markdown_report_sections(
+    feeds: &[Feed],
+    seen_urls: &HashSet<String>,
+    duplicates: &[&Feed],
+    categories: &HashSet<String>,
+    domain_counter: &HashMap<String, usize>,
+    sections: &[ReportSection],
 ) -> String {
     let mut report = String::new();
     report.push_str("# OPML Analysis Report\n\n");
@@ -57,14 +547,18 @@ pub fn format_markdown_report(
     ));
 
     // Summary section
-    report.push_str("## Summary\n\n");
-    report.push_str(&format!("Total Feeds: {}\n", feeds.len()));
-    report.push_str(&format!("Unique Feeds: {}\n", seen_urls.len()));
-    report.push_str(&format!("Categories Found: {}\n", categories.len()));
-    report.push_str(&format!("Unique Domains: {}\n\n", domain_counter.len()));
+    if sections.contains(&ReportSection::Summary) {
+        report.push_str("## Summary\n\n");
+        report.push_str(&format!("Total Feeds: {}\n", feeds.len()));
+        report.push_str(&format!("Unique Feeds: {}\n", seen_urls.len()));
+        report.push_str(&format!("Categories Found: {}\n", categories.len()));
+        report.push_str(&format!("Unique Domains: {}\n\n", domain_counter.len()));
+    }
 
     // Category breakdown
-    if categories.is_empty() {
+    if !sections.contains(&ReportSection::Categories) {
+        // Section disabled
+    } else if categories.is_empty() {
         report.push_str("No categories found\n\n");
     } else {
         report.push_str("## Categories\n\n");
@@ -89,28 +583,32 @@ pub fn format_markdown_report(
                 count
             ));
         }
-        report.push_str("\n");
+        report.push('\n');
     }
 
     // Top domains
-    report.push_str("## Top Domains\n\n");
-    report.push_str("| Domain | Feed Count |\n");
-    report.push_str("|--------|------------|\n");
-
-    let mut sorted_domains: Vec<_> = domain_counter.iter().collect();
-    sorted_domains.sort_by(|a, b| b.1.cmp(a.1));
-
-    for (domain, count) in sorted_domains.iter().take(10) {
-        report.push_str(&format!(
-            "| {} | {} |\n",
-            escape_special_chars(domain),
-            count
-        ));
+    if sections.contains(&ReportSection::Domains) {
+        report.push_str("## Top Domains\n\n");
+        report.push_str("| Domain | Feed Count |\n");
+        report.push_str("|--------|------------|\n");
+
+        let mut sorted_domains: Vec<_> = domain_counter.iter().collect();
+        sorted_domains.sort_by(|a, b| b.1.cmp(a.1));
+
+        for (domain, count) in sorted_domains.iter().take(10) {
+            report.push_str(&format!(
+                "| {} | {} |\n",
+                escape_special_chars(domain),
+                count
+            ));
+        }
+        report.push('\n');
     }
-    report.push_str("\n");
 
     // Duplicate feeds
-    if duplicates.is_empty() {
+    if !sections.contains(&ReportSection::Duplicates) {
+        // Section disabled
+    } else if duplicates.is_empty() {
         report.push_str("No duplicate feeds found\n\n");
     } else {
         report.push_str("## Duplicate Feeds Found\n\n");
@@ -128,16 +626,300 @@ pub fn format_markdown_report(
                     escaped_categories.join(" > ")
                 ));
             }
-            report.push_str("\n");
+            report.push('\n');
+        }
+    }
+
+    // Likely near-duplicates
+    if sections.contains(&ReportSection::NearDuplicates) {
+        let near_duplicates = find_near_duplicates(feeds);
+        if near_duplicates.is_empty() {
+            report.push_str("No near-duplicate feeds found\n\n");
+        } else {
+            report.push_str("## Possible Near-Duplicates\n\n");
+            report.push_str("| Feed | Similar To | Reason | Title Similarity |\n");
+            report.push_str("|------|------------|--------|------------------|\n");
+            for near in &near_duplicates {
+                report.push_str(&format!(
+                    "| {} ({}) | {} ({}) | {} | {:.0}% |\n",
+                    escape_special_chars(&near.title),
+                    escape_special_chars(&near.url),
+                    escape_special_chars(&near.similar_to_title),
+                    escape_special_chars(&near.similar_to),
+                    near.reason,
+                    near.similarity * 100.0
+                ));
+            }
+            report.push('\n');
         }
     }
 
     // List of all feeds
-    report.push_str("## All Feeds\n\n");
-    for feed in feeds {
-        report.push_str(&format!("- {}\n", escape_special_chars(&feed.title)));
+    if sections.contains(&ReportSection::Feeds) {
+        report.push_str("## All Feeds\n\n");
+        for feed in feeds {
+            report.push_str(&format!("- {}\n", escape_special_chars(&feed.title)));
+        }
+        report.push('\n');
     }
-    report.push_str("\n");
 
     report
 }
+
+/// Formats an analysis summary as human-readable text
+pub fn format_analysis_text(summary: &AnalysisSummary) -> String {
+    let mut output = String::new();
+    output.push_str("\n📊 OPML Analysis Report\n");
+    output.push_str(&format!("Total Feeds: {}\n", summary.total_feeds));
+    output.push_str(&format!("Unique Feeds: {}\n", summary.unique_feeds));
+    output.push_str(&format!("Duplicates: {}\n", summary.duplicate_count));
+    output.push_str(&format!("Total Categories: {}\n", summary.category_count));
+
+    if !summary.duplicates.is_empty() {
+        output.push_str("\n🔄 Duplicate Feeds:\n");
+        for feed in &summary.duplicates {
+            output.push_str(&format!("  - {} ({})\n", feed.title, feed.url));
+            if !feed.categories.is_empty() {
+                output.push_str(&format!(
+                    "    Categories: {}\n",
+                    feed.categories.join(" > ")
+                ));
+            }
+        }
+    }
+
+    if !summary.near_duplicates.is_empty() {
+        output.push_str("\n🔍 Possible Near-Duplicates:\n");
+        for near in &summary.near_duplicates {
+            output.push_str(&format!(
+                "  - {} ({})\n    Resembles {} ({}): {}\n",
+                near.title, near.url, near.similar_to_title, near.similar_to, near.reason
+            ));
+        }
+    }
+
+    if !summary.content_duplicates.is_empty() {
+        output.push_str("\n🧬 Content Duplicates:\n");
+        for duplicate in &summary.content_duplicates {
+            output.push_str(&format!(
+                "  - {} ({})\n    Same as {} ({}), {:.0}% confidence\n",
+                duplicate.title,
+                duplicate.url,
+                duplicate.duplicate_of_title,
+                duplicate.duplicate_of,
+                duplicate.confidence * 100.0
+            ));
+        }
+    }
+
+    output
+}
+
+/// Formats a validation run as a Markdown report
+pub fn format_validation_markdown(report: &ValidationReport) -> String {
+    let mut output = String::new();
+    output.push_str("# Feed Validation Report\n\n");
+    output.push_str(&format!(
+        "Generated on: {}\n\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+    output.push_str(&format!("Source OPML: {}\n\n", report.source));
+    if report.is_partial() {
+        output.push_str(&format!(
+            "**Partial report:** the run was interrupted before {} feeds were checked.\n\n",
+            report.total_cancelled
+        ));
+    }
+
+    output.push_str("## Summary\n\n");
+    output.push_str(&format!(
+        "- Total feeds checked: {}\n",
+        report.total_checked
+    ));
+    output.push_str(&format!("- Failed: {}\n", report.total_failed));
+    output.push_str(&format!("- Reconciliation: {}\n", report.reconciliation()));
+    for (status, count) in &report.status_counts {
+        output.push_str(&format!("- {}: {}\n", status, count));
+    }
+    output.push('\n');
+
+    if !report.format_counts.is_empty() {
+        output.push_str("### Formats\n\n");
+        for (format, count) in &report.format_counts {
+            output.push_str(&format!("- {}: {}\n", format, count));
+        }
+        output.push('\n');
+    }
+
+    if !report.issue_counts.is_empty() {
+        output.push_str("### Issues\n\n");
+        for (kind, count) in &report.issue_counts {
+            output.push_str(&format!("- {}: {}\n", kind, count));
+        }
+        output.push('\n');
+    }
+
+    // Stale feeds get their own section listing how long they have been silent
+    for status in ValidationStatus::all()
+        .iter()
+        .filter(|s| **s != ValidationStatus::Stale)
+    {
+        let mut status_results: Vec<_> = report
+            .results
+            .iter()
+            .filter(|r| r.status == *status)
+            .collect();
+
+        if !status_results.is_empty() {
+            // Group problems of the same kind together
+            status_results.sort_by_key(|r| r.issue_kind());
+            let name = status.as_str().replace('-', " ");
+            let status_capitalized = name[0..1].to_uppercase() + &name[1..];
+            output.push_str(&format!("## {} Feeds\n\n", status_capitalized));
+            output.push_str("| Feed | URL | Issue | Error | Categories |\n");
+            output.push_str("|------|-----|-------|-------|------------|\n");
+
+            for result in status_results {
+                let categories = result.categories.join(" > ");
+                let error = result.error.replace("|", "\\|");
+                let feed = result.feed.replace("|", "&#124;");
+                let issue = result
+                    .issue_kind()
+                    .map(|kind| kind.to_string())
+                    .unwrap_or_default();
+                output.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    feed, result.url, issue, error, categories
+                ));
+            }
+            output.push('\n');
+        }
+    }
+
+    if report
+        .results
+        .iter()
+        .any(|r| r.status == ValidationStatus::Stale)
+    {
+        output.push_str(&format_staleness_markdown(&report.results, Utc::now()));
+    }
+
+    let redirected: Vec<_> = report
+        .results
+        .iter()
+        .filter(|r| !r.redirects.is_empty())
+        .collect();
+    if !redirected.is_empty() {
+        output.push_str("## Redirected Feeds\n\n");
+        output.push_str("| Feed | URL | Redirects | Final URL |\n");
+        output.push_str("|------|-----|-----------|-----------|\n");
+        for result in redirected {
+            let hops: Vec<String> = result
+                .redirects
+                .iter()
+                .map(|r| {
+                    let kind = if r.permanent {
+                        "permanent"
+                    } else {
+                        "temporary"
+                    };
+                    format!("{} {}", r.status, kind)
+                })
+                .collect();
+            output.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                result.feed.replace("|", "&#124;"),
+                result.url,
+                hops.join(" → "),
+                result.final_url()
+            ));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Formats the stale feeds among validation results as a Markdown section
+///
+/// # Arguments
+/// * `results` - Validation results, of which only stale feeds are listed
+/// * `now` - The time silence is measured up to
+///
+/// # Returns
+/// * `String` - The section, listing the longest silent feeds first
+pub fn format_staleness_markdown(results: &[ValidationResult], now: DateTime<Utc>) -> String {
+    let mut output = String::from("## Stale Feeds\n\n");
+    let mut stale: Vec<_> = results
+        .iter()
+        .filter(|r| r.status == ValidationStatus::Stale)
+        .filter_map(|r| Some((r, r.newest_item()?)))
+        .collect();
+    if stale.is_empty() {
+        output.push_str("No stale feeds\n\n");
+        return output;
+    }
+    stale.sort_by_key(|(_, newest)| *newest);
+
+    output.push_str("| Feed | URL | Newest Item | Days Silent | Categories |\n");
+    output.push_str("|------|-----|-------------|-------------|------------|\n");
+    for (result, newest) in stale {
+        output.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            result.feed.replace("|", "&#124;"),
+            result.url,
+            newest.format("%Y-%m-%d"),
+            (now.fixed_offset() - newest).num_days(),
+            result.categories.join(" > ")
+        ));
+    }
+    output.push('\n');
+    output
+}
+
+/// Formats a validation run as human-readable text, listing feeds with problems
+pub fn format_validation_text(report: &ValidationReport) -> String {
+    let mut output = String::new();
+    output.push_str("\n🔍 Feed Validation Report\n");
+    if report.is_partial() {
+        output.push_str(&format!(
+            "Partial report: interrupted before {} feeds were checked\n",
+            report.total_cancelled
+        ));
+    }
+    output.push_str(&format!("Total feeds checked: {}\n", report.total_checked));
+    output.push_str(&format!("Failed: {}\n", report.total_failed));
+    output.push_str(&format!("Reconciliation: {}\n", report.reconciliation()));
+    for (status, count) in &report.status_counts {
+        output.push_str(&format!("  {}: {}\n", status, count));
+    }
+    if !report.format_counts.is_empty() {
+        let formats: Vec<String> = report
+            .format_counts
+            .iter()
+            .map(|(format, count)| format!("{} {}", format, count))
+            .collect();
+        output.push_str(&format!("Formats: {}\n", formats.join(", ")));
+    }
+
+    let problems: Vec<_> = report
+        .results
+        .iter()
+        .filter(|r| r.status != ValidationStatus::Valid)
+        .collect();
+    if !problems.is_empty() {
+        output.push_str("\n⚠️  Feeds with problems:\n");
+        for result in problems {
+            let label = result
+                .issue_kind()
+                .map(|kind| kind.to_string())
+                .unwrap_or_else(|| result.status.to_string());
+            output.push_str(&format!(
+                "  - [{}] {} ({})\n    {}\n",
+                label, result.feed, result.url, result.error
+            ));
+        }
+    }
+
+    output
+}
@@ -0,0 +1,135 @@
+//! When and how long to wait before retrying a failed feed request
+//!
+//! Failures are retried according to their `IssueKind`, with exponential
+//! backoff between attempts. Servers that answer 429 or 503 with a
+//! `Retry-After` header are taken at their word, unless they ask for a wait
+//! longer than the policy allows, in which case the feed fails right away.
+
+use crate::validation::IssueKind;
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Default number of attempts per request, including the first
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+
+/// Default delay before the first retry, in milliseconds
+pub const DEFAULT_BASE_DELAY_MS: u64 = 1000;
+
+/// Default longest delay between attempts, in milliseconds
+pub const DEFAULT_MAX_DELAY_MS: u64 = 30_000;
+
+/// Default share of each delay that is randomized
+pub const DEFAULT_JITTER: f64 = 0.1;
+
+/// Controls how failed requests are retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts per request, including the first; 1 disables retries
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub base_delay: Duration,
+    /// Longest delay between attempts, including delays asked for by `Retry-After`
+    pub max_delay: Duration,
+    /// Share of each delay, between 0 and 1, that is randomly taken off so
+    /// that feeds failing together are not retried together
+    pub jitter: f64,
+    /// Failures that are worth retrying
+    pub retry_on: Vec<IssueKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_BASE_DELAY_MS),
+            max_delay: Duration::from_millis(DEFAULT_MAX_DELAY_MS),
+            jitter: DEFAULT_JITTER,
+            retry_on: RetryPolicy::default_retry_on().to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Failures retried unless configured otherwise
+    pub fn default_retry_on() -> &'static [IssueKind] {
+        &[
+            IssueKind::Dns,
+            IssueKind::Tls,
+            IssueKind::Connect,
+            IssueKind::RateLimited,
+            IssueKind::ServerError,
+            IssueKind::BodyRead,
+        ]
+    }
+
+    /// A policy that never retries
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Decides whether and when to retry after a failed attempt
+    ///
+    /// # Arguments
+    /// * `attempt` - The number of the attempt that failed, starting at 1
+    /// * `kind` - What went wrong
+    /// * `retry_after` - The wait asked for by the server, if any
+    ///
+    /// # Returns
+    /// * `Option<Duration>` - How long to wait before the next attempt, or
+    ///   `None` to give up
+    pub fn delay(
+        &self,
+        attempt: u32,
+        kind: IssueKind,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.retry_on.contains(&kind) {
+            return None;
+        }
+
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let backoff = backoff.mul_f64(1.0 - self.jitter.clamp(0.0, 1.0) * random_fraction());
+
+        match retry_after {
+            Some(wait) if wait > self.max_delay => None,
+            Some(wait) => Some(wait.max(backoff)),
+            None => Some(backoff),
+        }
+    }
+}
+
+/// Parses a `Retry-After` header value
+///
+/// # Arguments
+/// * `value` - Either a number of seconds or an HTTP date
+/// * `now` - The time an HTTP date is measured from
+///
+/// # Returns
+/// * `Option<Duration>` - The wait asked for, zero for dates in the past, or
+///   `None` if the value cannot be parsed
+pub fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// A number in `[0, 1)` that differs between calls
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,148 @@
+//! Politeness limits for fetching many feeds at once
+//!
+//! Feed lists often hold hundreds of feeds on the same host (medium.com,
+//! substack.com, feedburner), so capping only the total number of requests
+//! still hammers a few servers. A `Scheduler` hands out permits that bound
+//! requests in flight overall and per host, and spaces out the starts of
+//! requests to the same host.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use url::Url;
+
+/// Default maximum number of requests in flight
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+/// Default maximum number of requests in flight to one host
+pub const DEFAULT_PER_HOST: usize = 2;
+
+/// Default minimum time between the starts of two requests to one host, in milliseconds
+pub const DEFAULT_HOST_DELAY_MS: u64 = 250;
+
+/// Limits applied by a `Scheduler`; a limit of 0 means unlimited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Maximum number of requests in flight
+    pub concurrency: usize,
+    /// Maximum number of requests in flight to one host
+    pub per_host: usize,
+    /// Minimum time between the starts of two requests to one host
+    pub host_delay: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            concurrency: DEFAULT_CONCURRENCY,
+            per_host: DEFAULT_PER_HOST,
+            host_delay: Duration::from_millis(DEFAULT_HOST_DELAY_MS),
+        }
+    }
+}
+
+/// Permission to send one request, released when dropped
+pub struct Permit {
+    _host: OwnedSemaphorePermit,
+    _global: OwnedSemaphorePermit,
+}
+
+struct Host {
+    slots: Arc<Semaphore>,
+    next_start: Mutex<Instant>,
+}
+
+/// Bounds concurrent requests overall and per host
+///
+/// The scheduler is shared between tasks, usually behind an `Arc`.
+pub struct Scheduler {
+    limits: Limits,
+    global: Arc<Semaphore>,
+    hosts: Mutex<HashMap<String, Arc<Host>>>,
+}
+
+fn permits(limit: usize) -> usize {
+    if limit == 0 {
+        Semaphore::MAX_PERMITS
+    } else {
+        limit
+    }
+}
+
+/// The key requests are grouped by: the lowercased host, or the whole URL
+/// if it has none
+fn host_key(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_lowercase))
+        .unwrap_or_else(|| url.to_string())
+}
+
+impl Scheduler {
+    pub fn new(limits: Limits) -> Self {
+        Scheduler {
+            limits,
+            global: Arc::new(Semaphore::new(permits(limits.concurrency))),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The limits this scheduler applies
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    fn host(&self, url: &str) -> Arc<Host> {
+        let mut hosts = self.hosts.lock().expect("scheduler lock poisoned");
+        hosts
+            .entry(host_key(url))
+            .or_insert_with(|| {
+                Arc::new(Host {
+                    slots: Arc::new(Semaphore::new(permits(self.limits.per_host))),
+                    next_start: Mutex::new(Instant::now()),
+                })
+            })
+            .clone()
+    }
+
+    /// Waits until a request to `url` may be sent
+    ///
+    /// A host slot is taken before a global one, so requests waiting for a
+    /// busy host do not keep requests to other hosts from running.
+    ///
+    /// # Arguments
+    /// * `url` - The URL about to be requested
+    ///
+    /// # Returns
+    /// * `Permit` - Held for the duration of the request
+    pub async fn acquire(&self, url: &str) -> Permit {
+        let host = self.host(url);
+        let host_permit = host
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("scheduler semaphore closed");
+        let global_permit = self
+            .global
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("scheduler semaphore closed");
+
+        let start = {
+            let mut next_start = host.next_start.lock().expect("scheduler lock poisoned");
+            let start = (*next_start).max(Instant::now());
+            *next_start = start + self.limits.host_delay;
+            start
+        };
+        tokio::time::sleep_until(start).await;
+
+        Permit {
+            _host: host_permit,
+            _global: global_permit,
+        }
+    }
+}
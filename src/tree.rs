@@ -0,0 +1,183 @@
+use crate::Feed;
+use std::collections::HashMap;
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// A node in the category hierarchy built from `Feed.category` paths
+#[derive(Debug, Clone, Default)]
+pub struct CategoryNode {
+    pub name: String,
+    pub children: Vec<CategoryNode>,
+    pub feeds: Vec<Feed>,
+}
+
+/// Options controlling how a category tree is rendered
+#[derive(Debug, Clone, Default)]
+pub struct TreeOptions {
+    /// Show individual feeds as leaves under their category
+    pub show_feeds: bool,
+    /// Maximum depth to render; top-level categories are at depth 1
+    pub max_depth: Option<usize>,
+    /// Emit ANSI color codes
+    pub color: bool,
+}
+
+impl CategoryNode {
+    /// Builds the category hierarchy, keeping categories in document order
+    ///
+    /// # Arguments
+    /// * `feeds` - The feeds to arrange by their category paths
+    ///
+    /// # Returns
+    /// * `CategoryNode` - An unnamed root node holding the hierarchy
+    pub fn from_feeds(feeds: &[Feed]) -> Self {
+        let mut root = CategoryNode::default();
+        for feed in feeds {
+            let mut node = &mut root;
+            for category in &feed.category {
+                let index = match node.children.iter().position(|c| &c.name == category) {
+                    Some(index) => index,
+                    None => {
+                        node.children.push(CategoryNode {
+                            name: category.clone(),
+                            ..Default::default()
+                        });
+                        node.children.len() - 1
+                    }
+                };
+                node = &mut node.children[index];
+            }
+            node.feeds.push(feed.clone());
+        }
+        root
+    }
+
+    /// Number of feeds placed directly in this category
+    pub fn direct_count(&self) -> usize {
+        self.feeds.len()
+    }
+
+    /// Number of feeds in this category and all of its subcategories
+    pub fn total_count(&self) -> usize {
+        self.feeds.len()
+            + self
+                .children
+                .iter()
+                .map(CategoryNode::total_count)
+                .sum::<usize>()
+    }
+}
+
+/// Renders a category tree as text using box-drawing characters
+///
+/// # Arguments
+/// * `root` - The root node returned by `CategoryNode::from_feeds`
+/// * `options` - Rendering options
+/// * `statuses` - Validation status per feed URL, shown as a marker after each feed
+///
+/// # Returns
+/// * `String` - The rendered tree
+pub fn render_tree(
+    root: &CategoryNode,
+    options: &TreeOptions,
+    statuses: &HashMap<String, String>,
+) -> String {
+    let mut output = format!(
+        "{} {}\n",
+        paint("Feeds", BOLD, options.color),
+        counts(root, options.color)
+    );
+    render_children(root, "", 1, options, statuses, &mut output);
+    output
+}
+
+fn render_children(
+    node: &CategoryNode,
+    prefix: &str,
+    depth: usize,
+    options: &TreeOptions,
+    statuses: &HashMap<String, String>,
+    output: &mut String,
+) {
+    if options.max_depth.is_some_and(|max| depth > max) {
+        return;
+    }
+
+    let feeds: &[Feed] = if options.show_feeds { &node.feeds } else { &[] };
+    let entries = feeds.len() + node.children.len();
+
+    for (i, feed) in feeds.iter().enumerate() {
+        let connector = if i + 1 == entries {
+            "└── "
+        } else {
+            "├── "
+        };
+        output.push_str(&format!("{}{}{}", prefix, connector, feed.title));
+        if let Some(status) = statuses.get(&feed.xml_url) {
+            output.push(' ');
+            output.push_str(&status_marker(status, options.color));
+        }
+        output.push('\n');
+    }
+
+    for (i, child) in node.children.iter().enumerate() {
+        let last = feeds.len() + i + 1 == entries;
+        let (connector, extension) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        output.push_str(&format!(
+            "{}{}{} {}\n",
+            prefix,
+            connector,
+            paint(&child.name, BOLD, options.color),
+            counts(child, options.color)
+        ));
+        render_children(
+            child,
+            &format!("{}{}", prefix, extension),
+            depth + 1,
+            options,
+            statuses,
+            output,
+        );
+    }
+}
+
+fn counts(node: &CategoryNode, color: bool) -> String {
+    let text = if node.children.is_empty() {
+        format!("({})", node.total_count())
+    } else {
+        format!(
+            "({} total, {} direct)",
+            node.total_count(),
+            node.direct_count()
+        )
+    };
+    paint(&text, DIM, color)
+}
+
+fn status_marker(status: &str, color: bool) -> String {
+    match status {
+        "valid" => paint("✓", GREEN, color),
+        "stale" => paint("✓ stale", YELLOW, color),
+        "moved" => paint("→ moved", YELLOW, color),
+        "parked" => paint("✗ parked", RED, color),
+        "invalid" => paint("✗ invalid", RED, color),
+        other => paint(&format!("! {}", other), YELLOW, color),
+    }
+}
+
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
@@ -1,134 +1,1246 @@
+use crate::cache::HttpCache;
+use crate::config::default_user_agent;
+use crate::fetcher::{FeedFetcher, FetchRequest};
+use crate::format::{looks_like_json, FeedFormat};
+use crate::html::{
+    discover_feeds, is_html_content_type, is_not_found_page, looks_like_html, page_title,
+    parking_signature,
+};
+use crate::metadata::{self, alternate_link, child_text, json_items, json_text, FeedMetadata};
+use crate::retry::{parse_retry_after, RetryPolicy};
+use crate::scheduler::{Limits, Scheduler};
 use crate::{Feed, Result};
-use reqwest::Client;
-use serde::Serialize;
+use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use clap::ValueEnum;
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use reqwest::header::{CONTENT_TYPE, ETAG, LAST_MODIFIED, LOCATION, RETRY_AFTER};
+use reqwest::{redirect, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::watch;
 use tokio::time::{sleep, Duration};
-use std::time::Instant;
+use url::Url;
 
-#[derive(Debug, Serialize)]
+/// Redirects followed before a feed is reported as an error
+const MAX_REDIRECTS: usize = 10;
+
+/// Request timeout used when none is configured, in seconds
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+/// Largest response body read when none is configured, in bytes
+pub const DEFAULT_MAX_BODY_SIZE: u64 = 20 * 1024 * 1024;
+
+/// Items whose GUIDs are kept in a feed's identity
+const IDENTITY_ITEMS: usize = 50;
+
+const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
+const ITUNES_NS: &str = "http://www.itunes.com/dtds/podcast-1.0.dtd";
+
+/// Overall outcome of validating a feed
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ValidationStatus {
+    /// The URL serves a well-formed RSS or Atom feed
+    #[default]
+    Valid,
+    /// A well-formed feed whose newest item is older than the staleness threshold
+    Stale,
+    /// An HTML page advertising a feed at another URL is served instead of the feed
+    Moved,
+    /// The domain serves a parking or for-sale page
+    Parked,
+    /// The URL answers, but not with a usable feed
+    Invalid,
+    /// The feed could not be fetched
+    Error,
+    /// Validation itself failed, for reasons given in the error
+    InternalError,
+    /// The run was interrupted before the feed was checked
+    Cancelled,
+}
+
+impl ValidationStatus {
+    /// Every status, in report order
+    pub fn all() -> &'static [ValidationStatus] {
+        &[
+            ValidationStatus::Valid,
+            ValidationStatus::Stale,
+            ValidationStatus::Moved,
+            ValidationStatus::Parked,
+            ValidationStatus::Invalid,
+            ValidationStatus::Error,
+            ValidationStatus::InternalError,
+            ValidationStatus::Cancelled,
+        ]
+    }
+
+    /// Returns true if the URL served a well-formed feed, stale or not
+    pub fn is_feed(self) -> bool {
+        matches!(self, ValidationStatus::Valid | ValidationStatus::Stale)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValidationStatus::Valid => "valid",
+            ValidationStatus::Stale => "stale",
+            ValidationStatus::Moved => "moved",
+            ValidationStatus::Parked => "parked",
+            ValidationStatus::Invalid => "invalid",
+            ValidationStatus::Error => "error",
+            ValidationStatus::InternalError => "internal-error",
+            ValidationStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for ValidationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What went wrong when validating a feed
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum IssueKind {
+    /// The feed URL cannot be parsed
+    InvalidUrl,
+    /// The host name does not resolve
+    Dns,
+    /// The TLS handshake or certificate check failed
+    Tls,
+    /// The connection was refused or dropped
+    Connect,
+    /// The server did not answer in time
+    Timeout,
+    /// More redirects than are followed
+    TooManyRedirects,
+    /// 404 Not Found
+    NotFound,
+    /// 410 Gone
+    Gone,
+    /// 401 Unauthorized or 403 Forbidden
+    AccessDenied,
+    /// 429 Too Many Requests
+    RateLimited,
+    /// Any other 4xx status
+    ClientError,
+    /// A 5xx status that persisted through retries
+    ServerError,
+    /// A status that is neither success, redirect nor error
+    UnexpectedStatus,
+    /// The response body could not be read
+    BodyRead,
+    /// The response body is larger than the configured maximum
+    ResponseTooLarge,
+    /// Audio, video, an image or another binary file is served instead of a feed
+    BinaryContent,
+    /// An HTML page is served instead of a feed
+    HtmlPage,
+    /// An HTML page whose autodiscovery links name another feed URL
+    Moved,
+    /// A domain parking or for-sale page
+    Parked,
+    /// An HTML "not found" page served with a success status
+    Soft404,
+    /// The document is not well-formed XML
+    MalformedXml,
+    /// The document looks like JSON but does not parse
+    MalformedJson,
+    /// A well-formed document that is not an RSS, Atom or JSON feed
+    NotAFeed,
+    /// The feed has not published anything for longer than the staleness threshold
+    Stale,
+    /// Validating the feed failed unexpectedly, for example in a crashed task
+    Internal,
+    /// The run was interrupted before the feed was checked
+    Cancelled,
+    /// Any other failure
+    Other,
+}
+
+impl IssueKind {
+    /// The status a feed with this issue gets
+    pub fn status(self) -> ValidationStatus {
+        match self {
+            IssueKind::HtmlPage
+            | IssueKind::BinaryContent
+            | IssueKind::MalformedXml
+            | IssueKind::MalformedJson
+            | IssueKind::Soft404
+            | IssueKind::NotAFeed => ValidationStatus::Invalid,
+            IssueKind::Moved => ValidationStatus::Moved,
+            IssueKind::Parked => ValidationStatus::Parked,
+            IssueKind::Stale => ValidationStatus::Stale,
+            IssueKind::Internal => ValidationStatus::InternalError,
+            IssueKind::Cancelled => ValidationStatus::Cancelled,
+            _ => ValidationStatus::Error,
+        }
+    }
+
+    /// The name used in serialized output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IssueKind::InvalidUrl => "invalid-url",
+            IssueKind::Dns => "dns",
+            IssueKind::Tls => "tls",
+            IssueKind::Connect => "connect",
+            IssueKind::Timeout => "timeout",
+            IssueKind::TooManyRedirects => "too-many-redirects",
+            IssueKind::NotFound => "not-found",
+            IssueKind::Gone => "gone",
+            IssueKind::AccessDenied => "access-denied",
+            IssueKind::RateLimited => "rate-limited",
+            IssueKind::ClientError => "client-error",
+            IssueKind::ServerError => "server-error",
+            IssueKind::UnexpectedStatus => "unexpected-status",
+            IssueKind::BodyRead => "body-read",
+            IssueKind::ResponseTooLarge => "response-too-large",
+            IssueKind::BinaryContent => "binary-content",
+            IssueKind::HtmlPage => "html-page",
+            IssueKind::Moved => "moved",
+            IssueKind::Parked => "parked",
+            IssueKind::Soft404 => "soft-404",
+            IssueKind::MalformedXml => "malformed-xml",
+            IssueKind::MalformedJson => "malformed-json",
+            IssueKind::NotAFeed => "not-a-feed",
+            IssueKind::Stale => "stale",
+            IssueKind::Internal => "internal",
+            IssueKind::Cancelled => "cancelled",
+            IssueKind::Other => "other",
+        }
+    }
+
+    /// Classifies an unsuccessful HTTP status
+    pub fn from_http_status(status: StatusCode) -> IssueKind {
+        match status.as_u16() {
+            404 => IssueKind::NotFound,
+            410 => IssueKind::Gone,
+            401 | 403 => IssueKind::AccessDenied,
+            429 => IssueKind::RateLimited,
+            400..=499 => IssueKind::ClientError,
+            500..=599 => IssueKind::ServerError,
+            _ => IssueKind::UnexpectedStatus,
+        }
+    }
+
+    /// Classifies a failed request by walking the error's causes
+    pub(crate) fn from_request_error(error: &reqwest::Error) -> IssueKind {
+        if error.is_timeout() {
+            return IssueKind::Timeout;
+        }
+        if error.is_redirect() {
+            return IssueKind::TooManyRedirects;
+        }
+
+        let mut causes = Vec::new();
+        let mut source: Option<&dyn std::error::Error> = Some(error);
+        while let Some(cause) = source {
+            causes.push(cause.to_string().to_lowercase());
+            source = cause.source();
+        }
+        let mentions = |needles: &[&str]| {
+            causes
+                .iter()
+                .any(|cause| needles.iter().any(|needle| cause.contains(needle)))
+        };
+
+        if mentions(&["dns error", "failed to lookup address"]) {
+            IssueKind::Dns
+        } else if mentions(&["certificate", "tls", "ssl", "handshake"]) {
+            IssueKind::Tls
+        } else if error.is_connect() {
+            IssueKind::Connect
+        } else if error.is_builder() {
+            IssueKind::InvalidUrl
+        } else {
+            IssueKind::Other
+        }
+    }
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Structured details of a failed validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    /// Status code of the last response, when one was received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    /// The URL the failure happened at, after redirects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+    /// Requests repeated after transient failures
+    #[serde(default)]
+    pub retries: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ValidationResult {
     pub feed: String,
     pub url: String,
-    pub status: String,
+    pub status: ValidationStatus,
+    /// Human-readable description of the issue, empty for valid feeds
     pub error: String,
+    /// What went wrong, for feeds that are not valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<ValidationIssue>,
     pub categories: Vec<String>,
+    /// What the feed declares about itself, for feeds that could be parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<FeedMetadata>,
+    /// The format and version detected, for feeds that could be parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<FeedFormat>,
+    /// Redirects followed from `url`, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<Redirect>,
+    /// URL announced by `<itunes:new-feed-url>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_feed_url: Option<String>,
+    /// URL of the feed's atom `link rel="self"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub self_url: Option<String>,
+    /// Feed URL advertised by the HTML page served instead of the feed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discovered_url: Option<String>,
+    /// When the feed's content last changed, known when validating with a cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_changed: Option<DateTime<Utc>>,
+    /// The server answered `304 Not Modified` and the cached copy was checked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub not_modified: bool,
+    /// What the feed's content identifies itself as; kept in memory only
+    #[serde(skip)]
+    pub identity: Option<FeedIdentity>,
+    /// The fetched document, kept only by `fetch_feed_document`
+    #[serde(skip)]
+    pub document: Option<FetchedDocument>,
 }
 
-pub async fn validate_feed(feed: &Feed, client: &Client) -> Result<ValidationResult> {
-    let mut attempts = 0;
-    let max_attempts = 5;
-    let mut backoff = Duration::from_secs(1);
+/// A response body as received, for checks beyond validation such as linting
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchedDocument {
+    /// The `Content-Type` header; `None` if absent or answered from the cache
+    pub content_type: Option<String>,
+    pub body: String,
+}
 
-    loop {
-        attempts += 1;
-        let start = Instant::now();
+/// Identifiers found in a feed document, used to recognise the same feed under different URLs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedIdentity {
+    /// The channel `link`, or the atom `link rel="alternate"`
+    pub link: Option<String>,
+    /// The atom feed `id`
+    pub id: Option<String>,
+    /// GUIDs of the most recent items, falling back to item links
+    pub guids: Vec<String>,
+}
 
-        let response = match client.get(&feed.xml_url).send().await {
-            Ok(resp) => resp,
-            Err(e) => {
-                if e.is_timeout() {
-                    return Ok(ValidationResult {
-                        feed: feed.title.replace("|", "&#124;"),
-                        url: feed.xml_url.clone(),
-                        status: "error".to_string(),
-                        error: "Network timeout".to_string(),
-                        categories: feed.category.clone(),
-                    });
+impl FeedIdentity {
+    fn from_document(doc: &roxmltree::Document, base: &Url) -> Self {
+        let channel = metadata::channel(doc);
+        let guids = metadata::items(doc)
+            .take(IDENTITY_ITEMS)
+            .filter_map(|item| {
+                child_text(item, "guid")
+                    .or_else(|| child_text(item, "id"))
+                    .or_else(|| alternate_link(item, base))
+            })
+            .collect();
+
+        FeedIdentity {
+            link: channel.and_then(|c| alternate_link(c, base)),
+            id: channel
+                .filter(|c| c.has_tag_name("feed"))
+                .and_then(|c| child_text(c, "id")),
+            guids,
+        }
+    }
+
+    fn from_json(value: &serde_json::Value, base: &Url) -> Self {
+        let resolve = |link: String| base.join(&link).ok().map(String::from);
+        let guids = json_items(value)
+            .iter()
+            .take(IDENTITY_ITEMS)
+            .filter_map(|item| {
+                // JSON Feed item ids may be numbers despite the spec asking for strings
+                match item.get("id") {
+                    Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+                    _ => json_text(item, "id"),
                 }
+                .or_else(|| json_text(item, "url").and_then(resolve))
+            })
+            .collect();
 
-                if attempts < max_attempts && (e.is_connect() || e.is_request()) {
-                    let elapsed = start.elapsed();
-                    if elapsed < backoff {
-                        sleep(backoff - elapsed).await;
-                    }
-                    backoff *= 2;
-                    continue;
+        FeedIdentity {
+            link: json_text(value, "home_page_url").and_then(resolve),
+            id: None,
+            guids,
+        }
+    }
+}
+
+/// One hop of a redirect chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Redirect {
+    pub from: String,
+    pub to: String,
+    pub status: u16,
+    /// True for 301 and 308, which ask clients to update the URL
+    pub permanent: bool,
+}
+
+impl Redirect {
+    fn new(from: &Url, to: &Url, status: StatusCode) -> Self {
+        Redirect {
+            from: from.to_string(),
+            to: to.to_string(),
+            status: status.as_u16(),
+            permanent: matches!(
+                status,
+                StatusCode::MOVED_PERMANENTLY | StatusCode::PERMANENT_REDIRECT
+            ),
+        }
+    }
+}
+
+impl ValidationResult {
+    fn valid(feed: &Feed) -> Self {
+        ValidationResult {
+            feed: feed.title.replace("|", "&#124;"),
+            url: feed.xml_url.clone(),
+            status: ValidationStatus::Valid,
+            error: String::new(),
+            issue: None,
+            categories: feed.category.clone(),
+            metadata: None,
+            format: None,
+            redirects: Vec::new(),
+            new_feed_url: None,
+            self_url: None,
+            discovered_url: None,
+            last_changed: None,
+            not_modified: false,
+            identity: None,
+            document: None,
+        }
+    }
+
+    fn failed(feed: &Feed, kind: IssueKind, error: impl Into<String>) -> Self {
+        ValidationResult {
+            status: kind.status(),
+            error: error.into(),
+            issue: Some(ValidationIssue {
+                kind,
+                http_status: None,
+                final_url: None,
+                retries: 0,
+            }),
+            ..ValidationResult::valid(feed)
+        }
+    }
+
+    fn http_failure(feed: &Feed, status: StatusCode) -> Self {
+        let mut result = ValidationResult::failed(
+            feed,
+            IssueKind::from_http_status(status),
+            format!("HTTP {}", status),
+        );
+        if let Some(issue) = &mut result.issue {
+            issue.http_status = Some(status.as_u16());
+        }
+        result
+    }
+
+    /// A result for a feed whose validation failed unexpectedly
+    ///
+    /// # Arguments
+    /// * `feed` - The feed that was being validated
+    /// * `details` - What went wrong, such as an error or a panic message
+    pub fn internal_error(feed: &Feed, details: impl fmt::Display) -> Self {
+        ValidationResult::failed(
+            feed,
+            IssueKind::Internal,
+            format!("internal error: {}", details),
+        )
+    }
+
+    /// A result for a feed left unchecked because the run was cancelled
+    pub fn cancelled(feed: &Feed) -> Self {
+        ValidationResult::failed(
+            feed,
+            IssueKind::Cancelled,
+            "Cancelled before the feed was checked",
+        )
+    }
+
+    /// Returns true if this is the result of validating `feed`
+    pub fn is_for(&self, feed: &Feed) -> bool {
+        self.url == feed.xml_url && self.feed == feed.title.replace("|", "&#124;")
+    }
+
+    /// The kind of issue, if the feed is not valid
+    pub fn issue_kind(&self) -> Option<IssueKind> {
+        self.issue.as_ref().map(|issue| issue.kind)
+    }
+
+    /// Publication date of the feed's newest item, when it declares one
+    pub fn newest_item(&self) -> Option<DateTime<FixedOffset>> {
+        self.metadata.as_ref().and_then(|m| m.newest_item)
+    }
+
+    /// Marks a valid feed as stale if its newest item is older than `stale_after`
+    ///
+    /// Feeds whose items carry no parsable dates are left alone.
+    ///
+    /// # Arguments
+    /// * `stale_after` - Age of the newest item beyond which the feed is stale
+    /// * `now` - The time ages are measured from
+    ///
+    /// # Returns
+    /// * `bool` - True if the feed was marked stale
+    pub fn mark_stale(&mut self, stale_after: TimeDelta, now: DateTime<Utc>) -> bool {
+        let Some(newest) = self.newest_item() else {
+            return false;
+        };
+        if self.status != ValidationStatus::Valid || now.fixed_offset() - newest <= stale_after {
+            return false;
+        }
+        self.status = ValidationStatus::Stale;
+        self.error = format!("No new items since {}", newest.format("%Y-%m-%d"));
+        self.issue = Some(ValidationIssue {
+            kind: IssueKind::Stale,
+            http_status: None,
+            final_url: Some(self.final_url().to_string()),
+            retries: 0,
+        });
+        true
+    }
+
+    /// The URL the feed was finally fetched from, after all redirects
+    pub fn final_url(&self) -> &str {
+        self.redirects.last().map_or(&self.url, |r| &r.to)
+    }
+
+    /// The URL reached by following only the leading permanent redirects
+    ///
+    /// # Returns
+    /// * `Option<&str>` - `None` if the first redirect is temporary or there is none
+    pub fn permanent_url(&self) -> Option<&str> {
+        self.redirects
+            .iter()
+            .take_while(|r| r.permanent)
+            .last()
+            .map(|r| r.to.as_str())
+    }
+}
+
+/// Builds a client suitable for `validate_feed`
+///
+/// Automatic redirects are disabled so that `validate_feed` can follow them
+/// itself and record the chain. Clients that follow redirects still work,
+/// but the redirects they follow go unrecorded.
+///
+/// # Arguments
+/// * `timeout` - Timeout for each request
+/// * `user_agent` - User-Agent header sent with each request
+pub fn build_client(timeout: Duration, user_agent: &str) -> Result<Client> {
+    Ok(Client::builder()
+        .timeout(timeout)
+        .user_agent(user_agent)
+        .redirect(redirect::Policy::none())
+        .build()?)
+}
+
+/// Finds the URLs a feed declares for itself: `<itunes:new-feed-url>` and atom `link rel="self"`
+///
+/// Relative URLs are resolved against `base`, the URL the feed was fetched from.
+fn declared_urls(doc: &roxmltree::Document, base: &Url) -> (Option<String>, Option<String>) {
+    let Some(channel) = metadata::channel(doc) else {
+        return (None, None);
+    };
+    let resolve = |href: &str| base.join(href.trim()).ok().map(String::from);
+
+    let new_feed_url = channel
+        .children()
+        .find(|n| n.has_tag_name((ITUNES_NS, "new-feed-url")))
+        .and_then(|n| n.text())
+        .and_then(resolve);
+    let self_url = channel
+        .children()
+        .find(|n| n.has_tag_name((ATOM_NS, "link")) && n.attribute("rel") == Some("self"))
+        .and_then(|n| n.attribute("href"))
+        .and_then(resolve);
+    (new_feed_url, self_url)
+}
+
+/// Fetches a feed and checks that it is a well-formed RSS, Atom or JSON Feed document
+///
+/// Redirects are followed manually, up to ten of them, and recorded in the
+/// result; see `build_client`. Failures are retried with the default
+/// `RetryPolicy`.
+pub async fn validate_feed(feed: &Feed, client: &Client) -> Result<ValidationResult> {
+    validate_feed_with(feed, client, &RetryPolicy::default()).await
+}
+
+/// Fetches and checks a feed, retrying failures according to `policy`
+///
+/// # Arguments
+/// * `feed` - The feed to validate
+/// * `client` - The HTTP client, usually from `build_client`
+/// * `policy` - Which failures are retried, how often and after how long
+///
+/// # Returns
+/// * `Result<ValidationResult>` - The outcome; failures to reach or parse the
+///   feed are reported in the result rather than as errors
+pub async fn validate_feed_with(
+    feed: &Feed,
+    client: &Client,
+    policy: &RetryPolicy,
+) -> Result<ValidationResult> {
+    validate(feed, client, FetchOptions::new(policy, None)).await
+}
+
+/// Fetches and checks a feed with conditional requests against a cache
+///
+/// The cached `ETag` and `Last-Modified` of the URL are sent along, and a
+/// `304 Not Modified` answer is checked against the cached body. Fresh
+/// bodies are stored for the next run.
+///
+/// # Arguments
+/// * `feed` - The feed to validate
+/// * `client` - The HTTP client, usually from `build_client`
+/// * `policy` - Which failures are retried, how often and after how long
+/// * `cache` - Where validators and bodies are kept between runs
+///
+/// # Returns
+/// * `Result<ValidationResult>` - The outcome, with `last_changed` set for
+///   feeds that could be fetched
+pub async fn validate_feed_cached(
+    feed: &Feed,
+    client: &Client,
+    policy: &RetryPolicy,
+    cache: &HttpCache,
+) -> Result<ValidationResult> {
+    validate(feed, client, FetchOptions::new(policy, Some(cache))).await
+}
+
+/// Fetches and checks a feed, keeping the fetched document on the result
+///
+/// # Arguments
+/// * `feed` - The feed to fetch
+/// * `client` - The HTTP client, usually from `build_client`
+/// * `policy` - Which failures are retried, how often and after how long
+/// * `cache` - Where validators and bodies are kept between runs, if anywhere
+///
+/// # Returns
+/// * `Result<ValidationResult>` - The outcome, with `document` set whenever a
+///   body was received
+pub async fn fetch_feed_document(
+    feed: &Feed,
+    client: &Client,
+    policy: &RetryPolicy,
+    cache: Option<&HttpCache>,
+) -> Result<ValidationResult> {
+    let options = FetchOptions {
+        keep_document: true,
+        ..FetchOptions::new(policy, cache)
+    };
+    validate(feed, client, options).await
+}
+
+/// How a feed is fetched; see the `validate_feed*` functions
+#[derive(Clone, Copy)]
+struct FetchOptions<'a> {
+    policy: &'a RetryPolicy,
+    cache: Option<&'a HttpCache>,
+    /// Keep the response body on the result
+    keep_document: bool,
+    max_body_size: Option<u64>,
+    /// Bounds each request, redirects and retries included, by the host it goes to
+    scheduler: Option<&'a Scheduler>,
+    /// Stops new requests from starting once cancelled
+    cancel: Option<&'a CancelHandle>,
+}
+
+impl<'a> FetchOptions<'a> {
+    fn new(policy: &'a RetryPolicy, cache: Option<&'a HttpCache>) -> Self {
+        FetchOptions {
+            policy,
+            cache,
+            keep_document: false,
+            max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+            scheduler: None,
+            cancel: None,
+        }
+    }
+}
+
+/// Receives progress while a `Validator` checks a list of feeds
+///
+/// Both methods do nothing by default, so observers implement only what they
+/// need. They are called from the task polling the result stream.
+pub trait ProgressObserver: Send + Sync {
+    /// Called once, before any feed is fetched
+    fn started(&self, _total: usize) {}
+
+    /// Called for each feed as its result comes out of the stream
+    fn finished(&self, _result: &ValidationResult) {}
+}
+
+/// Shares an observer, so the caller can keep a handle to it
+impl<T: ProgressObserver + ?Sized> ProgressObserver for Arc<T> {
+    fn started(&self, total: usize) {
+        (**self).started(total)
+    }
+
+    fn finished(&self, result: &ValidationResult) {
+        (**self).finished(result)
+    }
+}
+
+/// Time requests in flight get to finish once a `Validator` is cancelled
+pub const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Stops a `Validator` early
+///
+/// After `cancel`, no new requests start, and requests in flight get the
+/// validator's grace period to finish. Feeds cut off either way get the
+/// status `cancelled`. Clones share the same state.
+#[derive(Clone)]
+pub struct CancelHandle(Arc<watch::Sender<bool>>);
+
+impl CancelHandle {
+    fn new() -> Self {
+        CancelHandle(Arc::new(watch::Sender::new(false)))
+    }
+
+    /// Cancels the validator; cancelling twice does nothing more
+    pub fn cancel(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Waits until the validator is cancelled
+    async fn cancelled(&self) {
+        let mut receiver = self.0.subscribe();
+        // The sender lives as long as `self`, so waiting cannot fail
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+/// Validates feeds concurrently under shared limits, retry policy and cache
+///
+/// Built with `Validator::builder()`. Cloning a validator is cheap, and
+/// clones share the same scheduler, so their requests count against the
+/// same limits.
+#[derive(Clone)]
+pub struct Validator {
+    fetcher: Arc<dyn FeedFetcher>,
+    scheduler: Arc<Scheduler>,
+    policy: Arc<RetryPolicy>,
+    cache: Option<Arc<HttpCache>>,
+    stale_after: Option<TimeDelta>,
+    keep_documents: bool,
+    max_body_size: Option<u64>,
+    observer: Option<Arc<dyn ProgressObserver>>,
+    grace_period: Duration,
+    cancel: CancelHandle,
+}
+
+/// Configures a `Validator`
+pub struct ValidatorBuilder {
+    fetcher: Option<Arc<dyn FeedFetcher>>,
+    timeout: Duration,
+    user_agent: String,
+    limits: Limits,
+    policy: RetryPolicy,
+    cache: Option<HttpCache>,
+    stale_after: Option<TimeDelta>,
+    keep_documents: bool,
+    max_body_size: Option<u64>,
+    observer: Option<Arc<dyn ProgressObserver>>,
+    grace_period: Duration,
+}
+
+impl Default for ValidatorBuilder {
+    fn default() -> Self {
+        ValidatorBuilder {
+            fetcher: None,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            user_agent: default_user_agent(),
+            limits: Limits::default(),
+            policy: RetryPolicy::default(),
+            cache: None,
+            stale_after: None,
+            keep_documents: false,
+            max_body_size: Some(DEFAULT_MAX_BODY_SIZE),
+            observer: None,
+            grace_period: DEFAULT_GRACE_PERIOD,
+        }
+    }
+}
+
+impl ValidatorBuilder {
+    /// Uses an existing HTTP client instead of building one
+    ///
+    /// The timeout and User-Agent settings are then ignored; see
+    /// `build_client` for why the client should not follow redirects.
+    pub fn client(self, client: Client) -> Self {
+        self.fetcher(client)
+    }
+
+    /// Fetches feeds through another transport, such as a `Cassette`
+    ///
+    /// The timeout and User-Agent settings are then ignored.
+    pub fn fetcher(mut self, fetcher: impl FeedFetcher + 'static) -> Self {
+        self.fetcher = Some(Arc::new(fetcher));
+        self
+    }
+
+    /// Sets the timeout for each request
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the User-Agent header sent with each request
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sets the concurrency limits, overall and per host
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets which failures are retried, how often and after how long
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sends conditional requests against a cache, or not if `None`
+    pub fn cache(mut self, cache: Option<HttpCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Marks valid feeds stale when their newest item is older than this
+    pub fn stale_after(mut self, stale_after: Option<TimeDelta>) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Keeps the fetched documents on the results, for linting
+    pub fn keep_documents(mut self, keep_documents: bool) -> Self {
+        self.keep_documents = keep_documents;
+        self
+    }
+
+    /// Reports progress of `Validator::validate_all` to an observer
+    pub fn observer(mut self, observer: impl ProgressObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Sets the largest response body read, or `None` for no limit
+    ///
+    /// Larger feeds are reported as `response-too-large` without reading
+    /// the rest of the body.
+    pub fn max_body_size(mut self, max_body_size: Option<u64>) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Sets how long requests in flight may finish after cancellation
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Builds the validator
+    ///
+    /// # Returns
+    /// * `Result<Validator>` - The validator, or an error if the HTTP client
+    ///   could not be built
+    pub fn build(self) -> Result<Validator> {
+        let fetcher = match self.fetcher {
+            Some(fetcher) => fetcher,
+            None => Arc::new(build_client(self.timeout, &self.user_agent)?),
+        };
+        Ok(Validator {
+            fetcher,
+            scheduler: Arc::new(Scheduler::new(self.limits)),
+            policy: Arc::new(self.policy),
+            cache: self.cache.map(Arc::new),
+            stale_after: self.stale_after,
+            keep_documents: self.keep_documents,
+            max_body_size: self.max_body_size,
+            observer: self.observer,
+            grace_period: self.grace_period,
+            cancel: CancelHandle::new(),
+        })
+    }
+}
+
+impl Validator {
+    /// Starts configuring a validator with the default settings
+    pub fn builder() -> ValidatorBuilder {
+        ValidatorBuilder::default()
+    }
+
+    /// The handle that cancels this validator and its clones
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Validates one feed, waiting for the scheduler to allow each request
+    ///
+    /// Every request, including redirect hops and retries, takes its own
+    /// permit for the host it goes to, and none is held while waiting to retry.
+    ///
+    /// # Arguments
+    /// * `feed` - The feed to validate
+    ///
+    /// # Returns
+    /// * `Result<ValidationResult>` - The outcome; failures to reach or parse
+    ///   the feed are reported in the result rather than as errors, and a
+    ///   feed cut off by cancellation is reported as `cancelled`
+    pub async fn validate(&self, feed: &Feed) -> Result<ValidationResult> {
+        let options = FetchOptions {
+            keep_document: self.keep_documents,
+            max_body_size: self.max_body_size,
+            scheduler: Some(&self.scheduler),
+            cancel: Some(&self.cancel),
+            ..FetchOptions::new(&self.policy, self.cache.as_deref())
+        };
+        let grace_over = async {
+            self.cancel.cancelled().await;
+            sleep(self.grace_period).await;
+        };
+        let mut result = tokio::select! {
+            result = validate(feed, self.fetcher.as_ref(), options) => result?,
+            _ = grace_over => return Ok(ValidationResult::cancelled(feed)),
+        };
+        if let Some(stale_after) = self.stale_after {
+            result.mark_stale(stale_after, Utc::now());
+        }
+        Ok(result)
+    }
+
+    /// Validates feeds concurrently, yielding results as they finish
+    ///
+    /// Every feed is validated in its own task, spawned right away, so this
+    /// must be called from within a Tokio runtime. Requests are still bounded
+    /// by the validator's limits. Errors and panics while validating a feed
+    /// become `internal-error` results, so no feed goes missing.
+    ///
+    /// # Arguments
+    /// * `feeds` - The feeds to validate
+    ///
+    /// # Returns
+    /// * `impl Stream` - Exactly one `(index, result)` pair per feed in
+    ///   completion order, where `index` is the feed's position in `feeds`
+    pub fn validate_all(
+        &self,
+        feeds: &[Feed],
+    ) -> impl Stream<Item = (usize, ValidationResult)> + Send + 'static {
+        if let Some(observer) = &self.observer {
+            observer.started(feeds.len());
+        }
+
+        let tasks: FuturesUnordered<_> = feeds
+            .iter()
+            .enumerate()
+            .map(|(index, feed)| {
+                let validator = self.clone();
+                let feed = feed.clone();
+                let task = {
+                    let feed = feed.clone();
+                    tokio::spawn(async move { validator.validate(&feed).await })
+                };
+                async move {
+                    let result = match task.await {
+                        Ok(Ok(result)) => result,
+                        Ok(Err(e)) => ValidationResult::internal_error(&feed, e),
+                        Err(e) => ValidationResult::internal_error(
+                            &feed,
+                            format!("validation task failed: {}", e),
+                        ),
+                    };
+                    (index, result)
                 }
+            })
+            .collect();
 
-                return Ok(ValidationResult {
-                    feed: feed.title.replace("|", "&#124;"),
-                    url: feed.xml_url.clone(),
-                    status: "error".to_string(),
-                    error: e.to_string(),
-                    categories: feed.category.clone(),
-                });
+        let observer = self.observer.clone();
+        tasks.inspect(move |(_, result)| {
+            if let Some(observer) = &observer {
+                observer.finished(result);
             }
+        })
+    }
+}
+
+async fn validate(
+    feed: &Feed,
+    fetcher: &dyn FeedFetcher,
+    options: FetchOptions<'_>,
+) -> Result<ValidationResult> {
+    let url = match Url::parse(&feed.xml_url) {
+        Ok(url) => url,
+        Err(e) => {
+            return Ok(ValidationResult::failed(
+                feed,
+                IssueKind::InvalidUrl,
+                e.to_string(),
+            ))
+        }
+    };
+
+    let mut redirects = Vec::new();
+    let mut retries = 0;
+    let mut result = fetch(feed, fetcher, options, url, &mut redirects, &mut retries).await;
+    result.redirects = redirects;
+    let final_url = result.final_url().to_string();
+    if let Some(issue) = &mut result.issue {
+        issue.final_url = Some(final_url);
+        issue.retries = retries;
+    }
+    Ok(result)
+}
+
+async fn fetch(
+    feed: &Feed,
+    fetcher: &dyn FeedFetcher,
+    options: FetchOptions<'_>,
+    mut url: Url,
+    redirects: &mut Vec<Redirect>,
+    retries: &mut u32,
+) -> ValidationResult {
+    let FetchOptions {
+        policy,
+        cache,
+        keep_document,
+        max_body_size,
+        scheduler,
+        cancel,
+    } = options;
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        let cached = cache.and_then(|cache| cache.load(url.as_str()));
+        let request = FetchRequest {
+            url: url.clone(),
+            etag: cached.as_ref().and_then(|entry| entry.etag.clone()),
+            last_modified: cached
+                .as_ref()
+                .and_then(|entry| entry.last_modified.clone()),
+            max_body_size,
         };
 
-        if response.status().is_success() {
-            match response.text().await {
-                Ok(text) => {
-                    match roxmltree::Document::parse(&text) {
-                        Ok(doc) => {
-                            let root = doc.root_element();
-                            let is_rss = root.has_tag_name("rss") || root.has_tag_name("channel");
-                            let is_atom = root.has_tag_name("feed");
-                            
-                            if is_rss || is_atom {
-                                return Ok(ValidationResult {
-                                    feed: feed.title.replace("|", "&#124;"),
-                                    url: feed.xml_url.clone(),
-                                    status: "valid".to_string(),
-                                    error: String::new(),
-                                    categories: feed.category.clone(),
-                                });
-                            }
-                            
-                            return Ok(ValidationResult {
-                                feed: feed.title.replace("|", "&#124;"),
-                                url: feed.xml_url.clone(),
-                                status: "invalid".to_string(),
-                                error: "Document is not a valid RSS or Atom feed".to_string(),
-                                categories: feed.category.clone(),
-                            });
-                        },
-                        Err(e) => {
-                            return Ok(ValidationResult {
-                                feed: feed.title.replace("|", "&#124;"),
-                                url: feed.xml_url.clone(),
-                                status: "invalid".to_string(),
-                                error: e.to_string(),
-                                categories: feed.category.clone(),
-                            });
-                        }
-                    }
+        let outcome = {
+            let _permit = match (scheduler, cancel) {
+                (Some(scheduler), Some(cancel)) => tokio::select! {
+                    biased;
+                    _ = cancel.cancelled() => return ValidationResult::cancelled(feed),
+                    permit = scheduler.acquire(url.as_str()) => Some(permit),
                 },
-                Err(_) => {
-                    if attempts >= max_attempts {
-                        return Ok(ValidationResult {
-                            feed: feed.title.replace("|", "&#124;"),
-                            url: feed.xml_url.clone(),
-                            status: "error".to_string(),
-                            error: "Failed to read response text".to_string(),
-                            categories: feed.category.clone(),
-                        });
-                    }
+                (Some(scheduler), None) => Some(scheduler.acquire(url.as_str()).await),
+                (None, _) => None,
+            };
+            fetcher.fetch(&request).await
+        };
+        let response = match outcome {
+            Ok(response) => response,
+            Err(e) => {
+                if let Some(delay) = policy.delay(attempt, e.kind, None) {
+                    sleep(delay).await;
+                    *retries += 1;
+                    continue;
                 }
+                return ValidationResult::failed(feed, e.kind, e.message);
             }
-        } else if response.status().is_server_error() && attempts < max_attempts {
-            let elapsed = start.elapsed();
-            if elapsed < backoff {
-                sleep(backoff - elapsed).await;
+        };
+
+        let location = response
+            .header(LOCATION)
+            .and_then(|location| url.join(location).ok());
+
+        if let (StatusCode::NOT_MODIFIED, Some(cache), Some(entry)) =
+            (response.status, cache, &cached)
+        {
+            let entry = cache.touch(entry);
+            let mut result = parse_feed(feed, &url, &entry.body, None);
+            result.not_modified = true;
+            result.last_changed = Some(entry.changed_at);
+            if keep_document {
+                result.document = Some(FetchedDocument {
+                    content_type: None,
+                    body: entry.body,
+                });
+            }
+            return result;
+        } else if let (true, Some(location)) = (response.status.is_redirection(), location) {
+            if redirects.len() >= MAX_REDIRECTS {
+                return ValidationResult::failed(
+                    feed,
+                    IssueKind::TooManyRedirects,
+                    "Too many redirects",
+                );
             }
-            backoff *= 2;
+            redirects.push(Redirect::new(&url, &location, response.status));
+            url = location;
+            attempt = 0;
             continue;
+        } else if response.status.is_success() {
+            let header = |name| response.header(name).map(String::from);
+            let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+            let content_type = header(CONTENT_TYPE);
+            let text = response.body;
+            let mut result = parse_feed(feed, &url, &text, content_type.as_deref());
+            if let Some(cache) = cache {
+                let entry = cache.update(cached.as_ref(), url.as_str(), etag, last_modified, &text);
+                result.last_changed = Some(entry.changed_at);
+            }
+            if keep_document {
+                result.document = Some(FetchedDocument {
+                    content_type,
+                    body: text,
+                });
+            }
+            return result;
         } else {
-            return Ok(ValidationResult {
-                feed: feed.title.replace("|", "&#124;"),
-                url: feed.xml_url.clone(),
-                status: "error".to_string(),
-                error: format!("HTTP {}", response.status()),
-                categories: feed.category.clone(),
-            });
+            let status = response.status;
+            let retry_after = match status {
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => response
+                    .header(RETRY_AFTER)
+                    .and_then(|value| parse_retry_after(value, Utc::now())),
+                _ => None,
+            };
+            let kind = IssueKind::from_http_status(status);
+            if let Some(delay) = policy.delay(attempt, kind, retry_after) {
+                sleep(delay).await;
+                *retries += 1;
+                continue;
+            }
+            return ValidationResult::http_failure(feed, status);
         }
+    }
+}
+
+/// Checks that a fetched document is an RSS, Atom or JSON feed
+///
+/// HTML pages, recognised by `content_type` or by sniffing, are classified
+/// further by `parse_html_page`.
+fn parse_feed(feed: &Feed, url: &Url, text: &str, content_type: Option<&str>) -> ValidationResult {
+    if looks_like_json(text) {
+        return parse_json_feed(feed, url, text);
+    }
+    let is_html = content_type.is_some_and(is_html_content_type);
 
-        if attempts >= max_attempts {
-            return Ok(ValidationResult {
-                feed: feed.title.replace("|", "&#124;"),
-                url: feed.xml_url.clone(),
-                status: "error".to_string(),
-                error: "Max retry attempts reached".to_string(),
-                categories: feed.category.clone(),
-            });
+    match roxmltree::Document::parse(text) {
+        Ok(doc) => {
+            if let Some(format) = FeedFormat::from_xml(&doc) {
+                let mut result = ValidationResult::valid(feed);
+                result.metadata = Some(FeedMetadata::from_document(&doc, url));
+                result.format = Some(format);
+                (result.new_feed_url, result.self_url) = declared_urls(&doc, url);
+                result.identity = Some(FeedIdentity::from_document(&doc, url));
+                return result;
+            }
+
+            let error = "Document is not a valid RSS or Atom feed";
+            if is_html || doc.root_element().has_tag_name("html") {
+                parse_html_page(feed, url, text, error)
+            } else {
+                ValidationResult::failed(feed, IssueKind::NotAFeed, error)
+            }
+        }
+        Err(e) if is_html || looks_like_html(text) => {
+            parse_html_page(feed, url, text, &e.to_string())
         }
+        Err(e) => ValidationResult::failed(feed, IssueKind::MalformedXml, e.to_string()),
+    }
+}
+
+/// Tells parking pages, pages advertising a moved feed and "not found"
+/// pages apart from other HTML served in place of a feed
+///
+/// Autodiscovery comes first: a page linking to a feed belongs to a live site.
+fn parse_html_page(feed: &Feed, url: &Url, html: &str, error: &str) -> ValidationResult {
+    if let Some(candidate) = discover_feeds(html, url)
+        .into_iter()
+        .find(|candidate| candidate != url.as_str())
+    {
+        let mut result = ValidationResult::failed(
+            feed,
+            IssueKind::Moved,
+            format!("moved: candidate feed found at {}", candidate),
+        );
+        result.discovered_url = Some(candidate);
+        return result;
     }
+    if let Some(signature) = parking_signature(html) {
+        return ValidationResult::failed(
+            feed,
+            IssueKind::Parked,
+            format!("domain parked: page contains \"{}\"", signature),
+        );
+    }
+    if is_not_found_page(html) {
+        let title = page_title(html).unwrap_or_default();
+        return ValidationResult::failed(
+            feed,
+            IssueKind::Soft404,
+            format!("soft 404: page titled \"{}\"", title),
+        );
+    }
+    ValidationResult::failed(feed, IssueKind::HtmlPage, error)
+}
+
+/// Checks that a fetched JSON document is a JSON Feed
+fn parse_json_feed(feed: &Feed, url: &Url, text: &str) -> ValidationResult {
+    let text = text.trim_start_matches('\u{feff}');
+    let value: serde_json::Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => {
+            return ValidationResult::failed(feed, IssueKind::MalformedJson, e.to_string());
+        }
+    };
+    let Some(format) = FeedFormat::from_json(&value) else {
+        return ValidationResult::failed(
+            feed,
+            IssueKind::NotAFeed,
+            "JSON document is not a JSON Feed",
+        );
+    };
+
+    let mut result = ValidationResult::valid(feed);
+    result.metadata = Some(FeedMetadata::from_json(&value, url));
+    result.format = Some(format);
+    result.self_url = json_text(&value, "feed_url")
+        .and_then(|link| url.join(&link).ok())
+        .map(String::from);
+    result.identity = Some(FeedIdentity::from_json(&value, url));
+    result
 }
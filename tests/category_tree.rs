@@ -0,0 +1,74 @@
+use crate::common::{create_test_feed, create_test_feed_with_categories};
+use opml_manager::tree::{render_tree, CategoryNode, TreeOptions};
+use std::collections::HashMap;
+
+mod common;
+
+fn sample_feeds() -> Vec<opml_manager::Feed> {
+    vec![
+        create_test_feed("Loose Feed", "https://loose.com/feed"),
+        create_test_feed_with_categories("Tech Feed", "https://tech.com/feed", vec!["Tech"]),
+        create_test_feed_with_categories(
+            "Rust Feed",
+            "https://rust.com/feed",
+            vec!["Tech", "Rust"],
+        ),
+        create_test_feed_with_categories("Go Feed", "https://go.com/feed", vec!["Tech", "Go"]),
+    ]
+}
+
+#[test]
+fn test_counts() {
+    let root = CategoryNode::from_feeds(&sample_feeds());
+    assert_eq!(root.total_count(), 4);
+    assert_eq!(root.direct_count(), 1);
+
+    let tech = &root.children[0];
+    assert_eq!(tech.name, "Tech");
+    assert_eq!(tech.total_count(), 3);
+    assert_eq!(tech.direct_count(), 1);
+    assert_eq!(tech.children.len(), 2);
+}
+
+#[test]
+fn test_render_categories_only() {
+    let root = CategoryNode::from_feeds(&sample_feeds());
+    let output = render_tree(&root, &TreeOptions::default(), &HashMap::new());
+
+    assert!(output.starts_with("Feeds (4 total, 1 direct)\n"));
+    assert!(output.contains("└── Tech (3 total, 1 direct)"));
+    assert!(output.contains("    ├── Rust (1)"));
+    assert!(output.contains("    └── Go (1)"));
+    assert!(!output.contains("Rust Feed"));
+    assert!(!output.contains('\x1b'));
+}
+
+#[test]
+fn test_render_feeds_with_depth_limit_and_status() {
+    let root = CategoryNode::from_feeds(&sample_feeds());
+    let options = TreeOptions {
+        show_feeds: true,
+        max_depth: Some(1),
+        color: false,
+    };
+    let mut statuses = HashMap::new();
+    statuses.insert("https://loose.com/feed".to_string(), "valid".to_string());
+
+    let output = render_tree(&root, &options, &statuses);
+
+    assert!(output.contains("├── Loose Feed ✓"));
+    assert!(output.contains("└── Tech (3 total, 1 direct)"));
+    assert!(!output.contains("Rust (1)"));
+    assert!(!output.contains("Tech Feed"));
+}
+
+#[test]
+fn test_render_with_color() {
+    let root = CategoryNode::from_feeds(&sample_feeds());
+    let options = TreeOptions {
+        color: true,
+        ..Default::default()
+    };
+    let output = render_tree(&root, &options, &HashMap::new());
+    assert!(output.contains("\x1b[1mTech\x1b[0m"));
+}
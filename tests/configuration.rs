@@ -0,0 +1,127 @@
+use opml_manager::canonical::UrlRule;
+use opml_manager::cli::OutputFormat;
+use opml_manager::config::{Config, ConfigFile, Settings, Source};
+use opml_manager::report::ReportSection;
+use opml_manager::scheduler::{DEFAULT_CONCURRENCY, DEFAULT_PER_HOST};
+use std::path::Path;
+
+fn user_file() -> ConfigFile {
+    ConfigFile::parse(
+        Path::new("user.toml"),
+        r#"
+        timeout = 20
+        user-agent = "user-agent/1"
+
+        [profiles.ci]
+        timeout = 5
+        format = "json"
+        "#,
+    )
+    .unwrap()
+}
+
+fn project_file() -> ConfigFile {
+    ConfigFile::parse(
+        Path::new(".opml-manager.toml"),
+        r#"
+        concurrency = 8
+        report-sections = ["summary", "duplicates"]
+        url-rules = ["scheme", "trailing-slash"]
+        "#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_defaults() {
+    let config = Config::resolve(&[], &Settings::default(), &Settings::default(), None).unwrap();
+    assert_eq!(config.timeout.value, 10);
+    assert_eq!(config.timeout.source, Source::Default);
+    assert_eq!(config.concurrency.value, DEFAULT_CONCURRENCY);
+    assert_eq!(config.per_host.value, DEFAULT_PER_HOST);
+    assert!(config.cache.value);
+    assert_eq!(config.report_sections.value, ReportSection::all());
+    assert_eq!(config.url_rules.value, UrlRule::all());
+}
+
+#[test]
+fn test_layer_precedence() {
+    let env = Settings::from_env(vec![
+        (
+            "OPML_MANAGER_USER_AGENT".to_string(),
+            "env-agent/1".to_string(),
+        ),
+        ("OPML_MANAGER_CACHE".to_string(), "false".to_string()),
+        ("UNRELATED".to_string(), "ignored".to_string()),
+    ])
+    .unwrap();
+    let cli = Settings {
+        concurrency: Some(2),
+        ..Default::default()
+    };
+
+    let config = Config::resolve(&[user_file(), project_file()], &env, &cli, None).unwrap();
+
+    assert_eq!(config.timeout.value, 20);
+    assert_eq!(config.timeout.source, Source::File("user.toml".into()));
+    assert_eq!(config.user_agent.value, "env-agent/1");
+    assert_eq!(
+        config.user_agent.source,
+        Source::Env("OPML_MANAGER_USER_AGENT".to_string())
+    );
+    assert_eq!(config.concurrency.value, 2);
+    assert!(config.http_cache().is_none());
+    assert_eq!(config.concurrency.source, Source::Cli);
+    assert_eq!(
+        config.report_sections.value,
+        vec![ReportSection::Summary, ReportSection::Duplicates]
+    );
+    assert_eq!(
+        config.url_rules.value,
+        vec![UrlRule::Scheme, UrlRule::TrailingSlash]
+    );
+}
+
+#[test]
+fn test_profile_overrides_file_settings() {
+    let config = Config::resolve(
+        &[user_file(), project_file()],
+        &Settings::default(),
+        &Settings::default(),
+        Some("ci"),
+    )
+    .unwrap();
+
+    assert_eq!(config.timeout.value, 5);
+    assert_eq!(
+        config.timeout.source,
+        Source::Profile("ci".to_string(), "user.toml".into())
+    );
+    assert_eq!(config.format.value, Some(OutputFormat::Json));
+
+    let described = config.describe();
+    assert!(described.contains("# profile: ci"));
+    assert!(described.contains("timeout = 5  # user.toml [profiles.ci]"));
+}
+
+#[test]
+fn test_unknown_profile_is_an_error() {
+    let result = Config::resolve(
+        &[user_file()],
+        &Settings::default(),
+        &Settings::default(),
+        Some("missing"),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_invalid_values_are_rejected() {
+    assert!(ConfigFile::parse(Path::new("bad.toml"), "timeot = 5").is_err());
+    assert!(ConfigFile::parse(Path::new("bad.toml"), "format = \"xml\"").is_err());
+    assert!(Settings::from_env(vec![(
+        "OPML_MANAGER_TIMEOUT".to_string(),
+        "soon".to_string()
+    )])
+    .is_err());
+}
@@ -0,0 +1,161 @@
+use opml_manager::canonical::Canonicalizer;
+use opml_manager::duplicates::{find_content_duplicates, score, Signal, DEFAULT_MIN_CONFIDENCE};
+use opml_manager::validation::{
+    build_client, validate_feed, FeedIdentity, ValidationResult, ValidationStatus,
+};
+use std::time::Duration;
+mod common;
+
+fn rss(title: &str, guids: &[&str]) -> String {
+    let items: String = guids
+        .iter()
+        .map(|guid| format!("<item><title>{0}</title><guid>{0}</guid></item>", guid))
+        .collect();
+    format!(
+        r#"<?xml version="1.0"?><rss version="2.0"><channel><title>{}</title><link>https://blog.example.com/</link>{}</channel></rss>"#,
+        title, items
+    )
+}
+
+fn result(url: &str, identity: FeedIdentity) -> ValidationResult {
+    ValidationResult {
+        feed: url.to_string(),
+        url: url.to_string(),
+        status: ValidationStatus::Valid,
+        identity: Some(identity),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_duplicates_found_by_item_guids() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _origin = server
+        .mock("GET", "/feed")
+        .with_body(rss("Blog", &["post-1", "post-2", "post-3"]))
+        .create();
+    let _proxy = server
+        .mock("GET", "/proxy.xml")
+        .with_body(rss("Blog (proxy)", &["post-2", "post-3"]))
+        .create();
+    let _comments = server
+        .mock("GET", "/comments")
+        .with_body(rss("Comments", &["comment-1", "comment-2"]))
+        .create();
+
+    let feeds = vec![
+        common::create_test_feed("Blog", &format!("{}/feed", server.url())),
+        common::create_test_feed("Blog Proxy", &format!("{}/proxy.xml", server.url())),
+        common::create_test_feed("Comments", &format!("{}/comments", server.url())),
+    ];
+    let client = build_client(Duration::from_secs(5), "opml-manager-test").unwrap();
+    let results: Vec<ValidationResult> = rt.block_on(async {
+        let mut results = Vec::new();
+        for feed in &feeds {
+            results.push(validate_feed(feed, &client).await.unwrap());
+        }
+        results
+    });
+
+    let duplicates = find_content_duplicates(
+        &feeds,
+        &results,
+        &Canonicalizer::default(),
+        DEFAULT_MIN_CONFIDENCE,
+    );
+
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].title, "Blog Proxy");
+    assert_eq!(duplicates[0].duplicate_of_title, "Blog");
+    assert_eq!(
+        duplicates[0].signals,
+        vec![Signal::ChannelLink, Signal::ItemGuids]
+    );
+    assert!(duplicates[0].confidence > 0.9);
+}
+
+#[test]
+fn test_score_signals() {
+    let canonical = Canonicalizer::default();
+    let atom = |id: &str| FeedIdentity {
+        id: Some(id.to_string()),
+        ..Default::default()
+    };
+
+    let (confidence, signals) = score(
+        &result("https://a.com/atom", atom("urn:uuid:1")),
+        &result("https://feeds.example.net/a", atom("urn:uuid:1")),
+        &canonical,
+    );
+    assert_eq!(signals, vec![Signal::AtomId]);
+    assert!(confidence >= DEFAULT_MIN_CONFIDENCE);
+
+    let mut proxy = result("https://feeds.example.net/b", FeedIdentity::default());
+    proxy.self_url = Some("http://b.com/feed/".to_string());
+    let (confidence, signals) = score(
+        &result("https://b.com/feed", FeedIdentity::default()),
+        &proxy,
+        &canonical,
+    );
+    assert_eq!(signals, vec![Signal::SelfLink]);
+    assert!(confidence >= DEFAULT_MIN_CONFIDENCE);
+
+    let unchecked = ValidationResult {
+        url: "https://c.com/feed".to_string(),
+        status: ValidationStatus::Error,
+        ..Default::default()
+    };
+    let (confidence, _) = score(&unchecked, &unchecked.clone(), &canonical);
+    assert_eq!(confidence, 0.0);
+}
+
+#[test]
+fn test_clusters_keep_the_first_feed() {
+    let identity = FeedIdentity {
+        guids: vec!["1".to_string(), "2".to_string()],
+        ..Default::default()
+    };
+    let feeds = vec![
+        common::create_test_feed("First", "https://a.com/feed"),
+        common::create_test_feed("Second", "https://b.com/feed"),
+        common::create_test_feed("Third", "https://c.com/feed"),
+    ];
+    let results = vec![
+        result("https://c.com/feed", identity.clone()),
+        result("https://b.com/feed", identity.clone()),
+        result("https://a.com/feed", identity),
+    ];
+
+    let duplicates = find_content_duplicates(&feeds, &results, &Canonicalizer::default(), 0.8);
+
+    assert_eq!(duplicates.len(), 2);
+    assert!(duplicates
+        .iter()
+        .all(|d| d.duplicate_of == "https://a.com/feed"));
+}
+
+#[test]
+fn test_matches_are_not_chained() {
+    let guids = |ids: &[&str]| FeedIdentity {
+        guids: ids.iter().map(|id| id.to_string()).collect(),
+        ..Default::default()
+    };
+    let feeds = vec![
+        common::create_test_feed("A", "https://a.com/feed"),
+        common::create_test_feed("B", "https://b.com/feed"),
+        common::create_test_feed("C", "https://c.com/feed"),
+    ];
+    // B shares its items with both A and C, but A and C share nothing
+    let results = vec![
+        result("https://a.com/feed", guids(&["1", "2"])),
+        result("https://b.com/feed", guids(&["1", "2", "3", "4"])),
+        result("https://c.com/feed", guids(&["3", "4"])),
+    ];
+
+    let duplicates = find_content_duplicates(&feeds, &results, &Canonicalizer::default(), 0.8);
+
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].url, "https://b.com/feed");
+    assert_eq!(duplicates[0].duplicate_of, "https://a.com/feed");
+}
@@ -0,0 +1,120 @@
+use futures::future::BoxFuture;
+use opml_manager::fetcher::{
+    Cassette, FeedFetcher, FetchRequest, FetchResponse, FetchResult, Recorder,
+};
+use opml_manager::retry::RetryPolicy;
+use opml_manager::validation::{
+    build_client, IssueKind, ValidationResult, ValidationStatus, Validator,
+};
+use opml_manager::Feed;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use reqwest::StatusCode;
+use std::time::Duration;
+use tempfile::TempDir;
+mod common;
+
+const FEED: &str = r#"<rss version="2.0"><channel><title>Recorded</title></channel></rss>"#;
+
+/// Answers every request with the same feed
+struct StaticFetcher;
+
+impl FeedFetcher for StaticFetcher {
+    fn fetch<'a>(&'a self, _request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        Box::pin(async {
+            let mut response = FetchResponse {
+                status: StatusCode::OK,
+                body: FEED.to_string(),
+                ..FetchResponse::default()
+            };
+            response.headers.insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/rss+xml"),
+            );
+            Ok(response)
+        })
+    }
+}
+
+fn validate_with(fetcher: impl FeedFetcher + 'static, feeds: &[Feed]) -> Vec<ValidationResult> {
+    let rt = common::get_test_runtime();
+    let validator = Validator::builder()
+        .fetcher(fetcher)
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let mut results = Vec::new();
+        for feed in feeds {
+            results.push(validator.validate(feed).await.unwrap());
+        }
+        results
+    })
+}
+
+#[test]
+fn test_custom_fetcher() {
+    let feed = common::create_test_feed("Any", "https://example.invalid/feed.xml");
+    let results = validate_with(StaticFetcher, &[feed]);
+
+    assert_eq!(results[0].status, ValidationStatus::Valid);
+    assert_eq!(
+        results[0].metadata.as_ref().unwrap().title.as_deref(),
+        Some("Recorded")
+    );
+}
+
+#[test]
+fn test_record_and_replay() {
+    let dir = TempDir::new().unwrap();
+    let mut server = mockito::Server::new();
+    server
+        .mock("GET", "/old.xml")
+        .with_status(301)
+        .with_header("location", "/feed.xml")
+        .create();
+    server
+        .mock("GET", "/feed.xml")
+        .with_header("content-type", "application/rss+xml")
+        .with_body(FEED)
+        .create();
+    server.mock("GET", "/gone.xml").with_status(410).create();
+
+    let feeds = vec![
+        common::create_test_feed("Moved", &format!("{}/old.xml", server.url())),
+        common::create_test_feed("Gone", &format!("{}/gone.xml", server.url())),
+        // Nothing listens on port 1
+        common::create_test_feed("Down", "http://127.0.0.1:1/feed.xml"),
+    ];
+
+    let client = build_client(Duration::from_secs(5), "test").unwrap();
+    let recorder = Recorder::new(client, Cassette::new(dir.path()));
+    let recorded = validate_with(recorder, &feeds);
+    drop(server);
+    let replayed = validate_with(Cassette::new(dir.path()), &feeds);
+
+    assert_eq!(recorded[0].status, ValidationStatus::Valid);
+    assert_eq!(recorded[1].issue_kind(), Some(IssueKind::Gone));
+    assert_eq!(recorded[2].issue_kind(), Some(IssueKind::Connect));
+    for (recorded, replayed) in recorded.iter().zip(&replayed) {
+        assert_eq!(replayed.status, recorded.status);
+        assert_eq!(replayed.error, recorded.error);
+        assert_eq!(replayed.issue, recorded.issue);
+        assert_eq!(replayed.redirects, recorded.redirects);
+    }
+    assert!(replayed[0].final_url().ends_with("/feed.xml"));
+    // One recording per URL, the redirect included
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 4);
+}
+
+#[test]
+fn test_replay_without_recording() {
+    let dir = TempDir::new().unwrap();
+    let feed = common::create_test_feed("Unknown", "https://example.com/feed.xml");
+    let results = validate_with(Cassette::new(dir.path()), &[feed]);
+
+    assert_eq!(results[0].status, ValidationStatus::Error);
+    assert_eq!(results[0].issue_kind(), Some(IssueKind::Other));
+    assert!(results[0]
+        .error
+        .starts_with("No recorded response for https://example.com/feed.xml"));
+}
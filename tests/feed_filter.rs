@@ -0,0 +1,115 @@
+use opml_manager::filter::Filter;
+use opml_manager::opml::parse_opml;
+mod common;
+
+#[test]
+fn test_title_regex_case_insensitive() {
+    let filter = Filter::parse("title ~ /rust/i").unwrap();
+    let feed = common::create_test_feed("This Week in RUST", "https://example.com/feed.xml");
+    assert!(filter.matches(&feed));
+
+    let other = common::create_test_feed("Go Weekly", "https://example.com/go.xml");
+    assert!(!filter.matches(&other));
+}
+
+#[test]
+fn test_category_glob() {
+    let filter = Filter::parse(r#"category = "Tech/*""#).unwrap();
+    let nested =
+        common::create_test_feed_with_categories("A", "https://a.com/feed", vec!["Tech", "Rust"]);
+    let top = common::create_test_feed_with_categories("B", "https://b.com/feed", vec!["News"]);
+    assert!(filter.matches(&nested));
+    assert!(!filter.matches(&top));
+}
+
+#[test]
+fn test_domain_matches_subdomains() {
+    let filter = Filter::parse("domain = medium.com").unwrap();
+    let sub = common::create_test_feed("Blog", "https://blog.medium.com/feed");
+    let exact = common::create_test_feed("Medium", "https://medium.com/feed/@someone");
+    let other = common::create_test_feed("Not Medium", "https://notmedium.com/feed");
+    assert!(filter.matches(&sub));
+    assert!(filter.matches(&exact));
+    assert!(!filter.matches(&other));
+}
+
+#[test]
+fn test_boolean_combinators() {
+    let filter =
+        Filter::parse("(title = foo or title = bar) and not domain = example.com").unwrap();
+    let foo = common::create_test_feed("Foo", "https://foo.org/feed");
+    let bar = common::create_test_feed("Bar", "https://example.com/feed");
+    let baz = common::create_test_feed("Baz", "https://baz.org/feed");
+    assert!(filter.matches(&foo));
+    assert!(!filter.matches(&bar));
+    assert!(!filter.matches(&baz));
+}
+
+#[test]
+fn test_status_field() {
+    let filter = Filter::parse("status != valid").unwrap();
+    let feed = common::create_test_feed("Feed", "https://example.com/feed");
+    assert!(filter.uses_status());
+    assert!(filter.matches_with_status(&feed, Some("error")));
+    assert!(!filter.matches_with_status(&feed, Some("valid")));
+    assert!(!Filter::parse("title = x").unwrap().uses_status());
+}
+
+#[test]
+fn test_tags_and_attributes() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <opml version="2.0">
+        <head><title>Test</title></head>
+        <body>
+            <outline type="rss" text="Podcast" xmlUrl="https://example.com/pod.xml" category="audio, weekly" language="en"/>
+            <outline type="rss" text="Blog" xmlUrl="https://example.com/blog.xml" language="de"/>
+        </body>
+    </opml>"#;
+    let feeds = parse_opml(content).unwrap();
+
+    let by_tag = Filter::parse("tag = audio").unwrap();
+    assert!(by_tag.matches(&feeds[0]));
+    assert!(!by_tag.matches(&feeds[1]));
+
+    let by_attr = Filter::parse("@language = de").unwrap();
+    assert!(!by_attr.matches(&feeds[0]));
+    assert!(by_attr.matches(&feeds[1]));
+}
+
+#[test]
+fn test_remove_by_status_validates_first() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/feed.xml")
+        .with_header("content-type", "application/rss+xml")
+        .with_body(r#"<rss version="2.0"><channel><title>Up</title></channel></rss>"#)
+        .create();
+    let input = dir.path().join("feeds.opml");
+    let output = dir.path().join("out.opml");
+    std::fs::write(
+        &input,
+        format!(
+            r#"<?xml version="1.0"?><opml version="2.0"><body>
+<outline text="Up" title="Up" type="rss" xmlUrl="{}/feed.xml"/>
+</body></opml>"#,
+            server.url()
+        ),
+    )
+    .unwrap();
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_opml-manager"))
+        .current_dir(dir.path())
+        .env("XDG_CONFIG_HOME", dir.path())
+        .args(["--no-cache", "remove"])
+        .arg(&input)
+        .arg(&output)
+        .args(["--where", "status != valid"])
+        .output()
+        .unwrap();
+    assert!(status.status.success(), "{:?}", status);
+
+    mock.assert();
+    let feeds = parse_opml(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(feeds.len(), 1);
+}
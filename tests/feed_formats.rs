@@ -0,0 +1,169 @@
+use opml_manager::format::{FeedFormat, FeedKind};
+use opml_manager::report::{format_validation_markdown, ValidationReport};
+use opml_manager::validation::{validate_feed, IssueKind, ValidationResult, ValidationStatus};
+mod common;
+
+const RDF: &str = r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+         xmlns="http://purl.org/rss/1.0/"
+         xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel rdf:about="https://example.com/">
+    <title>RDF Site</title>
+    <link>https://example.com/</link>
+    <description>An RSS 1.0 feed</description>
+  </channel>
+  <item rdf:about="https://example.com/1">
+    <title>First</title>
+    <dc:date>2024-03-01T10:00:00Z</dc:date>
+  </item>
+</rdf:RDF>"#;
+
+const JSON_FEED: &str = r#"{
+  "version": "https://jsonfeed.org/version/1.1",
+  "title": "JSON Site",
+  "home_page_url": "https://example.com/",
+  "feed_url": "/feed.json",
+  "language": "en",
+  "items": [
+    {"id": "1", "url": "/posts/1", "date_published": "2024-03-01T10:00:00Z"},
+    {"id": 2, "content_text": "Numeric id", "date_modified": "2024-04-01T10:00:00Z"}
+  ]
+}"#;
+
+fn detect(xml: &str) -> Option<FeedFormat> {
+    FeedFormat::from_xml(&roxmltree::Document::parse(xml).unwrap())
+}
+
+fn validate(body: &str) -> ValidationResult {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _mock = server.mock("GET", "/feed").with_body(body).create();
+    let feed = common::create_test_feed("Feed", &format!("{}/feed", server.url()));
+    let client = reqwest::Client::new();
+    rt.block_on(async { validate_feed(&feed, &client).await })
+        .unwrap()
+}
+
+#[test]
+fn test_detect_xml_formats() {
+    let cases = [
+        (r#"<rss version="2.0"><channel/></rss>"#, "RSS 2.0"),
+        (r#"<rss version="0.91"><channel/></rss>"#, "RSS 0.91"),
+        (r#"<rss><channel/></rss>"#, "RSS"),
+        (RDF, "RSS 1.0"),
+        (
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns="http://my.netscape.com/rdf/simple/0.9/"><channel/></rdf:RDF>"#,
+            "RSS 0.90",
+        ),
+        (r#"<feed xmlns="http://www.w3.org/2005/Atom"/>"#, "Atom 1.0"),
+        (
+            r#"<feed version="0.3" xmlns="http://purl.org/atom/ns#"/>"#,
+            "Atom 0.3",
+        ),
+    ];
+    for (xml, expected) in cases {
+        assert_eq!(
+            detect(xml).unwrap().to_string(),
+            expected,
+            "detecting {}",
+            xml
+        );
+    }
+
+    assert_eq!(detect(RDF).unwrap().kind, FeedKind::Rdf);
+    assert!(detect("<html><body/></html>").is_none());
+    // An RDF document without a channel is not a feed
+    assert!(
+        detect(r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"/>"#).is_none()
+    );
+}
+
+#[test]
+fn test_detect_json_feed_versions() {
+    let detect = |json: &str| FeedFormat::from_json(&serde_json::from_str(json).unwrap());
+
+    assert_eq!(
+        detect(r#"{"version": "https://jsonfeed.org/version/1"}"#)
+            .unwrap()
+            .to_string(),
+        "JSON Feed 1.0"
+    );
+    assert_eq!(
+        detect(r#"{"version": "https://jsonfeed.org/version/1.1"}"#)
+            .unwrap()
+            .to_string(),
+        "JSON Feed 1.1"
+    );
+    assert!(detect(r#"{"version": "2"}"#).is_none());
+    assert!(detect(r#"{"items": []}"#).is_none());
+}
+
+#[test]
+fn test_rdf_feed_is_valid() {
+    let result = validate(RDF);
+
+    assert_eq!(result.status, ValidationStatus::Valid);
+    assert_eq!(result.format.as_ref().unwrap().to_string(), "RSS 1.0");
+    let metadata = result.metadata.as_ref().unwrap();
+    assert_eq!(metadata.title.as_deref(), Some("RDF Site"));
+    assert_eq!(metadata.item_count, 1);
+    assert!(metadata.newest_item.is_some());
+}
+
+#[test]
+fn test_json_feed_is_valid() {
+    let result = validate(JSON_FEED);
+
+    assert_eq!(result.status, ValidationStatus::Valid);
+    let format = result.format.as_ref().unwrap();
+    assert_eq!(format.kind, FeedKind::JsonFeed);
+    assert_eq!(format.version.as_deref(), Some("1.1"));
+    assert!(result.self_url.as_ref().unwrap().ends_with("/feed.json"));
+
+    let metadata = result.metadata.as_ref().unwrap();
+    assert_eq!(metadata.title.as_deref(), Some("JSON Site"));
+    assert_eq!(metadata.language.as_deref(), Some("en"));
+    assert_eq!(metadata.item_count, 2);
+    assert_eq!(
+        metadata.newest_item.unwrap().to_rfc3339(),
+        "2024-04-01T10:00:00+00:00"
+    );
+    assert_eq!(result.identity.as_ref().unwrap().guids, vec!["1", "2"]);
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["format"]["kind"], "json-feed");
+    assert_eq!(json["format"]["version"], "1.1");
+}
+
+#[test]
+fn test_broken_json() {
+    let malformed = validate(r#"{"version": "https://jsonfeed.org/version/1.1", "#);
+    assert_eq!(malformed.status, ValidationStatus::Invalid);
+    assert_eq!(malformed.issue_kind(), Some(IssueKind::MalformedJson));
+
+    let other = validate(r#"{"status": "ok"}"#);
+    assert_eq!(other.status, ValidationStatus::Invalid);
+    assert_eq!(other.issue_kind(), Some(IssueKind::NotAFeed));
+    assert!(other.format.is_none());
+}
+
+#[test]
+fn test_format_mix_in_summary() {
+    let results = vec![
+        validate(RDF),
+        validate(JSON_FEED),
+        validate(r#"<rss version="2.0"><channel/></rss>"#),
+        validate(r#"<rss version="2.0"><channel/></rss>"#),
+        validate("<note/>"),
+    ];
+    let report = ValidationReport::new("feeds.opml", results.len(), results);
+
+    assert_eq!(report.format_counts["RSS 2.0"], 2);
+    assert_eq!(report.format_counts["RSS 1.0"], 1);
+    assert_eq!(report.format_counts["JSON Feed 1.1"], 1);
+    assert_eq!(report.format_counts.len(), 3);
+
+    let markdown = format_validation_markdown(&report);
+    assert!(markdown.contains("### Formats\n\n- JSON Feed 1.1: 1\n- RSS 1.0: 1\n- RSS 2.0: 2\n"));
+}
@@ -0,0 +1,202 @@
+use opml_manager::lint::{
+    format_lint_markdown, lint_body, lint_feed, lint_headers, LintReport, LintRule, LintWarning,
+    Severity,
+};
+use opml_manager::retry::RetryPolicy;
+use url::Url;
+mod common;
+
+const SLOPPY_RSS: &str = r#"<?xml version="1.0" encoding="ISO-8859-1"?>
+<rss version="2.0">
+  <channel>
+    <description>No title or link</description>
+    <lastBuildDate>Tuesday, 05 March 2019 08:30:00 GMT</lastBuildDate>
+    <item><title>One</title><guid>a</guid><link>/posts/1</link><pubDate>yesterday</pubDate></item>
+    <item><title>Two</title><guid>a</guid><pubDate>Tue, 05 Mar 2019 08:30:00 GMT</pubDate></item>
+    <item><title>Three</title></item>
+  </channel>
+</rss>"#;
+
+const CLEAN_ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:base="https://example.com/">
+  <title>Clean</title>
+  <link rel="alternate" href="/"/>
+  <entry><id>urn:1</id><link href="/posts/1"/><updated>2024-05-01T12:00:00Z</updated></entry>
+</feed>"#;
+
+fn base() -> Url {
+    Url::parse("https://example.com/feed.xml").unwrap()
+}
+
+fn find(warnings: &[LintWarning], rule: LintRule) -> &LintWarning {
+    warnings
+        .iter()
+        .find(|w| w.rule == rule)
+        .unwrap_or_else(|| panic!("no {} warning in {:?}", rule, warnings))
+}
+
+#[test]
+fn test_lint_sloppy_rss() {
+    let warnings = lint_body(SLOPPY_RSS, &base());
+
+    assert_eq!(
+        find(&warnings, LintRule::MissingTitle).severity,
+        Severity::Error
+    );
+    assert_eq!(
+        find(&warnings, LintRule::MissingLink).severity,
+        Severity::Warning
+    );
+    let missing = find(&warnings, LintRule::MissingGuid);
+    assert_eq!(missing.message, "1 of 3 items have no GUID, e.g. \"Three\"");
+    assert!(find(&warnings, LintRule::DuplicateGuid)
+        .message
+        .ends_with("e.g. a"));
+    assert!(find(&warnings, LintRule::InvalidDate)
+        .message
+        .contains("\"yesterday\""));
+    assert_eq!(
+        find(&warnings, LintRule::NonStandardDate).severity,
+        Severity::Info
+    );
+    assert!(find(&warnings, LintRule::RelativeLink)
+        .message
+        .contains("\"/posts/1\""));
+}
+
+#[test]
+fn test_lint_clean_feeds() {
+    assert_eq!(lint_body(CLEAN_ATOM, &base()), Vec::new());
+
+    let json = r#"{"version": "https://jsonfeed.org/version/1.1", "title": "J",
+        "home_page_url": "https://example.com/",
+        "items": [{"id": "1", "date_published": "2024-05-01T12:00:00Z"}]}"#;
+    assert_eq!(lint_body(json, &base()), Vec::new());
+
+    // Documents that are not feeds are left to validation
+    assert_eq!(lint_body("<html></html>", &base()), Vec::new());
+    assert_eq!(lint_body("<broken", &base()), Vec::new());
+}
+
+#[test]
+fn test_lint_json_feed() {
+    let json = r#"{"version": "https://jsonfeed.org/version/1",
+        "items": [{"id": 7}, {"id": 7}, {"date_published": "2024-05-01"}]}"#;
+    let warnings = lint_body(json, &base());
+
+    assert_eq!(
+        find(&warnings, LintRule::MissingTitle).severity,
+        Severity::Error
+    );
+    assert_eq!(
+        find(&warnings, LintRule::MissingLink).severity,
+        Severity::Info
+    );
+    assert_eq!(
+        find(&warnings, LintRule::MissingGuid).severity,
+        Severity::Error
+    );
+    assert!(find(&warnings, LintRule::DuplicateGuid)
+        .message
+        .ends_with("e.g. 7"));
+    assert!(find(&warnings, LintRule::NonStandardDate)
+        .message
+        .contains("2024-05-01"));
+}
+
+#[test]
+fn test_lint_headers() {
+    assert_eq!(
+        lint_headers(Some("application/rss+xml; charset=utf-8"), CLEAN_ATOM),
+        Vec::new()
+    );
+    assert_eq!(
+        lint_headers(Some("text/xml; charset=\"UTF8\""), CLEAN_ATOM),
+        Vec::new()
+    );
+    assert_eq!(
+        lint_headers(Some("application/feed+json"), "{}"),
+        Vec::new()
+    );
+
+    let html = lint_headers(Some("text/html"), CLEAN_ATOM);
+    assert_eq!(html[0].rule, LintRule::ContentType);
+    assert!(html[0].message.starts_with("Served as text/html"));
+
+    let json = lint_headers(Some("application/rss+xml"), "{}");
+    assert_eq!(json[0].rule, LintRule::ContentType);
+
+    let charset = lint_headers(Some("application/rss+xml; charset=utf-8"), SLOPPY_RSS);
+    assert_eq!(charset.len(), 1);
+    assert_eq!(charset[0].rule, LintRule::CharsetMismatch);
+    assert_eq!(
+        charset[0].message,
+        "Content-Type says charset utf-8, XML declaration says ISO-8859-1"
+    );
+
+    assert_eq!(
+        lint_headers(None, CLEAN_ATOM)[0].rule,
+        LintRule::ContentType
+    );
+}
+
+#[test]
+fn test_lint_feed_and_report() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _sloppy = server
+        .mock("GET", "/sloppy.xml")
+        .with_header("content-type", "text/html")
+        .with_body(SLOPPY_RSS)
+        .create();
+    let _clean = server
+        .mock("GET", "/clean.xml")
+        .with_header("content-type", "application/atom+xml")
+        .with_body(CLEAN_ATOM)
+        .create();
+    let _missing = server.mock("GET", "/missing.xml").with_status(404).create();
+
+    let client = reqwest::Client::new();
+    let policy = RetryPolicy::none();
+    let lints = rt.block_on(async {
+        let mut lints = Vec::new();
+        for (title, path) in [
+            ("Sloppy", "/sloppy.xml"),
+            ("Clean", "/clean.xml"),
+            ("Missing", "/missing.xml"),
+        ] {
+            let feed = common::create_test_feed(title, &format!("{}{}", server.url(), path));
+            lints.push(lint_feed(&feed, &client, &policy).await.unwrap());
+        }
+        lints
+    });
+
+    assert_eq!(lints[0].worst(), Some(Severity::Error));
+    assert_eq!(lints[0].warnings[0].severity, Severity::Error);
+    assert!(lints[0]
+        .warnings
+        .iter()
+        .any(|w| w.rule == LintRule::ContentType));
+    assert!(lints[1].warnings.is_empty());
+    assert!(lints[2].warnings.is_empty());
+    assert!(lints.iter().all(|l| l.result.document.is_none()));
+
+    let json = serde_json::to_value(&lints[0]).unwrap();
+    assert_eq!(json["status"], "valid");
+    assert_eq!(json["warnings"][0]["severity"], "error");
+
+    let report = LintReport::new("feeds.opml", lints, Severity::Warning);
+    assert!(report.feeds[0]
+        .warnings
+        .iter()
+        .all(|w| w.severity >= Severity::Warning));
+    assert!(!report.rule_counts.contains_key(&LintRule::NonStandardDate));
+    assert_eq!(report.severity_counts[&Severity::Error], 1);
+
+    let markdown = format_lint_markdown(&report);
+    assert!(markdown.contains("- Feeds without warnings: 1\n"));
+    assert!(markdown.contains("| Sloppy | "));
+    assert!(markdown.contains("| error | missing-title | Channel has no title |"));
+    assert!(markdown.contains("## Not Linted"));
+    assert!(markdown.contains("| Missing | "));
+}
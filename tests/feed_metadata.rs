@@ -0,0 +1,103 @@
+use opml_manager::metadata::FeedMetadata;
+use opml_manager::validation::{validate_feed, ValidationStatus};
+use url::Url;
+mod common;
+
+fn metadata(xml: &str) -> FeedMetadata {
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    FeedMetadata::from_document(&doc, &Url::parse("https://example.com/feed.xml").unwrap())
+}
+
+#[test]
+fn test_rss_metadata() {
+    let metadata = metadata(
+        r#"<?xml version="1.0"?>
+        <rss version="2.0">
+          <channel>
+            <title> Example Blog </title>
+            <link>https://example.com/</link>
+            <description>Posts about examples</description>
+            <language>en-us</language>
+            <generator>WordPress 6.4</generator>
+            <item><pubDate>Mon, 01 Jan 2018 10:00:00 +0000</pubDate></item>
+            <item><pubDate>Tue, 05 Mar 2019 08:30:00 GMT</pubDate></item>
+            <item><title>Undated</title></item>
+          </channel>
+        </rss>"#,
+    );
+
+    assert_eq!(metadata.title.as_deref(), Some("Example Blog"));
+    assert_eq!(metadata.link.as_deref(), Some("https://example.com/"));
+    assert_eq!(
+        metadata.description.as_deref(),
+        Some("Posts about examples")
+    );
+    assert_eq!(metadata.language.as_deref(), Some("en-us"));
+    assert_eq!(metadata.generator.as_deref(), Some("WordPress 6.4"));
+    assert_eq!(metadata.item_count, 3);
+    assert_eq!(
+        metadata.newest_item.unwrap().to_rfc3339(),
+        "2019-03-05T08:30:00+00:00"
+    );
+    assert_eq!(
+        metadata.oldest_item.unwrap().to_rfc3339(),
+        "2018-01-01T10:00:00+00:00"
+    );
+}
+
+#[test]
+fn test_atom_metadata() {
+    let metadata = metadata(
+        r#"<?xml version="1.0"?>
+        <feed xmlns="http://www.w3.org/2005/Atom" xml:lang="de">
+          <title>Atom Blog</title>
+          <subtitle>Kurze Beiträge</subtitle>
+          <link rel="self" href="/feed.xml"/>
+          <link rel="alternate" href="/blog/"/>
+          <generator uri="https://gohugo.io/">Hugo</generator>
+          <entry><updated>2024-05-01T12:00:00Z</updated></entry>
+        </feed>"#,
+    );
+
+    assert_eq!(metadata.title.as_deref(), Some("Atom Blog"));
+    assert_eq!(metadata.link.as_deref(), Some("https://example.com/blog/"));
+    assert_eq!(metadata.description.as_deref(), Some("Kurze Beiträge"));
+    assert_eq!(metadata.language.as_deref(), Some("de"));
+    assert_eq!(metadata.generator.as_deref(), Some("Hugo"));
+    assert_eq!(metadata.item_count, 1);
+    assert_eq!(metadata.newest_item, metadata.oldest_item);
+}
+
+#[test]
+fn test_metadata_on_validation_result() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _feed = server
+        .mock("GET", "/feed.xml")
+        .with_body(r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Declared</title></channel></rss>"#)
+        .create();
+    let _page = server
+        .mock("GET", "/page")
+        .with_body(r#"<?xml version="1.0"?><note/>"#)
+        .create();
+
+    let client = reqwest::Client::new();
+    let feed = common::create_test_feed("Listed", &format!("{}/feed.xml", server.url()));
+    let page = common::create_test_feed("Page", &format!("{}/page", server.url()));
+    let (feed, page) = rt.block_on(async {
+        (
+            validate_feed(&feed, &client).await.unwrap(),
+            validate_feed(&page, &client).await.unwrap(),
+        )
+    });
+
+    assert_eq!(feed.status, ValidationStatus::Valid);
+    let metadata = feed.metadata.as_ref().unwrap();
+    assert_eq!(metadata.title.as_deref(), Some("Declared"));
+    assert_eq!(metadata.item_count, 0);
+    assert!(page.metadata.is_none());
+
+    let json = serde_json::to_value(&feed).unwrap();
+    assert_eq!(json["metadata"]["title"], "Declared");
+    assert!(json["metadata"].get("language").is_none());
+}
@@ -0,0 +1,252 @@
+use chrono::{DateTime, Local, TimeDelta};
+use opml_manager::metadata::FeedMetadata;
+use opml_manager::prune::{
+    classify, parse_validation_results, plan_prune, PruneHistory, PruneOptions, PruneReason,
+};
+use opml_manager::validation::{IssueKind, ValidationIssue, ValidationResult, ValidationStatus};
+mod common;
+
+fn result(url: &str, status: ValidationStatus, error: &str) -> ValidationResult {
+    ValidationResult {
+        feed: url.to_string(),
+        url: url.to_string(),
+        status,
+        error: error.to_string(),
+        categories: vec![],
+        ..Default::default()
+    }
+}
+
+fn now() -> DateTime<Local> {
+    DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Local)
+}
+
+#[test]
+fn test_classify_reasons() {
+    let not_found = result("a", ValidationStatus::Error, "HTTP 404 Not Found");
+    let gone = result("b", ValidationStatus::Error, "HTTP 410 Gone");
+    let dns = result(
+        "c",
+        ValidationStatus::Error,
+        "error trying to connect: dns error: failed to lookup address",
+    );
+    let not_feed = result(
+        "d",
+        ValidationStatus::Invalid,
+        "Document is not a valid RSS or Atom feed",
+    );
+    let server = result("e", ValidationStatus::Error, "HTTP 503 Service Unavailable");
+
+    assert_eq!(
+        classify(&not_found, None, now()),
+        Some(PruneReason::Http404)
+    );
+    assert_eq!(classify(&gone, None, now()), Some(PruneReason::Http410));
+    assert_eq!(classify(&dns, None, now()), Some(PruneReason::Dns));
+    assert_eq!(
+        classify(&not_feed, None, now()),
+        Some(PruneReason::NotAFeed)
+    );
+    assert_eq!(classify(&server, None, now()), None);
+}
+
+#[test]
+fn test_classify_by_issue_kind() {
+    let with_issue = |kind| ValidationResult {
+        issue: Some(ValidationIssue {
+            kind,
+            http_status: None,
+            final_url: None,
+            retries: 0,
+        }),
+        ..result("a", ValidationStatus::Error, "")
+    };
+
+    assert_eq!(
+        classify(&with_issue(IssueKind::NotFound), None, now()),
+        Some(PruneReason::Http404)
+    );
+    assert_eq!(
+        classify(&with_issue(IssueKind::Gone), None, now()),
+        Some(PruneReason::Http410)
+    );
+    assert_eq!(
+        classify(&with_issue(IssueKind::Dns), None, now()),
+        Some(PruneReason::Dns)
+    );
+    assert_eq!(classify(&with_issue(IssueKind::Timeout), None, now()), None);
+
+    // Binary content is not pruned unless asked for
+    let binary = ValidationResult {
+        status: ValidationStatus::Invalid,
+        ..with_issue(IssueKind::BinaryContent)
+    };
+    assert_eq!(
+        classify(&binary, None, now()),
+        Some(PruneReason::BinaryContent)
+    );
+    assert!(!PruneReason::defaults().contains(&PruneReason::BinaryContent));
+}
+
+#[test]
+fn test_classify_stale() {
+    let with_newest = |url, date| ValidationResult {
+        metadata: Some(FeedMetadata {
+            newest_item: Some(DateTime::parse_from_rfc3339(date).unwrap()),
+            ..Default::default()
+        }),
+        ..result(url, ValidationStatus::Valid, "")
+    };
+    let old = with_newest("a", "2020-01-01T00:00:00Z");
+    let fresh = with_newest("b", "2024-05-01T00:00:00Z");
+
+    let year = Some(TimeDelta::days(365));
+    assert_eq!(classify(&old, year, now()), Some(PruneReason::Stale));
+    assert_eq!(classify(&fresh, year, now()), None);
+    assert_eq!(classify(&old, None, now()), None);
+}
+
+#[test]
+fn test_plan_prune_respects_selected_reasons() {
+    let feeds = vec![
+        common::create_test_feed("Gone", "https://gone.com/feed"),
+        common::create_test_feed("Broken", "https://broken.com/feed"),
+        common::create_test_feed("Fine", "https://fine.com/feed"),
+        common::create_test_feed("Unchecked", "https://unchecked.com/feed"),
+    ];
+    let results = vec![
+        result(
+            "https://gone.com/feed",
+            ValidationStatus::Error,
+            "HTTP 410 Gone",
+        ),
+        result(
+            "https://broken.com/feed",
+            ValidationStatus::Invalid,
+            "unexpected end of stream",
+        ),
+        result("https://fine.com/feed", ValidationStatus::Valid, ""),
+    ];
+    let options = PruneOptions {
+        reasons: vec![PruneReason::Http410],
+        stale_after: None,
+        grace: 1,
+    };
+
+    let mut history = PruneHistory::default();
+    let (kept, removed) = plan_prune(feeds, &results, &options, &mut history, now());
+
+    assert_eq!(kept.len(), 3);
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].title, "Gone");
+    assert_eq!(removed[0].reason, PruneReason::Http410);
+    assert_eq!(removed[0].detail, "HTTP 410 Gone");
+}
+
+#[test]
+fn test_grace_period_requires_consecutive_failures() {
+    let feed = common::create_test_feed("Flaky", "https://flaky.com/feed");
+    let failing = vec![result(
+        "https://flaky.com/feed",
+        ValidationStatus::Error,
+        "HTTP 404 Not Found",
+    )];
+    let passing = vec![result(
+        "https://flaky.com/feed",
+        ValidationStatus::Valid,
+        "",
+    )];
+    let options = PruneOptions {
+        reasons: PruneReason::defaults(),
+        stale_after: None,
+        grace: 2,
+    };
+    let mut history = PruneHistory::default();
+
+    let (_, removed) = plan_prune(vec![feed.clone()], &failing, &options, &mut history, now());
+    assert!(removed.is_empty());
+
+    // A successful run resets the count
+    plan_prune(vec![feed.clone()], &passing, &options, &mut history, now());
+    let (_, removed) = plan_prune(vec![feed.clone()], &failing, &options, &mut history, now());
+    assert!(removed.is_empty());
+
+    // Runs that never got to check the feed leave the count alone
+    for status in [ValidationStatus::Cancelled, ValidationStatus::InternalError] {
+        let unchecked = vec![result("https://flaky.com/feed", status, "")];
+        let (kept, _) = plan_prune(
+            vec![feed.clone()],
+            &unchecked,
+            &options,
+            &mut history,
+            now(),
+        );
+        assert_eq!(kept.len(), 1);
+    }
+
+    // History survives a round trip through its file format
+    let mut history = PruneHistory::from_json(&history.to_json().unwrap()).unwrap();
+    let (kept, removed) = plan_prune(vec![feed], &failing, &options, &mut history, now());
+    assert!(kept.is_empty());
+    assert_eq!(removed[0].consecutive_failures, 2);
+}
+
+#[test]
+fn test_parse_saved_validation_results() {
+    let json = r#"{
+        "source": "feeds.opml",
+        "generated_at": "2024-06-01T00:00:00+00:00",
+        "total_checked": 1,
+        "status_counts": {"error": 1},
+        "results": [{"feed": "A", "url": "https://a.com/feed", "status": "error", "error": "HTTP 404 Not Found", "categories": []}]
+    }"#;
+    let results = parse_validation_results(json).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].status, ValidationStatus::Error);
+
+    let ndjson = concat!(
+        r#"{"feed": "A", "url": "https://a.com/feed", "status": "valid", "error": "", "categories": []}"#,
+        "\n",
+        r#"{"feed": "B", "url": "https://b.com/feed", "status": "invalid", "error": "bad", "categories": ["X"]}"#,
+        "\n"
+    );
+    let results = parse_validation_results(ndjson).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[1].categories, vec!["X"]);
+
+    assert!(parse_validation_results("not json").is_err());
+}
+
+#[test]
+fn test_dns_failures_are_not_pruned_by_default() {
+    assert!(!PruneReason::defaults().contains(&PruneReason::Dns));
+
+    let feed = common::create_test_feed("Offline", "https://offline.com/feed");
+    let mut dns = result(
+        "https://offline.com/feed",
+        ValidationStatus::Error,
+        "DNS resolution failed",
+    );
+    dns.issue = Some(ValidationIssue {
+        kind: IssueKind::Dns,
+        http_status: None,
+        final_url: None,
+        retries: 0,
+    });
+    let options = PruneOptions {
+        reasons: PruneReason::defaults(),
+        stale_after: None,
+        grace: 1,
+    };
+    let (kept, removed) = plan_prune(
+        vec![feed],
+        &[dns],
+        &options,
+        &mut PruneHistory::default(),
+        now(),
+    );
+    assert_eq!(kept.len(), 1);
+    assert!(removed.is_empty());
+}
@@ -0,0 +1,258 @@
+use opml_manager::redirects::{announced_urls, fix_redirects, UrlSource};
+use opml_manager::validation::{
+    build_client, validate_feed, Redirect, ValidationResult, ValidationStatus,
+};
+use std::time::Duration;
+mod common;
+
+const RSS: &str =
+    r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Test</title></channel></rss>"#;
+
+fn client() -> reqwest::Client {
+    build_client(Duration::from_secs(5), "opml-manager-test").unwrap()
+}
+
+fn redirect(from: &str, to: &str, status: u16) -> Redirect {
+    Redirect {
+        from: from.to_string(),
+        to: to.to_string(),
+        status,
+        permanent: matches!(status, 301 | 308),
+    }
+}
+
+fn result(url: &str, status: ValidationStatus, redirects: Vec<Redirect>) -> ValidationResult {
+    ValidationResult {
+        feed: url.to_string(),
+        url: url.to_string(),
+        status,
+        redirects,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_redirect_chain_is_recorded() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _old = server
+        .mock("GET", "/old.xml")
+        .with_status(301)
+        .with_header("Location", "/moved.xml")
+        .create();
+    let _moved = server
+        .mock("GET", "/moved.xml")
+        .with_status(302)
+        .with_header("Location", format!("{}/feed.xml", server.url()).as_str())
+        .create();
+    let _feed = server
+        .mock("GET", "/feed.xml")
+        .with_status(200)
+        .with_body(RSS)
+        .create();
+
+    let feed = common::create_test_feed("Moved", &format!("{}/old.xml", server.url()));
+    let result = rt
+        .block_on(async { validate_feed(&feed, &client()).await })
+        .unwrap();
+
+    assert_eq!(result.status, ValidationStatus::Valid);
+    assert_eq!(result.redirects.len(), 2);
+    assert_eq!(result.redirects[0].status, 301);
+    assert!(result.redirects[0].permanent);
+    assert_eq!(result.redirects[1].status, 302);
+    assert!(!result.redirects[1].permanent);
+    assert_eq!(
+        result.permanent_url(),
+        Some(format!("{}/moved.xml", server.url()).as_str())
+    );
+    assert_eq!(result.final_url(), format!("{}/feed.xml", server.url()));
+}
+
+#[test]
+fn test_redirect_loop_is_an_error() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _loop = server
+        .mock("GET", "/loop.xml")
+        .with_status(308)
+        .with_header("Location", "/loop.xml")
+        .expect_at_least(1)
+        .create();
+
+    let feed = common::create_test_feed("Loop", &format!("{}/loop.xml", server.url()));
+    let result = rt
+        .block_on(async { validate_feed(&feed, &client()).await })
+        .unwrap();
+
+    assert_eq!(result.status, ValidationStatus::Error);
+    assert_eq!(result.error, "Too many redirects");
+}
+
+#[test]
+fn test_declared_feed_urls() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _feed = server
+        .mock("GET", "/podcast.xml")
+        .with_status(200)
+        .with_body(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd" xmlns:atom="http://www.w3.org/2005/Atom">
+              <channel>
+                <title>Podcast</title>
+                <atom:link rel="self" href="/self.xml" type="application/rss+xml"/>
+                <itunes:new-feed-url>https://new.example.com/podcast.xml</itunes:new-feed-url>
+              </channel>
+            </rss>"#,
+        )
+        .create();
+
+    let feed = common::create_test_feed("Podcast", &format!("{}/podcast.xml", server.url()));
+    let result = rt
+        .block_on(async { validate_feed(&feed, &client()).await })
+        .unwrap();
+
+    assert_eq!(
+        result.new_feed_url.as_deref(),
+        Some("https://new.example.com/podcast.xml")
+    );
+    assert_eq!(result.self_url, Some(format!("{}/self.xml", server.url())));
+}
+
+#[test]
+fn test_fix_redirects_rewrites_permanent_moves() {
+    let mut feeds = vec![
+        common::create_test_feed("Permanent", "http://a.com/feed"),
+        common::create_test_feed("Temporary", "http://b.com/feed"),
+        common::create_test_feed("Broken", "http://c.com/feed"),
+    ];
+    let results = vec![
+        result(
+            "http://a.com/feed",
+            ValidationStatus::Valid,
+            vec![
+                redirect("http://a.com/feed", "https://a.com/feed", 301),
+                redirect("https://a.com/feed", "https://a.com/rss", 308),
+            ],
+        ),
+        result(
+            "http://b.com/feed",
+            ValidationStatus::Valid,
+            vec![redirect("http://b.com/feed", "http://b.com/rss", 302)],
+        ),
+        result(
+            "http://c.com/feed",
+            ValidationStatus::Error,
+            vec![redirect("http://c.com/feed", "http://c.com/gone", 301)],
+        ),
+    ];
+
+    let changes = fix_redirects(&mut feeds, &results, &UrlSource::all(), &[]);
+
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].from, "http://a.com/feed");
+    assert_eq!(changes[0].to, "https://a.com/rss");
+    assert_eq!(changes[0].source, UrlSource::Redirect);
+    assert_eq!(feeds[0].xml_url, "https://a.com/rss");
+    assert_eq!(feeds[1].xml_url, "http://b.com/feed");
+    assert_eq!(feeds[2].xml_url, "http://c.com/feed");
+}
+
+#[test]
+fn test_fix_redirects_skips_collisions() {
+    let mut feeds = vec![
+        common::create_test_feed("Old", "http://a.com/feed"),
+        common::create_test_feed("New", "https://a.com/feed"),
+        common::create_test_feed("Mirror one", "http://b.com/feed"),
+        common::create_test_feed("Mirror two", "http://c.com/feed"),
+    ];
+    let moved =
+        |from: &str, to: &str| result(from, ValidationStatus::Valid, vec![redirect(from, to, 301)]);
+    let results = vec![
+        moved("http://a.com/feed", "https://a.com/feed"),
+        result("https://a.com/feed", ValidationStatus::Valid, vec![]),
+        moved("http://b.com/feed", "https://d.com/feed"),
+        moved("http://c.com/feed", "https://d.com/feed"),
+    ];
+
+    let changes = fix_redirects(&mut feeds, &results, &UrlSource::all(), &[]);
+
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].from, "http://b.com/feed");
+    let urls: Vec<&str> = feeds.iter().map(|f| f.xml_url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "http://a.com/feed",
+            "https://a.com/feed",
+            "https://d.com/feed",
+            "http://c.com/feed"
+        ]
+    );
+}
+
+#[test]
+fn test_fix_redirects_sources() {
+    let mut with_declared = result(
+        "https://a.com/feed",
+        ValidationStatus::Valid,
+        vec![redirect("https://a.com/feed", "https://a.com/rss", 301)],
+    );
+    with_declared.new_feed_url = Some("https://b.com/podcast".to_string());
+    let mut downgrade = result("https://c.com/feed", ValidationStatus::Valid, vec![]);
+    downgrade.self_url = Some("http://c.com/feed".to_string());
+    let mut self_link = result("https://d.com/feed", ValidationStatus::Valid, vec![]);
+    self_link.self_url = Some("https://d.com/atom".to_string());
+    let results = vec![with_declared, downgrade, self_link];
+
+    let feeds = vec![
+        common::create_test_feed("Declared", "https://a.com/feed"),
+        common::create_test_feed("Downgrade", "https://c.com/feed"),
+        common::create_test_feed("Self", "https://d.com/feed"),
+    ];
+
+    assert_eq!(
+        announced_urls(&results, &UrlSource::all()),
+        vec![
+            "https://b.com/podcast",
+            "http://c.com/feed",
+            "https://d.com/atom"
+        ]
+    );
+    let targets = vec![
+        result("https://b.com/podcast", ValidationStatus::Valid, vec![]),
+        result("https://d.com/atom", ValidationStatus::Valid, vec![]),
+    ];
+
+    let mut all = feeds.clone();
+    let changes = fix_redirects(&mut all, &results, &UrlSource::all(), &targets);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].source, UrlSource::NewFeedUrl);
+    assert_eq!(all[0].xml_url, "https://b.com/podcast");
+    assert_eq!(all[1].xml_url, "https://c.com/feed");
+    assert_eq!(all[2].xml_url, "https://d.com/atom");
+
+    // Announced URLs that do not serve a feed are passed over
+    let dead_targets = vec![
+        result("https://b.com/podcast", ValidationStatus::Error, vec![]),
+        result("https://d.com/atom", ValidationStatus::Invalid, vec![]),
+    ];
+    let mut unverified = feeds.clone();
+    let changes = fix_redirects(&mut unverified, &results, &UrlSource::all(), &dead_targets);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].source, UrlSource::Redirect);
+    assert_eq!(unverified[0].xml_url, "https://a.com/rss");
+    assert_eq!(unverified[2].xml_url, "https://d.com/feed");
+
+    let mut redirects_only = feeds.clone();
+    let changes = fix_redirects(
+        &mut redirects_only,
+        &results,
+        &[UrlSource::Redirect],
+        &targets,
+    );
+    assert_eq!(changes.len(), 1);
+    assert_eq!(redirects_only[0].xml_url, "https://a.com/rss");
+    assert_eq!(redirects_only[2].xml_url, "https://d.com/feed");
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use opml_manager::cli::parse_age;
+use opml_manager::config::{Config, ConfigFile, Settings};
+use opml_manager::metadata::{parse_date, FeedMetadata};
+use opml_manager::report::{
+    format_staleness_markdown, format_validation_markdown, ValidationReport,
+};
+use opml_manager::validation::{IssueKind, ValidationResult, ValidationStatus};
+use std::path::Path;
+
+fn now() -> DateTime<Utc> {
+    "2024-06-01T00:00:00Z".parse().unwrap()
+}
+
+fn dated(url: &str, newest: &str) -> ValidationResult {
+    ValidationResult {
+        feed: url.to_string(),
+        url: url.to_string(),
+        metadata: Some(FeedMetadata {
+            newest_item: Some(DateTime::parse_from_rfc3339(newest).unwrap()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_date_variants() {
+    let expected = "2019-03-05T08:30:00+00:00";
+    for text in [
+        "Tue, 05 Mar 2019 08:30:00 GMT",
+        "Tue, 05 Mar 2019 08:30:00 +0000",
+        "2019-03-05T08:30:00Z",
+        "2019-03-05T08:30:00.000+00:00",
+        // Wrong weekday, full names, odd case and spacing
+        "Mon, 05 Mar 2019 08:30:00 GMT",
+        "Tuesday, 05 March 2019 08:30:00 GMT",
+        "  Tue,  5 MAR 2019 08:30:00 UTC ",
+        // Missing seconds or time zone
+        "Tue, 05 Mar 2019 08:30 +0000",
+        "05 Mar 2019 08:30:00",
+        "2019-03-05 08:30:00",
+        "2019-03-05T08:30:00",
+    ] {
+        let parsed = parse_date(text).unwrap_or_else(|| panic!("could not parse {:?}", text));
+        assert_eq!(parsed.to_rfc3339(), expected, "parsing {:?}", text);
+    }
+
+    assert_eq!(
+        parse_date("Tue, 05 Mar 2019 10:30:00 CEST")
+            .unwrap()
+            .to_rfc3339(),
+        "2019-03-05T10:30:00+02:00"
+    );
+    assert_eq!(
+        parse_date("2019-03-05").unwrap().to_rfc3339(),
+        "2019-03-05T00:00:00+00:00"
+    );
+    assert!(parse_date("last tuesday").is_none());
+    assert!(parse_date("").is_none());
+}
+
+#[test]
+fn test_mark_stale() {
+    let year = TimeDelta::days(365);
+    let mut old = dated("https://old.com/feed", "2020-01-01T00:00:00Z");
+    let mut fresh = dated("https://fresh.com/feed", "2024-05-01T00:00:00Z");
+    let mut undated = ValidationResult::default();
+    let mut broken = ValidationResult {
+        status: ValidationStatus::Error,
+        ..dated("https://broken.com/feed", "2020-01-01T00:00:00Z")
+    };
+
+    assert!(old.mark_stale(year, now()));
+    assert!(!fresh.mark_stale(year, now()));
+    assert!(!undated.mark_stale(year, now()));
+    assert!(!broken.mark_stale(year, now()));
+
+    assert_eq!(old.status, ValidationStatus::Stale);
+    assert_eq!(old.issue_kind(), Some(IssueKind::Stale));
+    assert_eq!(old.error, "No new items since 2020-01-01");
+    assert!(old.status.is_feed());
+    assert_eq!(fresh.status, ValidationStatus::Valid);
+    assert_eq!(broken.status, ValidationStatus::Error);
+
+    let json = serde_json::to_value(&old).unwrap();
+    assert_eq!(json["status"], "stale");
+}
+
+#[test]
+fn test_staleness_sections() {
+    let mut results = vec![
+        dated("https://recent.com/feed", "2022-06-01T00:00:00Z"),
+        dated("https://ancient.com/feed", "2015-01-01T00:00:00Z"),
+        dated("https://fresh.com/feed", "2024-05-01T00:00:00Z"),
+    ];
+    for result in &mut results {
+        result.mark_stale(TimeDelta::days(365), now());
+    }
+
+    let section = format_staleness_markdown(&results, now());
+    assert!(section.starts_with("## Stale Feeds"));
+    let ancient = section.find("ancient.com").unwrap();
+    let recent = section.find("recent.com").unwrap();
+    assert!(ancient < recent);
+    assert!(!section.contains("fresh.com"));
+    assert!(section.contains("| 2022-06-01 | 731 |"));
+
+    assert!(format_staleness_markdown(&results[2..], now()).contains("No stale feeds"));
+
+    let report = ValidationReport::new("feeds.opml", results.len(), results);
+    assert_eq!(report.status_counts[&ValidationStatus::Stale], 2);
+    assert_eq!(report.exit_code(), 0);
+    let markdown = format_validation_markdown(&report);
+    assert_eq!(markdown.matches("## Stale Feeds").count(), 1);
+}
+
+#[test]
+fn test_stale_after_setting() {
+    let file = ConfigFile::parse(Path::new("config.toml"), "stale-after = \"3y\"").unwrap();
+    let config =
+        Config::resolve(&[file], &Settings::default(), &Settings::default(), None).unwrap();
+    assert_eq!(config.stale_after(), Some(TimeDelta::days(3 * 365)));
+    assert!(!config.stale_after_from_cli());
+    assert!(config.describe().contains("stale-after = \"1095d\""));
+
+    let disabled = Settings {
+        stale_after: Some(TimeDelta::zero()),
+        ..Default::default()
+    };
+    let config = Config::resolve(&[], &Settings::default(), &disabled, None).unwrap();
+    assert_eq!(config.stale_after(), None);
+    assert!(!config.stale_after_from_cli());
+
+    let default = Config::resolve(&[], &Settings::default(), &Settings::default(), None).unwrap();
+    assert!(!default.stale_after_from_cli());
+    let env = Settings {
+        stale_after: Some(TimeDelta::days(30)),
+        ..Default::default()
+    };
+    let config = Config::resolve(&[], &env, &Settings::default(), None).unwrap();
+    assert!(!config.stale_after_from_cli());
+    let cli = Settings {
+        stale_after: Some(TimeDelta::days(30)),
+        ..Default::default()
+    };
+    let config = Config::resolve(&[], &Settings::default(), &cli, None).unwrap();
+    assert!(config.stale_after_from_cli());
+
+    assert!(ConfigFile::parse(Path::new("config.toml"), "stale-after = \"soon\"").is_err());
+}
+
+#[test]
+fn test_parse_age_out_of_range() {
+    assert_eq!(parse_age("2w"), Ok(TimeDelta::days(14)));
+    assert_eq!(parse_age("3y"), Ok(TimeDelta::days(3 * 365)));
+    for age in [
+        "99999999999999d",
+        "99999999999999w",
+        "9999999999999999h",
+        "99999999999999999y",
+    ] {
+        assert!(parse_age(age).unwrap_err().contains("too large"), "{}", age);
+    }
+}
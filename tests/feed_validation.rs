@@ -1,6 +1,5 @@
-use opml_manager::validation::validate_feed;
+use opml_manager::validation::{validate_feed, IssueKind, ValidationStatus};
 mod common;
-use std::time::Duration;
 
 #[test]
 fn test_validate_valid_feed() {
@@ -20,7 +19,7 @@ fn test_validate_valid_feed() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "valid");
+    assert_eq!(result.status, ValidationStatus::Valid);
     assert!(result.error.is_empty());
     mock.assert();
 }
@@ -42,7 +41,8 @@ fn test_malformed_xml() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "invalid");
+    assert_eq!(result.status, ValidationStatus::Invalid);
+    assert_eq!(result.issue_kind(), Some(IssueKind::MalformedXml));
     assert!(!result.error.is_empty());
     mock.assert();
 }
@@ -64,7 +64,8 @@ fn test_valid_xml_invalid_feed() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "invalid");
+    assert_eq!(result.status, ValidationStatus::Invalid);
+    assert_eq!(result.issue_kind(), Some(IssueKind::NotAFeed));
     assert!(!result.error.is_empty());
     mock.assert();
 }
@@ -95,7 +96,7 @@ fn test_redirect() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "valid");
+    assert_eq!(result.status, ValidationStatus::Valid);
     assert!(result.error.is_empty());
     mock_redirect.assert();
     mock_final.assert();
@@ -119,7 +120,7 @@ fn test_different_encoding() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "invalid");
+    assert_eq!(result.status, ValidationStatus::Invalid);
     mock.assert();
 }
 
@@ -140,8 +141,10 @@ fn test_rate_limit() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "error");
+    assert_eq!(result.status, ValidationStatus::Error);
     assert!(result.error.contains("429"));
+    assert_eq!(result.issue_kind(), Some(IssueKind::RateLimited));
+    assert_eq!(result.issue.as_ref().and_then(|i| i.http_status), Some(429));
     mock.assert();
 }
 
@@ -153,7 +156,7 @@ fn test_compressed_response() {
         .mock("GET", "/feed.xml")
         .with_status(200)
         .with_header("Content-Encoding", "gzip")
-        .with_body([0x1f, 0x8b].to_vec()) // Basic gzip header
+        .with_body([0x1f, 0x8b]) // Basic gzip header
         .create();
 
     let feed = common::create_test_feed("Compressed Feed", &format!("{}/feed.xml", server.url()));
@@ -163,7 +166,7 @@ fn test_compressed_response() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "invalid");
+    assert_eq!(result.status, ValidationStatus::Invalid);
     mock.assert();
 }
 
@@ -185,7 +188,7 @@ fn test_validate_atom_feed() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "valid");
+    assert_eq!(result.status, ValidationStatus::Valid);
     assert!(result.error.is_empty());
     mock.assert();
 }
@@ -207,7 +210,74 @@ fn test_invalid_feed_format() {
         .block_on(async { validate_feed(&feed, &client).await })
         .unwrap();
 
-    assert_eq!(result.status, "invalid");
+    assert_eq!(result.status, ValidationStatus::Invalid);
     assert_eq!(result.error, "Document is not a valid RSS or Atom feed");
     mock.assert();
 }
+
+#[test]
+fn test_newest_item_date() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/feed.xml")
+        .with_status(200)
+        .with_body(
+            r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Test</title>
+            <item><title>Old</title><pubDate>Mon, 01 Jan 2018 10:00:00 +0000</pubDate></item>
+            <item><title>New</title><pubDate>Tue, 05 Mar 2019 08:30:00 GMT</pubDate></item>
+            </channel></rss>"#,
+        )
+        .create();
+
+    let feed = common::create_test_feed("Dated Feed", &format!("{}/feed.xml", server.url()));
+    let client = reqwest::Client::new();
+
+    let result = rt
+        .block_on(async { validate_feed(&feed, &client).await })
+        .unwrap();
+
+    assert_eq!(result.status, ValidationStatus::Valid);
+    assert_eq!(
+        result.newest_item().unwrap().to_rfc3339(),
+        "2019-03-05T08:30:00+00:00"
+    );
+    mock.assert();
+}
+
+#[test]
+fn test_issue_kinds() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _missing = server
+        .mock("GET", "/missing.xml")
+        .with_status(404)
+        .create();
+    let _html = server
+        .mock("GET", "/blog")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<!DOCTYPE html><html><head><title>Blog</title></head><body><p>Hi<br></body></html>")
+        .create();
+
+    let client = reqwest::Client::new();
+    let missing = common::create_test_feed("Missing", &format!("{}/missing.xml", server.url()));
+    let html = common::create_test_feed("Blog", &format!("{}/blog", server.url()));
+
+    let (missing, html) = rt.block_on(async {
+        (
+            validate_feed(&missing, &client).await.unwrap(),
+            validate_feed(&html, &client).await.unwrap(),
+        )
+    });
+
+    assert_eq!(missing.status, ValidationStatus::Error);
+    assert_eq!(missing.issue_kind(), Some(IssueKind::NotFound));
+    assert_eq!(missing.issue.as_ref().and_then(|i| i.http_status), Some(404));
+    assert_eq!(html.status, ValidationStatus::Invalid);
+    assert_eq!(html.issue_kind(), Some(IssueKind::HtmlPage));
+
+    let json = serde_json::to_value(&missing).unwrap();
+    assert_eq!(json["status"], "error");
+    assert_eq!(json["issue"]["kind"], "not-found");
+}
@@ -0,0 +1,168 @@
+use chrono::Local;
+use opml_manager::html::{discover_feeds, is_not_found_page, page_title, parking_signature};
+use opml_manager::prune::{classify, PruneReason};
+use opml_manager::report::{ValidationReport, EXIT_INVALID_FEEDS};
+use opml_manager::validation::{validate_feed, IssueKind, ValidationResult, ValidationStatus};
+use url::Url;
+mod common;
+
+const PARKED: &str = r#"<!DOCTYPE html><html><head><title>example.com</title></head>
+<body><h1>This domain is for sale!</h1><a href="https://www.hugedomains.com/">Buy now</a></body></html>"#;
+
+const HOMEPAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+  <title>Example Blog</title>
+  <LINK REL="alternate" TYPE="application/atom+xml" HREF="/blog/atom.xml" title="Atom">
+  <link rel=stylesheet href=/style.css>
+</head><body><p>Welcome<br></body></html>"#;
+
+const NOT_FOUND: &str = r#"<!DOCTYPE html><html><head><title>
+  Page Not Found | Example
+</title></head><body>Sorry</body></html>"#;
+
+fn validate(body: &str, content_type: &str) -> ValidationResult {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let _mock = server
+        .mock("GET", "/feed.xml")
+        .with_header("content-type", content_type)
+        .with_body(body)
+        .create();
+    let feed = common::create_test_feed("Feed", &format!("{}/feed.xml", server.url()));
+    let client = reqwest::Client::new();
+    rt.block_on(async { validate_feed(&feed, &client).await })
+        .unwrap()
+}
+
+#[test]
+fn test_discover_feeds() {
+    let base = Url::parse("https://example.com/old/feed").unwrap();
+    assert_eq!(
+        discover_feeds(HOMEPAGE, &base),
+        vec!["https://example.com/blog/atom.xml"]
+    );
+
+    let page = r#"<head>
+        <base href="https://cdn.example.org/site/">
+        <link rel="alternate stylesheet" type="text/css" href="alt.css">
+        <link rel='alternate' type='application/rss+xml' href='feed.rss?a=1&amp;b=2' />
+        <link rel="alternate" type="application/feed+json" href="https://example.com/feed.json">
+        <linkage rel="alternate" type="application/rss+xml" href="wrong.xml">
+    </head>"#;
+    assert_eq!(
+        discover_feeds(page, &base),
+        vec![
+            "https://cdn.example.org/site/feed.rss?a=1&b=2",
+            "https://example.com/feed.json"
+        ]
+    );
+    assert!(discover_feeds("<p>No links</p>", &base).is_empty());
+}
+
+#[test]
+fn test_page_signatures() {
+    assert_eq!(parking_signature(PARKED), Some("this domain is for sale"));
+    assert_eq!(parking_signature(HOMEPAGE), None);
+    assert_eq!(
+        parking_signature("<title>Buy this domain</title>"),
+        Some("buy this domain")
+    );
+    // Single mentions on a real site, or parking words in scripts, are not enough
+    let blog = r#"<html><head><title>Domain Tips</title></head>
+        <body><p>Never click "buy this domain" banners.</p></body></html>"#;
+    assert_eq!(parking_signature(blog), None);
+    let app = r#"<html><head><title>Garage</title>
+        <script>window.parkingLot = "this domain is for sale";</script></head></html>"#;
+    assert_eq!(parking_signature(app), None);
+    let sponsor = r#"<html><head><title>Weekly</title></head>
+        <body><a href="https://bodis.com/">Our sponsor</a></body></html>"#;
+    assert_eq!(parking_signature(sponsor), None);
+
+    assert_eq!(
+        page_title(NOT_FOUND).as_deref(),
+        Some("Page Not Found | Example")
+    );
+    assert!(is_not_found_page(NOT_FOUND));
+    assert!(!is_not_found_page(HOMEPAGE));
+    assert_eq!(page_title("<p>untitled</p>"), None);
+}
+
+#[test]
+fn test_parked_domain() {
+    let result = validate(PARKED, "text/html; charset=utf-8");
+
+    assert_eq!(result.status, ValidationStatus::Parked);
+    assert_eq!(result.issue_kind(), Some(IssueKind::Parked));
+    assert!(result.error.starts_with("domain parked"));
+    assert_eq!(
+        classify(&result, None, Local::now()),
+        Some(PruneReason::Parked)
+    );
+}
+
+#[test]
+fn test_discovery_wins_over_parking() {
+    let page = HOMEPAGE.replace(
+        "<p>Welcome<br>",
+        "<p>This domain is for sale? No. Sedoparking.com says hi.</p>",
+    );
+    assert!(parking_signature(&page).is_some());
+    let result = validate(&page, "text/html");
+    assert_eq!(result.issue_kind(), Some(IssueKind::Moved));
+}
+
+#[test]
+fn test_moved_to_discovered_feed() {
+    let result = validate(HOMEPAGE, "text/html");
+
+    assert_eq!(result.status, ValidationStatus::Moved);
+    assert_eq!(result.issue_kind(), Some(IssueKind::Moved));
+    let candidate = result.discovered_url.clone().unwrap();
+    assert!(candidate.ends_with("/blog/atom.xml"));
+    assert_eq!(
+        result.error,
+        format!("moved: candidate feed found at {}", candidate)
+    );
+    assert_eq!(classify(&result, None, Local::now()), None);
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["status"], "moved");
+    assert_eq!(json["discovered_url"], candidate);
+}
+
+#[test]
+fn test_soft_404_and_plain_html() {
+    let soft = validate(NOT_FOUND, "text/html");
+    assert_eq!(soft.status, ValidationStatus::Invalid);
+    assert_eq!(soft.issue_kind(), Some(IssueKind::Soft404));
+    assert_eq!(
+        soft.error,
+        "soft 404: page titled \"Page Not Found | Example\""
+    );
+
+    // Sniffed as HTML even when mislabelled as XML
+    let page = validate(
+        "<!DOCTYPE html><html><head><title>Blog</title></head><body></body></html>",
+        "application/xml",
+    );
+    assert_eq!(page.issue_kind(), Some(IssueKind::HtmlPage));
+
+    // A feed served as text/html is still a feed
+    let feed = validate(r#"<rss version="2.0"><channel/></rss>"#, "text/html");
+    assert_eq!(feed.status, ValidationStatus::Valid);
+}
+
+#[test]
+fn test_exit_code_for_html_pages() {
+    let report = ValidationReport::new(
+        "feeds.opml",
+        2,
+        vec![
+            validate(PARKED, "text/html"),
+            validate(HOMEPAGE, "text/html"),
+        ],
+    );
+    assert_eq!(report.status_counts[&ValidationStatus::Parked], 1);
+    assert_eq!(report.status_counts[&ValidationStatus::Moved], 1);
+    assert_eq!(report.exit_code(), EXIT_INVALID_FEEDS);
+}
@@ -0,0 +1,94 @@
+use mockito::Matcher;
+use opml_manager::cache::{content_hash, HttpCache};
+use opml_manager::retry::RetryPolicy;
+use opml_manager::validation::{validate_feed_cached, ValidationResult, ValidationStatus};
+use opml_manager::Feed;
+mod common;
+
+const RSS: &str =
+    r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Test</title></channel></rss>"#;
+
+fn validate(feed: &Feed, cache: &HttpCache) -> ValidationResult {
+    let rt = common::get_test_runtime();
+    let client = reqwest::Client::new();
+    rt.block_on(async { validate_feed_cached(feed, &client, &RetryPolicy::none(), cache).await })
+        .unwrap()
+}
+
+#[test]
+fn test_not_modified_uses_cached_copy() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let mut server = mockito::Server::new();
+    let full = server
+        .mock("GET", "/feed.xml")
+        .match_header("if-none-match", Matcher::Missing)
+        .with_status(200)
+        .with_header("ETag", "\"v1\"")
+        .with_header("Last-Modified", "Sat, 01 Jun 2024 00:00:00 GMT")
+        .with_body(RSS)
+        .expect(1)
+        .create();
+    let conditional = server
+        .mock("GET", "/feed.xml")
+        .match_header("if-none-match", "\"v1\"")
+        .match_header("if-modified-since", "Sat, 01 Jun 2024 00:00:00 GMT")
+        .with_status(304)
+        .expect(1)
+        .create();
+
+    let feed = common::create_test_feed("Cached", &format!("{}/feed.xml", server.url()));
+    let first = validate(&feed, &cache);
+    let second = validate(&feed, &cache);
+
+    assert_eq!(first.status, ValidationStatus::Valid);
+    assert!(!first.not_modified);
+    assert_eq!(second.status, ValidationStatus::Valid);
+    assert!(second.not_modified);
+    assert_eq!(second.last_changed, first.last_changed);
+    full.assert();
+    conditional.assert();
+
+    let entry = cache.load(&feed.xml_url).unwrap();
+    assert_eq!(entry.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(entry.content_hash, content_hash(RSS));
+    assert!(entry.checked_at > entry.changed_at);
+}
+
+#[test]
+fn test_content_hash_tracks_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let mut server = mockito::Server::new();
+    let feed = common::create_test_feed("Changing", &format!("{}/feed.xml", server.url()));
+
+    let mock = server.mock("GET", "/feed.xml").with_body(RSS).create();
+    let first = validate(&feed, &cache);
+    let unchanged = validate(&feed, &cache);
+    mock.remove();
+
+    let changed_body = RSS.replace("Test", "Changed");
+    let _mock = server
+        .mock("GET", "/feed.xml")
+        .with_body(changed_body)
+        .create();
+    let changed = validate(&feed, &cache);
+
+    assert!(first.last_changed.is_some());
+    assert_eq!(unchanged.last_changed, first.last_changed);
+    assert!(changed.last_changed > first.last_changed);
+}
+
+#[test]
+fn test_cache_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = HttpCache::new(dir.path().join("nested"));
+
+    assert!(cache.load("https://a.com/feed").is_none());
+    let entry = cache.update(None, "https://a.com/feed", None, None, "body");
+    assert_eq!(cache.load("https://a.com/feed"), Some(entry));
+    assert!(cache.load("https://b.com/feed").is_none());
+
+    assert_eq!(content_hash(""), "cbf29ce484222325");
+    assert_ne!(content_hash("a"), content_hash("b"));
+}
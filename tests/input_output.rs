@@ -0,0 +1,113 @@
+use opml_manager::io::{backup_path, is_stdio, read_input, write_atomic, write_output, FileLock};
+use std::fs;
+
+#[test]
+fn test_dash_means_stdio() {
+    assert!(is_stdio("-"));
+    assert!(!is_stdio("feeds.opml"));
+    assert!(!is_stdio("./-"));
+}
+
+#[test]
+fn test_file_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("feeds.opml");
+    let path = path.to_str().unwrap();
+
+    write_output(path, "<opml/>").unwrap();
+    assert_eq!(read_input(path).unwrap(), "<opml/>");
+}
+
+#[test]
+fn test_missing_input_is_io_error() {
+    let result = read_input("/nonexistent/feeds.opml");
+    assert!(matches!(result, Err(opml_manager::OPMLError::Io(_))));
+}
+
+#[test]
+fn test_atomic_write_rotates_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("feeds.opml");
+    fs::write(&path, "v1").unwrap();
+
+    write_atomic(&path, b"v2", 2).unwrap();
+    write_atomic(&path, b"v3", 2).unwrap();
+    write_atomic(&path, b"v4", 2).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "v4");
+    assert_eq!(fs::read_to_string(backup_path(&path, 0)).unwrap(), "v3");
+    assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "v2");
+    assert!(!backup_path(&path, 2).exists());
+}
+
+#[test]
+fn test_atomic_write_without_backups_leaves_no_extra_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("feeds.opml");
+    fs::write(&path, "old").unwrap();
+
+    write_atomic(&path, b"new", 0).unwrap();
+
+    let entries: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+}
+
+#[test]
+fn test_file_lock_is_exclusive() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("feeds.opml");
+
+    let lock = FileLock::try_acquire(&path).unwrap();
+    assert!(lock.is_some());
+    assert!(FileLock::try_acquire(&path).unwrap().is_none());
+
+    drop(lock);
+    let lock = FileLock::try_acquire(&path).unwrap();
+    assert!(lock.is_some());
+
+    drop(lock);
+    let entries: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+    assert!(entries.is_empty(), "lock sidecar left behind");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_output_into_fifo() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.fifo");
+    let status = std::process::Command::new("mkfifo")
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let reader = {
+        let path = path.clone();
+        std::thread::spawn(move || fs::read_to_string(path).unwrap())
+    };
+    write_output(path.to_str().unwrap(), "piped").unwrap();
+
+    assert_eq!(reader.join().unwrap(), "piped");
+    assert!(!fs::metadata(&path).unwrap().file_type().is_file());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_atomic_write_through_symlink() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("dotfiles").join("feeds.opml");
+    fs::create_dir(target.parent().unwrap()).unwrap();
+    fs::write(&target, "v1").unwrap();
+    let link = dir.path().join("feeds.opml");
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    write_atomic(&link, b"v2", 1).unwrap();
+
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "v2");
+    assert_eq!(fs::read_to_string(backup_path(&target, 0)).unwrap(), "v1");
+}
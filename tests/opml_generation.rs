@@ -1,4 +1,4 @@
-use opml_manager::opml::generate_opml;
+use opml_manager::opml::{generate_opml, parse_opml};
 
 #[test]
 fn test_generate_empty_opml() {
@@ -8,3 +8,46 @@ fn test_generate_empty_opml() {
     assert!(output.contains("<body>"));
     assert!(output.contains("</body>"));
 }
+
+#[test]
+fn test_generate_preserves_tags_and_attributes() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <opml version="2.0">
+        <head><title>Test</title></head>
+        <body>
+            <outline type="rss" text="A &amp; B" xmlUrl="https://example.com/feed?a=1&amp;b=2" category="news,tech" language="en"/>
+        </body>
+    </opml>"#;
+    let feeds = parse_opml(content).unwrap();
+    let output = generate_opml(&feeds).unwrap();
+
+    let reparsed = parse_opml(&output).unwrap();
+    assert_eq!(reparsed.len(), 1);
+    assert_eq!(reparsed[0].title, "A & B");
+    assert_eq!(reparsed[0].xml_url, "https://example.com/feed?a=1&b=2");
+    assert_eq!(reparsed[0].tags, vec!["news", "tech"]);
+    assert_eq!(reparsed[0].attributes.get("language").unwrap(), "en");
+}
+
+#[test]
+fn test_generate_preserves_namespaced_attributes() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <opml version="2.0" xmlns:podcast="https://podcastindex.org/namespace/1.0">
+        <body>
+            <outline type="rss" text="Show" xmlUrl="https://example.com/show.xml" podcast:guid="abc" xml:lang="de"/>
+        </body>
+    </opml>"#;
+    let feeds = parse_opml(content).unwrap();
+    let output = generate_opml(&feeds).unwrap();
+    assert!(output.contains(r#"xmlns:podcast="https://podcastindex.org/namespace/1.0""#));
+    assert!(output.contains(r#"podcast:guid="abc""#));
+
+    let reparsed = parse_opml(&output).unwrap();
+    assert_eq!(reparsed[0].attributes.get("podcast:guid").unwrap(), "abc");
+    assert_eq!(reparsed[0].attributes.get("xml:lang").unwrap(), "de");
+    assert_eq!(
+        reparsed[0].namespaces.get("podcast").unwrap(),
+        "https://podcastindex.org/namespace/1.0"
+    );
+    assert_eq!(reparsed[0].attributes, feeds[0].attributes);
+}
@@ -130,7 +130,10 @@ fn test_url_normalization() {
     </opml>"#;
 
     let feeds = parse_opml(content).unwrap();
-    assert_eq!(feeds.len(), 1); // All URLs should be normalized to the same value
+    // Scheme and trailing slash are normalized, but paths are case-sensitive
+    assert_eq!(feeds.len(), 2);
+    assert_eq!(feeds[0].xml_url, "http://example.com/feed");
+    assert_eq!(feeds[1].xml_url, "http://example.com/FEED");
 }
 
 #[test]
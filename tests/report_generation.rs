@@ -1,5 +1,11 @@
 use crate::common::{create_test_feed, create_test_feed_with_categories};
-use opml_manager::report::format_markdown_report;
+use opml_manager::canonical::Canonicalizer;
+use opml_manager::report::{
+    find_near_duplicates, format_markdown_report, format_markdown_report_sections,
+    format_validation_markdown, registrable_domain, AnalysisSummary, NearDuplicateReason,
+    ReportSection, ValidationReport, EXIT_FEED_ERRORS, EXIT_INVALID_FEEDS,
+};
+use opml_manager::validation::{IssueKind, ValidationIssue, ValidationResult, ValidationStatus};
 use opml_manager::Feed;
 use std::collections::{HashMap, HashSet};
 
@@ -166,3 +172,254 @@ fn test_nested_categories_in_report() {
     assert!(report.contains("tech.com"));
     assert!(report.contains("subtech.com"));
 }
+
+#[test]
+fn test_analysis_summary_serializes() {
+    let feeds = vec![
+        create_test_feed_with_categories("Tech Feed", "http://tech.com/feed.xml", vec!["Tech"]),
+        create_test_feed("Tech Feed Again", "http://tech.com/feed.xml"),
+    ];
+
+    let summary = AnalysisSummary::from_feeds(&feeds, &Canonicalizer::default());
+    assert_eq!(summary.total_feeds, 2);
+    assert_eq!(summary.duplicate_count, 1);
+
+    let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["unique_feeds"], 1);
+    assert_eq!(json["duplicates"][0]["title"], "Tech Feed Again");
+    assert_eq!(json["domains"]["tech.com"], 2);
+    assert_eq!(json["categories"][0], "Tech");
+}
+
+#[test]
+fn test_validation_report_formats() {
+    let results = vec![
+        ValidationResult {
+            feed: "Good".to_string(),
+            url: "http://good.com/feed".to_string(),
+            status: ValidationStatus::Valid,
+            error: String::new(),
+            categories: vec![],
+            ..Default::default()
+        },
+        ValidationResult {
+            feed: "Bad".to_string(),
+            url: "http://bad.com/feed".to_string(),
+            status: ValidationStatus::Error,
+            error: "HTTP 404 Not Found".to_string(),
+            issue: Some(ValidationIssue {
+                kind: IssueKind::NotFound,
+                http_status: Some(404),
+                final_url: None,
+                retries: 0,
+            }),
+            categories: vec!["News".to_string()],
+            ..Default::default()
+        },
+    ];
+
+    let report = ValidationReport::new("feeds.opml", results.len(), results);
+    assert_eq!(report.total_checked, 2);
+    assert_eq!(report.status_counts[&ValidationStatus::Error], 1);
+    assert_eq!(report.issue_counts[&IssueKind::NotFound], 1);
+    assert_eq!(report.exit_code(), EXIT_FEED_ERRORS);
+
+    let markdown = format_validation_markdown(&report);
+    assert!(markdown.contains("Source OPML: feeds.opml"));
+    assert!(markdown.contains("## Error Feeds"));
+    assert!(
+        markdown.contains("| Bad | http://bad.com/feed | not-found | HTTP 404 Not Found | News |")
+    );
+    assert!(markdown.contains("- not-found: 1"));
+
+    let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["results"][1]["status"], "error");
+    assert_eq!(json["results"][1]["issue"]["kind"], "not-found");
+    assert_eq!(json["results"][1]["issue"]["http_status"], 404);
+    assert!(json["results"][0].get("issue").is_none());
+}
+
+#[test]
+fn test_validation_exit_codes() {
+    let result = |status| ValidationResult {
+        url: "http://a.com/feed".to_string(),
+        status,
+        ..Default::default()
+    };
+    let exit_code = |statuses: &[ValidationStatus]| {
+        let results = statuses.iter().map(|&s| result(s)).collect();
+        ValidationReport::new("feeds.opml", statuses.len(), results).exit_code()
+    };
+
+    assert_eq!(exit_code(&[]), 0);
+    assert_eq!(exit_code(&[ValidationStatus::Valid]), 0);
+    assert_eq!(
+        exit_code(&[ValidationStatus::Valid, ValidationStatus::Invalid]),
+        EXIT_INVALID_FEEDS
+    );
+    assert_eq!(
+        exit_code(&[ValidationStatus::Invalid, ValidationStatus::Error]),
+        EXIT_FEED_ERRORS
+    );
+}
+
+#[test]
+fn test_report_sections() {
+    let feeds = vec![create_test_feed_with_categories(
+        "Tech Feed",
+        "http://tech.com/feed.xml",
+        vec!["Technology"],
+    )];
+    let seen_urls = HashSet::new();
+    let mut categories = HashSet::new();
+    categories.insert("Technology".to_string());
+    let domain_counter = HashMap::new();
+
+    let report = format_markdown_report_sections(
+        &feeds,
+        &seen_urls,
+        &[],
+        &categories,
+        &domain_counter,
+        &[ReportSection::Summary],
+    );
+
+    assert!(report.contains("## Summary"));
+    assert!(!report.contains("## Categories"));
+    assert!(!report.contains("## Top Domains"));
+    assert!(!report.contains("No duplicate feeds found"));
+    assert!(!report.contains("## All Feeds"));
+}
+
+#[test]
+fn test_registrable_domain() {
+    assert_eq!(registrable_domain("blog.example.com"), "example.com");
+    assert_eq!(registrable_domain("www.example.co.uk"), "example.co.uk");
+    assert_eq!(
+        registrable_domain("alice.blogspot.com"),
+        "alice.blogspot.com"
+    );
+    assert_eq!(registrable_domain("example.com"), "example.com");
+    assert_eq!(registrable_domain("127.0.0.1"), "127.0.0.1");
+}
+
+#[test]
+fn test_near_duplicates() {
+    let mut posts = create_test_feed("Foo Blog - Posts", "https://feeds.example.net/foo");
+    posts.html_url = Some("https://www.fooblog.com/".to_string());
+    let feeds = vec![
+        create_test_feed("Foo Blog", "https://fooblog.com/feed"),
+        posts,
+        create_test_feed("Foo Blog", "https://fooblog.com/comments/feed"),
+        create_test_feed("Foo Blog: Rust", "https://fooblog.com/category/rust/feed"),
+        create_test_feed("NYT > Technology", "https://rss.nytimes.com/tech.xml"),
+        create_test_feed("NYT > World", "https://rss.nytimes.com/world.xml"),
+        create_test_feed("Alice Writes", "https://alice.blogspot.com/feeds/posts"),
+        create_test_feed("Bob Writes", "https://bob.blogspot.com/feeds/posts"),
+        create_test_feed("Foo Blog", "https://foo.example.org/feed"),
+    ];
+
+    let near = find_near_duplicates(&feeds);
+    let reasons: Vec<(&str, NearDuplicateReason)> =
+        near.iter().map(|n| (n.url.as_str(), n.reason)).collect();
+
+    assert_eq!(
+        reasons,
+        vec![
+            (
+                "https://feeds.example.net/foo",
+                NearDuplicateReason::SimilarTitle
+            ),
+            (
+                "https://fooblog.com/comments/feed",
+                NearDuplicateReason::CommentsFeed
+            ),
+            (
+                "https://fooblog.com/category/rust/feed",
+                NearDuplicateReason::CategoryFeed
+            ),
+            (
+                "https://foo.example.org/feed",
+                NearDuplicateReason::SameTitle
+            ),
+        ]
+    );
+    assert_eq!(near[0].similar_to, "https://fooblog.com/feed");
+    assert_eq!(near[1].similar_to, "https://fooblog.com/feed");
+
+    let summary = AnalysisSummary::from_feeds(&feeds, &Canonicalizer::default());
+    assert_eq!(summary.near_duplicates.len(), 4);
+    let report = format_markdown_report_sections(
+        &feeds,
+        &HashSet::new(),
+        &[],
+        &HashSet::new(),
+        &HashMap::new(),
+        &[ReportSection::NearDuplicates],
+    );
+    assert!(report.contains("## Possible Near-Duplicates"));
+    assert!(report.contains("| comments feed |"));
+}
+
+#[test]
+fn test_near_duplicates_keep_the_main_feed() {
+    let main = create_test_feed("Foo Blog", "https://fooblog.com/feed");
+    for (secondary, reason) in [
+        (
+            create_test_feed("Foo Blog", "https://fooblog.com/comments/feed"),
+            NearDuplicateReason::CommentsFeed,
+        ),
+        (
+            create_test_feed("Foo Blog: Rust", "https://fooblog.com/category/rust/feed"),
+            NearDuplicateReason::CategoryFeed,
+        ),
+    ] {
+        // The secondary feed comes first, but the main feed is still kept
+        let near = find_near_duplicates(&[secondary.clone(), main.clone()]);
+        assert_eq!(near.len(), 1);
+        assert_eq!(near[0].url, secondary.xml_url);
+        assert_eq!(near[0].similar_to, main.xml_url);
+        assert_eq!(near[0].reason, reason);
+    }
+}
+
+#[test]
+fn test_near_duplicates_on_shared_hosts() {
+    let feeds = vec![
+        create_test_feed("Alice on Medium", "https://medium.com/feed/@alice"),
+        create_test_feed("Bob on Medium", "https://medium.com/feed/@bob"),
+        create_test_feed("Rust on Medium", "https://medium.com/feed/tag/rust"),
+        create_test_feed(
+            "Alice on Medium: Rust",
+            "https://medium.com/feed/@alice/tagged/rust",
+        ),
+        create_test_feed("Foo Weekly", "https://feeds.feedburner.com/FooWeekly"),
+        create_test_feed("Bar Daily", "https://feeds.feedburner.com/BarDaily"),
+        create_test_feed(
+            "Bar Daily",
+            "https://feeds.feedburner.com/bardaily/comments",
+        ),
+    ];
+
+    let near = find_near_duplicates(&feeds);
+    let reasons: Vec<(&str, &str, NearDuplicateReason)> = near
+        .iter()
+        .map(|n| (n.url.as_str(), n.similar_to.as_str(), n.reason))
+        .collect();
+
+    assert_eq!(
+        reasons,
+        vec![
+            (
+                "https://medium.com/feed/@alice/tagged/rust",
+                "https://medium.com/feed/@alice",
+                NearDuplicateReason::SimilarTitle
+            ),
+            (
+                "https://feeds.feedburner.com/bardaily/comments",
+                "https://feeds.feedburner.com/BarDaily",
+                NearDuplicateReason::CommentsFeed
+            ),
+        ]
+    );
+}
@@ -0,0 +1,196 @@
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use opml_manager::fetcher::{FeedFetcher, FetchError, FetchRequest, FetchResponse, FetchResult};
+use opml_manager::retry::RetryPolicy;
+use opml_manager::scheduler::{Limits, Scheduler};
+use opml_manager::validation::{IssueKind, ValidationStatus, Validator};
+use opml_manager::Feed;
+use reqwest::header::{HeaderValue, LOCATION};
+use reqwest::StatusCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::time::Instant;
+
+/// Runs one request per URL through the scheduler and returns the highest
+/// number of requests that were in flight at once
+async fn max_in_flight(scheduler: Arc<Scheduler>, urls: &[&str]) -> usize {
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max = Arc::new(AtomicUsize::new(0));
+    let mut tasks = Vec::new();
+    for url in urls {
+        let url = url.to_string();
+        let (scheduler, in_flight, max) = (scheduler.clone(), in_flight.clone(), max.clone());
+        tasks.push(tokio::spawn(async move {
+            let _permit = scheduler.acquire(&url).await;
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            max.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        }));
+    }
+    futures::future::join_all(tasks).await;
+    max.load(Ordering::SeqCst)
+}
+
+#[test]
+fn test_concurrency_limit() {
+    let rt = Runtime::new().unwrap();
+    let scheduler = Arc::new(Scheduler::new(Limits {
+        concurrency: 3,
+        per_host: 0,
+        host_delay: Duration::ZERO,
+    }));
+    let urls: Vec<String> = (0..10)
+        .map(|i| format!("https://host{}.com/feed", i))
+        .collect();
+    let urls: Vec<&str> = urls.iter().map(String::as_str).collect();
+
+    assert_eq!(rt.block_on(max_in_flight(scheduler, &urls)), 3);
+}
+
+#[test]
+fn test_per_host_limit() {
+    let rt = Runtime::new().unwrap();
+    let scheduler = Arc::new(Scheduler::new(Limits {
+        concurrency: 0,
+        per_host: 1,
+        host_delay: Duration::ZERO,
+    }));
+    let urls = [
+        "https://medium.com/feed/a",
+        "https://MEDIUM.com/feed/b",
+        "https://medium.com/feed/c",
+        "https://substack.com/feed",
+    ];
+
+    // One request to medium.com plus one to substack.com
+    assert_eq!(rt.block_on(max_in_flight(scheduler, &urls)), 2);
+}
+
+#[test]
+fn test_host_delay_spaces_requests() {
+    let rt = Runtime::new().unwrap();
+    let scheduler = Scheduler::new(Limits {
+        concurrency: 0,
+        per_host: 0,
+        host_delay: Duration::from_millis(50),
+    });
+
+    let (same_host, other_host) = rt.block_on(async {
+        let started = Instant::now();
+        for _ in 0..3 {
+            scheduler.acquire("https://example.com/feed").await;
+        }
+        let same_host = started.elapsed();
+
+        let started = Instant::now();
+        scheduler.acquire("https://example.org/feed").await;
+        (same_host, started.elapsed())
+    });
+
+    assert!(same_host >= Duration::from_millis(100));
+    assert!(other_host < Duration::from_millis(50));
+}
+
+/// Redirects every `a*.example` feed to the same host and fails `/flaky`
+/// once, counting requests in flight to that host
+#[derive(Default)]
+struct SharedOrigin {
+    in_flight: AtomicUsize,
+    max: AtomicUsize,
+    flaky_failed: AtomicUsize,
+}
+
+impl FeedFetcher for SharedOrigin {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        Box::pin(async move {
+            let host = request.url.host_str().unwrap_or_default();
+            if host.starts_with('a') {
+                let mut response = FetchResponse {
+                    status: StatusCode::MOVED_PERMANENTLY,
+                    ..FetchResponse::default()
+                };
+                let location = format!("https://origin.example/{}", host);
+                response
+                    .headers
+                    .insert(LOCATION, HeaderValue::from_str(&location).unwrap());
+                return Ok(response);
+            }
+            if request.url.path() == "/flaky"
+                && self.flaky_failed.fetch_add(1, Ordering::SeqCst) == 0
+            {
+                return Err(FetchError::new(IssueKind::Connect, "connection refused"));
+            }
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(FetchResponse {
+                status: StatusCode::OK,
+                body: r#"<rss version="2.0"><channel/></rss>"#.to_string(),
+                ..FetchResponse::default()
+            })
+        })
+    }
+}
+
+#[test]
+fn test_redirect_hops_count_against_their_host() {
+    let rt = Runtime::new().unwrap();
+    let fetcher = Arc::new(SharedOrigin::default());
+    let validator = Validator::builder()
+        .fetcher(fetcher.clone())
+        .limits(Limits {
+            concurrency: 0,
+            per_host: 1,
+            host_delay: Duration::ZERO,
+        })
+        .build()
+        .unwrap();
+    let feeds: Vec<Feed> = (0..4)
+        .map(|i| {
+            let url = format!("https://a{}.example/feed", i);
+            Feed::new(url.clone(), url, None, vec![])
+        })
+        .collect();
+
+    let results: Vec<_> = rt.block_on(async { validator.validate_all(&feeds).collect().await });
+    assert!(results
+        .iter()
+        .all(|(_, result)| result.status == ValidationStatus::Valid));
+    assert_eq!(fetcher.max.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_retry_wait_frees_the_slot() {
+    let rt = Runtime::new().unwrap();
+    let validator = Validator::builder()
+        .fetcher(SharedOrigin::default())
+        .limits(Limits {
+            concurrency: 1,
+            per_host: 0,
+            host_delay: Duration::ZERO,
+        })
+        .retry_policy(RetryPolicy {
+            base_delay: Duration::from_millis(300),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        })
+        .build()
+        .unwrap();
+    let feeds: Vec<Feed> = ["flaky", "fast"]
+        .iter()
+        .map(|name| {
+            let url = format!("https://origin.example/{}", name);
+            Feed::new(name.to_string(), url, None, vec![])
+        })
+        .collect();
+
+    let results: Vec<_> = rt.block_on(async { validator.validate_all(&feeds).collect().await });
+    let order: Vec<_> = results.iter().map(|(index, _)| *index).collect();
+    // The fast feed goes ahead while the flaky one waits to retry
+    assert_eq!(order, vec![1, 0]);
+    assert_eq!(results[1].1.status, ValidationStatus::Valid);
+}
@@ -0,0 +1,142 @@
+use opml_manager::cli::parse_size;
+use opml_manager::config::{Config, ConfigFile, Settings};
+use opml_manager::format::{is_binary_content_type, sniff_binary};
+use opml_manager::retry::RetryPolicy;
+use opml_manager::validation::{
+    IssueKind, ValidationResult, ValidationStatus, Validator, DEFAULT_MAX_BODY_SIZE,
+};
+use std::path::Path;
+mod common;
+
+const FEED: &str = r#"<rss version="2.0"><channel><title>Small</title></channel></rss>"#;
+
+fn validate(
+    server: &mockito::ServerGuard,
+    path: &str,
+    max_body_size: Option<u64>,
+) -> ValidationResult {
+    let rt = common::get_test_runtime();
+    let feed = common::create_test_feed("Feed", &format!("{}{}", server.url(), path));
+    let validator = Validator::builder()
+        .retry_policy(RetryPolicy::none())
+        .max_body_size(max_body_size)
+        .build()
+        .unwrap();
+    rt.block_on(validator.validate(&feed)).unwrap()
+}
+
+#[test]
+fn test_response_too_large() {
+    let mut server = mockito::Server::new();
+    let big = format!("{}<!--{}-->", FEED, "x".repeat(4096));
+    server.mock("GET", "/big.xml").with_body(&big).create();
+    // Without a Content-Length the limit is only hit while reading
+    server
+        .mock("GET", "/endless.xml")
+        .with_chunked_body(|w| {
+            w.write_all(FEED.as_bytes())?;
+            for _ in 0..64 {
+                w.write_all(&[b' '; 1024])?;
+            }
+            Ok(())
+        })
+        .create();
+
+    for path in ["/big.xml", "/endless.xml"] {
+        let result = validate(&server, path, Some(1024));
+        assert_eq!(result.status, ValidationStatus::Error, "{}", path);
+        assert_eq!(result.issue_kind(), Some(IssueKind::ResponseTooLarge));
+        assert!(result.error.contains("1024 bytes"), "{}", result.error);
+    }
+
+    let result = validate(&server, "/big.xml", None);
+    assert_eq!(result.status, ValidationStatus::Valid);
+}
+
+#[test]
+fn test_binary_content() {
+    let mut server = mockito::Server::new();
+    server
+        .mock("GET", "/episode.mp3")
+        .with_header("content-type", "audio/mpeg")
+        .with_body(b"ID3\x03\x00\x00\x00")
+        .create();
+    let mut mp3 = b"ID3\x04\x00\x00\x00\x00\x00\x00".to_vec();
+    mp3.extend([0xAA; 2048]);
+    server
+        .mock("GET", "/mislabelled.xml")
+        .with_header("content-type", "application/rss+xml")
+        .with_body(mp3)
+        .create();
+
+    let result = validate(&server, "/episode.mp3", Some(DEFAULT_MAX_BODY_SIZE));
+    assert_eq!(result.status, ValidationStatus::Invalid);
+    assert_eq!(result.issue_kind(), Some(IssueKind::BinaryContent));
+    assert_eq!(result.error, "binary content: served as audio/mpeg");
+
+    let result = validate(&server, "/mislabelled.xml", Some(DEFAULT_MAX_BODY_SIZE));
+    assert_eq!(result.issue_kind(), Some(IssueKind::BinaryContent));
+    assert!(result.error.starts_with("binary content: looks like"));
+}
+
+#[test]
+fn test_declared_charset_is_decoded() {
+    let mut server = mockito::Server::new();
+    let body: Vec<u8> = br#"<rss version="2.0"><channel><title>Caf"#
+        .iter()
+        .copied()
+        .chain([0xE9])
+        .chain(br#"</title></channel></rss>"#.iter().copied())
+        .collect();
+    server
+        .mock("GET", "/latin1.xml")
+        .with_header("content-type", "application/rss+xml; charset=ISO-8859-1")
+        .with_body(body)
+        .create();
+    server
+        .mock("GET", "/feed.xml")
+        .with_header("content-type", "image/svg+xml")
+        .with_body(FEED)
+        .create();
+
+    let result = validate(&server, "/latin1.xml", Some(DEFAULT_MAX_BODY_SIZE));
+    assert_eq!(result.status, ValidationStatus::Valid);
+    assert_eq!(result.metadata.unwrap().title.as_deref(), Some("Café"));
+    // XML image types are text and still get a look
+    let result = validate(&server, "/feed.xml", Some(DEFAULT_MAX_BODY_SIZE));
+    assert_ne!(result.issue_kind(), Some(IssueKind::BinaryContent));
+}
+
+#[test]
+fn test_sniffing() {
+    assert_eq!(sniff_binary(b"%PDF-1.7\n"), Some("PDF document"));
+    assert!(sniff_binary(b"\x89PNG\r\n\x1a\n").is_some());
+    assert!(sniff_binary(b"\0\0\0\x20ftypisom").is_some());
+    assert!(sniff_binary(b"\xff\xfe<\0r\0s\0s\0").is_none());
+    assert!(sniff_binary(FEED.as_bytes()).is_none());
+    assert!(sniff_binary(b"").is_none());
+
+    assert!(is_binary_content_type("audio/mpeg"));
+    assert!(is_binary_content_type("Video/MP4; codecs=avc1"));
+    assert!(!is_binary_content_type("image/svg+xml"));
+    assert!(!is_binary_content_type("application/rss+xml"));
+}
+
+#[test]
+fn test_size_settings() {
+    assert_eq!(parse_size("512"), Ok(512));
+    assert_eq!(parse_size("512KB"), Ok(512 * 1024));
+    assert_eq!(parse_size("20mb"), Ok(20 * 1024 * 1024));
+    assert_eq!(parse_size("1G"), Ok(1 << 30));
+    assert!(parse_size("10 parsecs").is_err());
+    assert!(parse_size("MB").is_err());
+
+    let config = Config::resolve(&[], &Settings::default(), &Settings::default(), None).unwrap();
+    assert_eq!(config.max_body_size(), Some(DEFAULT_MAX_BODY_SIZE));
+    assert!(config.describe().contains("max-body-size = \"20MB\""));
+
+    let file = ConfigFile::parse(Path::new("config.toml"), "max-body-size = \"0\"").unwrap();
+    let config =
+        Config::resolve(&[file], &Settings::default(), &Settings::default(), None).unwrap();
+    assert_eq!(config.max_body_size(), None);
+}
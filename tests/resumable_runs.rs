@@ -0,0 +1,183 @@
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use opml_manager::checkpoint::{Checkpoint, Resumed};
+use opml_manager::fetcher::{FeedFetcher, FetchRequest, FetchResponse, FetchResult};
+use opml_manager::report::{format_validation_markdown, ValidationReport, EXIT_INTERRUPTED};
+use opml_manager::validation::{IssueKind, ValidationResult, ValidationStatus, Validator};
+use opml_manager::Feed;
+use reqwest::StatusCode;
+use std::io::Write;
+use std::time::Duration;
+use tempfile::TempDir;
+mod common;
+
+/// Answers `/slow*` URLs only after a minute, everything else right away
+struct SlowFetcher;
+
+impl FeedFetcher for SlowFetcher {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        Box::pin(async move {
+            if request.url.path().starts_with("/slow") {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }
+            Ok(FetchResponse {
+                status: StatusCode::OK,
+                body: r#"<rss version="2.0"><channel/></rss>"#.to_string(),
+                ..FetchResponse::default()
+            })
+        })
+    }
+}
+
+fn feeds(names: &[&str]) -> Vec<Feed> {
+    names
+        .iter()
+        .map(|name| common::create_test_feed(name, &format!("https://example.com/{}.xml", name)))
+        .collect()
+}
+
+fn result(feed: &Feed, status: ValidationStatus) -> ValidationResult {
+    ValidationResult {
+        feed: feed.title.clone(),
+        url: feed.xml_url.clone(),
+        status,
+        ..ValidationResult::default()
+    }
+}
+
+#[test]
+fn test_cancel_cuts_off_slow_feeds() {
+    let rt = common::get_test_runtime();
+    let feeds = feeds(&["fast", "slow1", "slow2"]);
+    let validator = Validator::builder()
+        .fetcher(SlowFetcher)
+        .grace_period(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let cancel = validator.cancel_handle();
+
+    let mut results: Vec<_> = rt.block_on(async {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            cancel.cancel();
+        });
+        validator.validate_all(&feeds).collect().await
+    });
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<_> = results.into_iter().map(|(_, result)| result).collect();
+
+    assert_eq!(results[0].status, ValidationStatus::Valid);
+    assert_eq!(results[1].status, ValidationStatus::Cancelled);
+    assert_eq!(results[2].issue_kind(), Some(IssueKind::Cancelled));
+
+    let report = ValidationReport::new("feeds.opml", feeds.len(), results);
+    assert!(report.is_partial());
+    assert_eq!(report.total_checked, 1);
+    assert_eq!(report.exit_code(), EXIT_INTERRUPTED);
+    assert_eq!(
+        report.reconciliation(),
+        "3 input feeds = 1 validated + 0 failed + 2 cancelled"
+    );
+    assert!(format_validation_markdown(&report)
+        .contains("**Partial report:** the run was interrupted before 2 feeds were checked."));
+}
+
+#[test]
+fn test_cancelled_validator_starts_nothing() {
+    let rt = common::get_test_runtime();
+    let feed = common::create_test_feed("Fast", "https://example.com/fast.xml");
+    let validator = Validator::builder().fetcher(SlowFetcher).build().unwrap();
+
+    let handle = validator.cancel_handle();
+    assert!(!handle.is_cancelled());
+    handle.cancel();
+    assert!(validator.cancel_handle().is_cancelled());
+
+    let result = rt.block_on(validator.validate(&feed)).unwrap();
+    assert_eq!(result.status, ValidationStatus::Cancelled);
+}
+
+#[test]
+fn test_checkpoint_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = Checkpoint::default_path(&dir.path().join("feeds.opml").to_string_lossy());
+    assert!(path.to_string_lossy().ends_with("feeds.opml.checkpoint"));
+    assert!(Checkpoint::load(&path).unwrap().is_empty());
+
+    let feeds = feeds(&["a", "b", "c", "d"]);
+    let checkpoint =
+        Checkpoint::create(&path, &[result(&feeds[0], ValidationStatus::Valid)]).unwrap();
+    checkpoint
+        .record(&result(&feeds[1], ValidationStatus::Error))
+        .unwrap();
+    checkpoint
+        .record(&result(&feeds[2], ValidationStatus::Cancelled))
+        .unwrap();
+    checkpoint
+        .record(&result(&feeds[3], ValidationStatus::InternalError))
+        .unwrap();
+    // A line cut short by the interruption
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap()
+        .write_all(b"{\"feed\": \"d\", \"ur")
+        .unwrap();
+
+    let loaded = Checkpoint::load(&path).unwrap();
+    let urls: Vec<_> = loaded.iter().map(|r| r.url.as_str()).collect();
+    assert_eq!(
+        urls,
+        vec!["https://example.com/a.xml", "https://example.com/b.xml"]
+    );
+
+    checkpoint.remove().unwrap();
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_checkpoint_survives_a_failed_run() {
+    let dir = TempDir::new().unwrap();
+    let feeds = feeds(&["a"]);
+    let path = dir.path().join("dropped.checkpoint");
+    let checkpoint = Checkpoint::create(&path, &[]).unwrap();
+    checkpoint
+        .record(&result(&feeds[0], ValidationStatus::Valid))
+        .unwrap();
+    // A run that fails part way drops its checkpoint without removing it
+    drop(checkpoint);
+    assert_eq!(Checkpoint::load(&path).unwrap().len(), 1);
+}
+
+#[test]
+fn test_resume_skips_checked_feeds() {
+    let feeds = feeds(&["a", "b", "c", "d"]);
+    let mut renamed = result(&feeds[3], ValidationStatus::Valid);
+    renamed.feed = "Old title".to_string();
+    let previous = vec![
+        result(&feeds[2], ValidationStatus::Error),
+        result(&feeds[0], ValidationStatus::Valid),
+        result(&feeds[1], ValidationStatus::Cancelled),
+        renamed,
+    ];
+
+    let resumed = Resumed::new(&feeds, previous);
+    let pending: Vec<_> = resumed.pending.iter().map(|f| f.title.as_str()).collect();
+    assert_eq!(pending, vec!["b", "d"]);
+    assert_eq!(resumed.carried_over().len(), 2);
+
+    let merged = resumed.merge(vec![
+        result(&feeds[1], ValidationStatus::Valid),
+        result(&feeds[3], ValidationStatus::Invalid),
+    ]);
+    let merged: Vec<_> = merged.iter().map(|r| (r.feed.as_str(), r.status)).collect();
+    assert_eq!(
+        merged,
+        vec![
+            ("a", ValidationStatus::Valid),
+            ("b", ValidationStatus::Valid),
+            ("c", ValidationStatus::Error),
+            ("d", ValidationStatus::Invalid),
+        ]
+    );
+}
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use opml_manager::retry::{parse_retry_after, RetryPolicy};
+use opml_manager::validation::{validate_feed_with, IssueKind, ValidationStatus};
+use std::time::{Duration, Instant};
+mod common;
+
+const RSS: &str =
+    r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Test</title></channel></rss>"#;
+
+fn fast_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_secs(5),
+        jitter: 0.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_retry_after_is_honoured() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let limited = server
+        .mock("GET", "/feed.xml")
+        .with_status(429)
+        .with_header("Retry-After", "1")
+        .expect(1)
+        .create();
+    let ok = server
+        .mock("GET", "/feed.xml")
+        .with_status(200)
+        .with_body(RSS)
+        .create();
+
+    let feed = common::create_test_feed("Limited", &format!("{}/feed.xml", server.url()));
+    let client = reqwest::Client::new();
+    let started = Instant::now();
+    let result = rt
+        .block_on(async { validate_feed_with(&feed, &client, &fast_policy()).await })
+        .unwrap();
+
+    assert_eq!(result.status, ValidationStatus::Valid);
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    limited.assert();
+    ok.assert();
+}
+
+#[test]
+fn test_retry_after_beyond_max_delay_gives_up() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/feed.xml")
+        .with_status(503)
+        .with_header("Retry-After", "3600")
+        .expect(1)
+        .create();
+
+    let feed = common::create_test_feed("Down", &format!("{}/feed.xml", server.url()));
+    let client = reqwest::Client::new();
+    let result = rt
+        .block_on(async { validate_feed_with(&feed, &client, &fast_policy()).await })
+        .unwrap();
+
+    assert_eq!(result.issue_kind(), Some(IssueKind::ServerError));
+    assert!(result.error.contains("503"));
+    mock.assert();
+}
+
+#[test]
+fn test_server_errors_retried_up_to_max_attempts() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let mock = server
+        .mock("GET", "/feed.xml")
+        .with_status(500)
+        .expect(3)
+        .create();
+
+    let feed = common::create_test_feed("Broken", &format!("{}/feed.xml", server.url()));
+    let client = reqwest::Client::new();
+    let result = rt
+        .block_on(async { validate_feed_with(&feed, &client, &fast_policy()).await })
+        .unwrap();
+
+    assert_eq!(result.status, ValidationStatus::Error);
+    assert_eq!(result.issue.as_ref().map(|i| i.retries), Some(2));
+    mock.assert();
+}
+
+#[test]
+fn test_retryable_issues_are_configurable() {
+    let rt = common::get_test_runtime();
+    let mut server = mockito::Server::new();
+    let not_found = server
+        .mock("GET", "/missing.xml")
+        .with_status(404)
+        .expect(2)
+        .create();
+    let limited = server
+        .mock("GET", "/limited.xml")
+        .with_status(429)
+        .expect(1)
+        .create();
+
+    let policy = RetryPolicy {
+        max_attempts: 2,
+        retry_on: vec![IssueKind::NotFound],
+        ..fast_policy()
+    };
+    let client = reqwest::Client::new();
+    let missing = common::create_test_feed("Missing", &format!("{}/missing.xml", server.url()));
+    let rate_limited =
+        common::create_test_feed("Limited", &format!("{}/limited.xml", server.url()));
+    rt.block_on(async {
+        validate_feed_with(&missing, &client, &policy)
+            .await
+            .unwrap();
+        validate_feed_with(&rate_limited, &client, &policy)
+            .await
+            .unwrap();
+    });
+
+    not_found.assert();
+    limited.assert();
+}
+
+#[test]
+fn test_policy_delays() {
+    let policy = fast_policy();
+    assert_eq!(
+        policy.delay(1, IssueKind::ServerError, None),
+        Some(Duration::from_millis(10))
+    );
+    assert_eq!(
+        policy.delay(2, IssueKind::ServerError, None),
+        Some(Duration::from_millis(20))
+    );
+    assert_eq!(policy.delay(3, IssueKind::ServerError, None), None);
+    assert_eq!(policy.delay(1, IssueKind::NotFound, None), None);
+    assert_eq!(
+        policy.delay(1, IssueKind::RateLimited, Some(Duration::from_secs(2))),
+        Some(Duration::from_secs(2))
+    );
+    assert_eq!(RetryPolicy::none().delay(1, IssueKind::Connect, None), None);
+
+    let jittered = RetryPolicy {
+        jitter: 0.5,
+        ..fast_policy()
+    };
+    for _ in 0..20 {
+        let delay = jittered.delay(1, IssueKind::Connect, None).unwrap();
+        assert!(delay > Duration::from_millis(5) && delay <= Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_parse_retry_after() {
+    let now: DateTime<Utc> = "2024-06-01T00:00:00Z".parse().unwrap();
+    assert_eq!(
+        parse_retry_after("120", now),
+        Some(Duration::from_secs(120))
+    );
+    assert_eq!(
+        parse_retry_after("Sat, 01 Jun 2024 00:01:30 GMT", now),
+        Some(Duration::from_secs(90))
+    );
+    assert_eq!(
+        parse_retry_after("Fri, 31 May 2024 23:00:00 GMT", now),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon", now), None);
+}
@@ -0,0 +1,75 @@
+use opml_manager::canonical::{canonicalize, Canonicalizer, UrlRule};
+use opml_manager::opml::parse_opml_with;
+use opml_manager::report::generate_summary;
+
+#[test]
+fn test_standard_equivalences() {
+    let none = Canonicalizer::new(&[]);
+    assert_eq!(
+        none.canonicalize("HTTP://Example.COM:80/Feed.xml#top"),
+        "http://example.com/Feed.xml"
+    );
+    assert_eq!(
+        none.canonicalize("https://example.com:443/feed"),
+        "https://example.com/feed"
+    );
+    assert!(none.same(
+        "http://bücher.example/feed",
+        "http://xn--bcher-kva.example/feed"
+    ));
+    assert!(!none.same("http://example.com/feed", "http://example.com/FEED"));
+}
+
+#[test]
+fn test_default_rules() {
+    assert_eq!(
+        canonicalize("http://www.example.com/feed/?b=2&utm_source=rss&a=1&fbclid=x"),
+        "https://example.com/feed?a=1&b=2"
+    );
+    assert_eq!(
+        canonicalize("http://example.com/feed?utm_medium=rss"),
+        "https://example.com/feed"
+    );
+    assert_eq!(canonicalize("not a url "), "not a url");
+}
+
+#[test]
+fn test_selected_rules() {
+    let scheme_only = Canonicalizer::new(&[UrlRule::Scheme]);
+    assert!(scheme_only.same("http://example.com/feed", "https://example.com/feed"));
+    assert!(!scheme_only.same("https://www.example.com/feed", "https://example.com/feed"));
+    assert!(!scheme_only.same("https://example.com/feed/", "https://example.com/feed"));
+    assert!(!scheme_only.same(
+        "https://example.com/feed?a=1&b=2",
+        "https://example.com/feed?b=2&a=1"
+    ));
+
+    let www = Canonicalizer::new(&[UrlRule::Www, UrlRule::Tracking]);
+    assert!(www.same(
+        "https://www.example.com/feed?utm_campaign=x",
+        "https://example.com/feed"
+    ));
+    assert!(!www.same("http://example.com/feed", "https://example.com/feed"));
+}
+
+#[test]
+fn test_parsing_and_analysis_agree() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+    <opml version="2.0">
+        <body>
+            <outline type="rss" text="Feed 1" xmlUrl="http://example.com/feed"/>
+            <outline type="rss" text="Feed 2" xmlUrl="https://www.example.com/feed/"/>
+        </body>
+    </opml>"#;
+
+    let strict = Canonicalizer::new(&[]);
+    let feeds = parse_opml_with(content, &strict).unwrap();
+    assert_eq!(feeds.len(), 2);
+
+    let (_, duplicates, _, _) = generate_summary(&feeds, &Canonicalizer::default());
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].title, "Feed 2");
+
+    let feeds = parse_opml_with(content, &Canonicalizer::default()).unwrap();
+    assert_eq!(feeds.len(), 1);
+}
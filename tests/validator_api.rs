@@ -0,0 +1,219 @@
+use chrono::TimeDelta;
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use opml_manager::fetcher::{FeedFetcher, FetchRequest, FetchResponse, FetchResult};
+use opml_manager::report::{
+    format_validation_markdown, format_validation_text, ValidationReport, EXIT_FEED_ERRORS,
+};
+use opml_manager::retry::RetryPolicy;
+use opml_manager::scheduler::Limits;
+use opml_manager::validation::{
+    IssueKind, ProgressObserver, ValidationResult, ValidationStatus, Validator,
+};
+use reqwest::StatusCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+mod common;
+
+const OLD_FEED: &str = r#"<rss version="2.0"><channel><title>Old</title>
+<item><pubDate>Mon, 01 Jan 2018 00:00:00 GMT</pubDate></item></channel></rss>"#;
+
+#[derive(Default)]
+struct Counter {
+    total: AtomicUsize,
+    finished: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+impl ProgressObserver for Counter {
+    fn started(&self, total: usize) {
+        self.total.store(total, Ordering::SeqCst);
+    }
+
+    fn finished(&self, result: &ValidationResult) {
+        self.finished.fetch_add(1, Ordering::SeqCst);
+        if result.status != ValidationStatus::Valid {
+            self.failed.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+fn server_with_feeds() -> mockito::ServerGuard {
+    let mut server = mockito::Server::new();
+    server
+        .mock("GET", "/good.xml")
+        .with_body(r#"<rss version="2.0"><channel/></rss>"#)
+        .create();
+    server.mock("GET", "/old.xml").with_body(OLD_FEED).create();
+    server.mock("GET", "/gone.xml").with_status(410).create();
+    server
+}
+
+#[test]
+fn test_validate_all_streams_every_feed() {
+    let rt = common::get_test_runtime();
+    let server = server_with_feeds();
+    let feeds: Vec<_> = ["good", "old", "gone", "good"]
+        .iter()
+        .map(|name| common::create_test_feed(name, &format!("{}/{}.xml", server.url(), name)))
+        .collect();
+
+    let counter = Arc::new(Counter::default());
+    let validator = Validator::builder()
+        .limits(Limits {
+            concurrency: 2,
+            ..Limits::default()
+        })
+        .retry_policy(RetryPolicy::none())
+        .observer(counter.clone())
+        .build()
+        .unwrap();
+
+    let mut results: Vec<_> = rt.block_on(async { validator.validate_all(&feeds).collect().await });
+    results.sort_by_key(|(index, _)| *index);
+
+    let indexes: Vec<_> = results.iter().map(|(index, _)| *index).collect();
+    assert_eq!(indexes, vec![0, 1, 2, 3]);
+    let statuses: Vec<_> = results.iter().map(|(_, result)| result.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ValidationStatus::Valid,
+            ValidationStatus::Valid,
+            ValidationStatus::Error,
+            ValidationStatus::Valid
+        ]
+    );
+
+    assert_eq!(counter.total.load(Ordering::SeqCst), 4);
+    assert_eq!(counter.finished.load(Ordering::SeqCst), 4);
+    assert_eq!(counter.failed.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_validator_settings() {
+    let rt = common::get_test_runtime();
+    let server = server_with_feeds();
+    let feed = common::create_test_feed("Old", &format!("{}/old.xml", server.url()));
+
+    let validator = Validator::builder()
+        .client(reqwest::Client::new())
+        .stale_after(Some(TimeDelta::days(365)))
+        .keep_documents(true)
+        .build()
+        .unwrap();
+    let result = rt.block_on(validator.validate(&feed)).unwrap();
+
+    assert_eq!(result.status, ValidationStatus::Stale);
+    assert_eq!(result.document.unwrap().body, OLD_FEED);
+
+    let plain = Validator::builder().build().unwrap();
+    let result = rt.block_on(plain.validate(&feed)).unwrap();
+    assert_eq!(result.status, ValidationStatus::Valid);
+    assert!(result.document.is_none());
+}
+
+#[test]
+fn test_validate_all_without_feeds() {
+    let rt = common::get_test_runtime();
+    let validator = Validator::builder().build().unwrap();
+
+    let results: Vec<_> = rt.block_on(async { validator.validate_all(&[]).collect().await });
+    assert!(results.is_empty());
+}
+
+/// Fails in every way a fetcher can fail: with a panic
+struct PanickingFetcher;
+
+impl FeedFetcher for PanickingFetcher {
+    fn fetch<'a>(&'a self, request: &'a FetchRequest) -> BoxFuture<'a, FetchResult> {
+        Box::pin(async move {
+            if request.url.path() == "/panic.xml" {
+                panic!("fetcher bug");
+            }
+            Ok(FetchResponse {
+                status: StatusCode::OK,
+                body: r#"<rss version="2.0"><channel/></rss>"#.to_string(),
+                ..FetchResponse::default()
+            })
+        })
+    }
+}
+
+#[test]
+fn test_no_feed_goes_missing() {
+    let rt = common::get_test_runtime();
+    let feeds: Vec<_> = ["one", "panic", "two"]
+        .iter()
+        .map(|name| common::create_test_feed(name, &format!("https://example.com/{}.xml", name)))
+        .collect();
+    let validator = Validator::builder()
+        .fetcher(PanickingFetcher)
+        .build()
+        .unwrap();
+
+    let mut results: Vec<_> = rt.block_on(async { validator.validate_all(&feeds).collect().await });
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<_> = results.into_iter().map(|(_, result)| result).collect();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1].feed, "panic");
+    assert_eq!(results[1].status, ValidationStatus::InternalError);
+    assert_eq!(results[1].issue_kind(), Some(IssueKind::Internal));
+    assert!(results[1]
+        .error
+        .starts_with("internal error: validation task failed"));
+    assert!(results[1].error.contains("fetcher bug"));
+
+    let report = ValidationReport::new("feeds.opml", feeds.len(), results);
+    assert_eq!(report.total_input, 3);
+    assert_eq!(report.total_checked, 2);
+    assert_eq!(report.total_failed, 1);
+    assert!(report.is_reconciled());
+    assert_eq!(report.exit_code(), EXIT_FEED_ERRORS);
+    let markdown = format_validation_markdown(&report);
+    assert!(markdown.contains("- Total feeds checked: 2\n- Failed: 1\n"));
+    assert!(markdown.contains("- Reconciliation: 3 input feeds = 2 validated + 1 failed\n"));
+    assert!(format_validation_text(&report)
+        .contains("Reconciliation: 3 input feeds = 2 validated + 1 failed\n"));
+    assert!(format_validation_markdown(&report).contains("## Internal error Feeds"));
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["results"][1]["status"], "internal-error");
+    assert_eq!(json["total_failed"], 1);
+}
+
+#[test]
+fn test_report_catches_missing_feeds() {
+    let feeds: Vec<_> = ["one", "two", "three"]
+        .iter()
+        .map(|name| common::create_test_feed(name, &format!("https://example.com/{}.xml", name)))
+        .collect();
+    let results = vec![ValidationResult {
+        status: ValidationStatus::Valid,
+        ..ValidationResult::cancelled(&feeds[0])
+    }];
+
+    let report = ValidationReport::new("feeds.opml", feeds.len(), results);
+    assert!(!report.is_reconciled());
+    assert_eq!(report.total_missing(), 2);
+    assert_eq!(
+        report.reconciliation(),
+        "3 input feeds = 1 validated + 0 failed + 2 missing"
+    );
+    assert_eq!(report.exit_code(), EXIT_FEED_ERRORS);
+}
+
+#[test]
+fn test_reconciliation_of_edited_reports() {
+    // Counts that do not add up must not make the summary panic
+    let report: ValidationReport = serde_json::from_str(
+        r#"{"source": "feeds.opml", "generated_at": "", "total_input": 1,
+            "total_checked": 0, "total_failed": 3, "status_counts": {}, "results": []}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        report.reconciliation(),
+        "1 input feeds = 0 validated + 3 failed + 1 missing"
+    );
+}